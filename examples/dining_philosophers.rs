@@ -0,0 +1,64 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use concurrent::lock::{Lock, TTASLock};
+
+mod support;
+
+use support::Watchdog;
+
+const PHILOSOPHERS: usize = 5;
+const MEALS: usize = 200;
+
+struct Fork {
+    lock: TTASLock,
+    in_use: AtomicBool,
+}
+
+impl Fork {
+    fn pick_up(&self) {
+        assert!(!self.in_use.swap(true, Ordering::Relaxed), "fork shared");
+    }
+    fn put_down(&self) {
+        self.in_use.store(false, Ordering::Relaxed);
+    }
+}
+
+fn main() {
+    let forks: Vec<Fork> = (0..PHILOSOPHERS)
+        .map(|_| Fork { lock: TTASLock::new(), in_use: AtomicBool::new(false) })
+        .collect();
+    let meals: Vec<AtomicUsize> = (0..PHILOSOPHERS).map(|_| AtomicUsize::new(0)).collect();
+    let watchdog = Watchdog::new(PHILOSOPHERS, Duration::from_secs(5));
+    thread::scope(|s| {
+        for philosopher in 0..PHILOSOPHERS {
+            let (forks, meals, watchdog) = (&forks, &meals, &watchdog);
+            s.spawn(move || {
+                let left = philosopher;
+                let right = (philosopher + 1) % PHILOSOPHERS;
+                // everyone picks up the lower-numbered fork first, so no
+                // cycle of philosophers each holding one fork can form
+                let (first, second) = (left.min(right), left.max(right));
+                for _ in 0..MEALS {
+                    let _first = forks[first].lock.acquire();
+                    let _second = forks[second].lock.acquire();
+                    forks[first].pick_up();
+                    forks[second].pick_up();
+                    meals[philosopher].fetch_add(1, Ordering::Relaxed);
+                    thread::yield_now();
+                    forks[second].put_down();
+                    forks[first].put_down();
+                    watchdog.beat(philosopher);
+                }
+                watchdog.finish(philosopher);
+            });
+        }
+        s.spawn(|| watchdog.run());
+    });
+    for (philosopher, meals) in meals.iter().enumerate() {
+        let meals = meals.load(Ordering::Relaxed);
+        assert_eq!(meals, MEALS);
+        println!("philosopher {} ate {} meals", philosopher, meals);
+    }
+}
@@ -0,0 +1,48 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Workers call beat after each unit of progress and finish when done;
+// run panics if any unfinished worker goes a whole stall period without
+// a beat, which catches both deadlock and starvation.
+pub struct Watchdog {
+    beats: Vec<AtomicUsize>,
+    finished: Vec<AtomicBool>,
+    stall: Duration,
+}
+
+impl Watchdog {
+    pub fn new(workers: usize, stall: Duration) -> Self {
+        Watchdog {
+            beats: (0..workers).map(|_| AtomicUsize::new(0)).collect(),
+            finished: (0..workers).map(|_| AtomicBool::new(false)).collect(),
+            stall,
+        }
+    }
+    pub fn beat(&self, worker: usize) {
+        self.beats[worker].fetch_add(1, Ordering::Relaxed);
+    }
+    pub fn finish(&self, worker: usize) {
+        self.finished[worker].store(true, Ordering::Release);
+    }
+    pub fn run(&self) {
+        let mut seen: Vec<(usize, Instant)> = self.beats.iter()
+            .map(|beats| (beats.load(Ordering::Relaxed), Instant::now()))
+            .collect();
+        loop {
+            thread::sleep(self.stall / 10);
+            let mut all_finished = true;
+            for (worker, (beats, since)) in seen.iter_mut().enumerate() {
+                if self.finished[worker].load(Ordering::Acquire) { continue; }
+                all_finished = false;
+                let now = self.beats[worker].load(Ordering::Relaxed);
+                if now != *beats {
+                    (*beats, *since) = (now, Instant::now());
+                } else if since.elapsed() > self.stall {
+                    panic!("worker {} made no progress for {:?}", worker, self.stall);
+                }
+            }
+            if all_finished { return; }
+        }
+    }
+}
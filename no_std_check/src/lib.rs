@@ -0,0 +1,58 @@
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use concurrent::hashset::StripedHashSet;
+use concurrent::listset::{
+    CoarseListSet, ConcurrentSet, FineListSet, LazyListSet, LockFreeListSet, MutSet,
+    RefCountListSet, RwListSet, SeqListSet, Set,
+};
+use concurrent::lock::{ArrayLock, CLHLock, Lock, Mutex, RwSpinLock, TASLock, TTASLock, TryLock};
+
+static RAW: lock_api::Mutex<TTASLock, u64> = lock_api::Mutex::new(0);
+
+pub fn bump() -> u64 {
+    let mut count = RAW.lock();
+    *count += 1;
+    *count
+}
+
+pub fn mutex(values: &[u32]) -> u32 {
+    let mutex = Mutex::new(Vec::new(), ArrayLock::new(2));
+    mutex.lock().extend_from_slice(values);
+    mutex.into_inner().iter().sum()
+}
+
+pub fn locks() -> bool {
+    let array = ArrayLock::new(4);
+    drop(array.acquire());
+    let clh = CLHLock::new();
+    drop(clh.acquire());
+    let tas = TASLock::new();
+    let _guard = tas.acquire();
+    let held = tas.try_acquire().is_none();
+    held
+}
+
+fn fill(set: &impl ConcurrentSet<u32>, n: u32) -> usize {
+    (0..n).filter(|&i| set.add(i)).count()
+}
+
+pub fn sets(n: u32) -> Vec<usize> {
+    let mut seq = SeqListSet::new();
+    for i in 0..n { seq.add(i); }
+    Vec::from([
+        seq.len(),
+        fill(&CoarseListSet::new(CLHLock::new()), n),
+        fill(&FineListSet::<u32, TTASLock>::new(), n),
+        fill(&LazyListSet::<u32, TASLock>::new(), n),
+        fill(&LockFreeListSet::new(), n),
+        fill(&RefCountListSet::new(TASLock::new()), n),
+        fill(&RwListSet::new(RwSpinLock::new()), n),
+        fill(&StripedHashSet::<u32, TASLock>::new(4), n),
+    ])
+}
+
+pub fn contains(set: &impl Set<u32>, n: u32) -> bool { set.contains(n) }
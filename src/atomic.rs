@@ -0,0 +1,295 @@
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use core::mem::{self, align_of};
+use core::ptr;
+use core::sync::atomic;
+
+use crate::hazard::HazardDomain;
+use crate::sync::{atomic::{AtomicPtr, AtomicUsize, Ordering}, const_fn, load_mut};
+
+// Two monotonic counters that can be read as a consistent pair: if two
+// consecutive collects agree, both values held at once at some point in
+// between, because neither counter ever goes back to an earlier value.
+#[derive(Debug, Default)]
+pub struct SnapshotPair {
+    adds: AtomicUsize,
+    removes: AtomicUsize,
+}
+
+impl SnapshotPair {
+    const_fn! {
+        pub fn new() -> Self {
+            SnapshotPair { adds: AtomicUsize::new(0), removes: AtomicUsize::new(0) }
+        }
+    }
+    pub fn record_add(&self) { self.adds.fetch_add(1, Ordering::SeqCst); }
+    pub fn record_remove(&self) { self.removes.fetch_add(1, Ordering::SeqCst); }
+    pub fn snapshot(&self) -> (usize, usize) {
+        let mut first = self.collect();
+        loop {
+            let second = self.collect();
+            if first == second { return first; }
+            first = second;
+        }
+    }
+    pub fn len(&self) -> usize {
+        let (adds, removes) = self.snapshot();
+        adds.wrapping_sub(removes)
+    }
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+    fn collect(&self) -> (usize, usize) {
+        let adds = self.adds.load(Ordering::SeqCst);
+        (adds, self.removes.load(Ordering::SeqCst))
+    }
+}
+
+// A pointer and a one-bit mark that are loaded, stored and compared
+// together. The mark lives in the low bit of the pointer, so the pointee
+// must be aligned to at least two bytes.
+#[derive(Debug)]
+pub struct AtomicMarkable<T> {
+    ptr: AtomicPtr<T>,
+}
+
+impl<T> AtomicMarkable<T> {
+    pub fn new(ptr: *mut T, mark: bool) -> Self {
+        debug_assert!(align_of::<T>() >= 2, "pointee alignment leaves no room for a mark");
+        AtomicMarkable { ptr: AtomicPtr::new(Self::pack(ptr, mark)) }
+    }
+    pub fn load(&self, order: Ordering) -> (*mut T, bool) {
+        Self::unpack(self.ptr.load(order))
+    }
+    pub fn store(&self, ptr: *mut T, mark: bool, order: Ordering) {
+        self.ptr.store(Self::pack(ptr, mark), order);
+    }
+    pub fn swap(&self, ptr: *mut T, mark: bool, order: Ordering) -> (*mut T, bool) {
+        Self::unpack(self.ptr.swap(Self::pack(ptr, mark), order))
+    }
+    pub fn compare_exchange(
+        &self, current: (*mut T, bool), new: (*mut T, bool),
+        success: Ordering, failure: Ordering,
+    ) -> Result<(*mut T, bool), (*mut T, bool)> {
+        self.ptr.compare_exchange(
+            Self::pack(current.0, current.1), Self::pack(new.0, new.1), success, failure
+        ).map(Self::unpack).map_err(Self::unpack)
+    }
+    pub fn compare_exchange_weak(
+        &self, current: (*mut T, bool), new: (*mut T, bool),
+        success: Ordering, failure: Ordering,
+    ) -> Result<(*mut T, bool), (*mut T, bool)> {
+        self.ptr.compare_exchange_weak(
+            Self::pack(current.0, current.1), Self::pack(new.0, new.1), success, failure
+        ).map(Self::unpack).map_err(Self::unpack)
+    }
+    pub fn get_mut(&mut self) -> (*mut T, bool) { Self::unpack(load_mut(&mut self.ptr)) }
+    pub fn into_inner(self) -> (*mut T, bool) { Self::unpack(self.ptr.into_inner()) }
+    fn pack(ptr: *mut T, mark: bool) -> *mut T {
+        debug_assert!(ptr.addr() & 1 == 0, "pointer is not aligned for a mark");
+        ptr.map_addr(|addr| addr | mark as usize)
+    }
+    fn unpack(ptr: *mut T) -> (*mut T, bool) {
+        (ptr.map_addr(|addr| addr & !1), ptr.addr() & 1 == 1)
+    }
+}
+
+// A pointer and a version stamp that change together, so a compare-exchange
+// against a pointer that was swapped out and back in again still fails.
+// The stamp takes the top 16 bits of the word, which 64-bit targets leave
+// unused in user-space addresses; it wraps after 65536 updates.
+#[cfg(target_pointer_width = "64")]
+#[derive(Debug)]
+pub struct AtomicStamped<T> {
+    ptr: AtomicPtr<T>,
+}
+
+#[cfg(target_pointer_width = "64")]
+impl<T> AtomicStamped<T> {
+    const STAMP_SHIFT: u32 = 48;
+    const ADDR_MASK: usize = (1 << Self::STAMP_SHIFT) - 1;
+
+    pub fn new(ptr: *mut T) -> Self {
+        AtomicStamped { ptr: AtomicPtr::new(Self::pack(ptr, 0)) }
+    }
+    pub fn load(&self, order: Ordering) -> (*mut T, u16) {
+        Self::unpack(self.ptr.load(order))
+    }
+    // every successful update bumps the stamp
+    pub fn swap(&self, ptr: *mut T, order: Ordering) -> (*mut T, u16) {
+        let mut current = self.ptr.load(Ordering::Relaxed);
+        loop {
+            let (_, stamp) = Self::unpack(current);
+            let new = Self::pack(ptr, stamp.wrapping_add(1));
+            match self.ptr.compare_exchange_weak(current, new, order, Ordering::Relaxed) {
+                Ok(old) => return Self::unpack(old),
+                Err(actual) => current = actual,
+            }
+        }
+    }
+    pub fn compare_exchange(
+        &self, current: *mut T, stamp: u16, new: *mut T,
+        success: Ordering, failure: Ordering,
+    ) -> Result<(*mut T, u16), (*mut T, u16)> {
+        self.ptr.compare_exchange(
+            Self::pack(current, stamp), Self::pack(new, stamp.wrapping_add(1)),
+            success, failure
+        ).map(Self::unpack).map_err(Self::unpack)
+    }
+    pub fn compare_exchange_weak(
+        &self, current: *mut T, stamp: u16, new: *mut T,
+        success: Ordering, failure: Ordering,
+    ) -> Result<(*mut T, u16), (*mut T, u16)> {
+        self.ptr.compare_exchange_weak(
+            Self::pack(current, stamp), Self::pack(new, stamp.wrapping_add(1)),
+            success, failure
+        ).map(Self::unpack).map_err(Self::unpack)
+    }
+    pub fn get_mut(&mut self) -> (*mut T, u16) { Self::unpack(load_mut(&mut self.ptr)) }
+    pub fn into_inner(self) -> (*mut T, u16) { Self::unpack(self.ptr.into_inner()) }
+    // a pointer already using the top bits (a 57-bit address, or a tagged
+    // heap pointer) would come back wrong, so this is checked in release too
+    fn pack(ptr: *mut T, stamp: u16) -> *mut T {
+        assert!(ptr.addr() & !Self::ADDR_MASK == 0, "pointer does not fit under a stamp");
+        ptr.map_addr(|addr| addr | (stamp as usize) << Self::STAMP_SHIFT)
+    }
+    fn unpack(ptr: *mut T) -> (*mut T, u16) {
+        let stamp = (ptr.addr() >> Self::STAMP_SHIFT) as u16;
+        (ptr.map_addr(|addr| addr & Self::ADDR_MASK), stamp)
+    }
+}
+
+// A box filled in at most once, by whichever thread gets there first.
+// Once a box is published it stays until the OnceBox is dropped, which is
+// what lets get hand out &T from &self; there is deliberately no take.
+pub struct OnceBox<T> {
+    ptr: AtomicPtr<T>,
+}
+
+// the value can be created on one thread and dropped on another
+unsafe impl<T: Send> Send for OnceBox<T> {}
+unsafe impl<T: Send + Sync> Sync for OnceBox<T> {}
+
+impl<T> OnceBox<T> {
+    const_fn! {
+        pub fn new() -> Self { OnceBox { ptr: AtomicPtr::new(ptr::null_mut()) } }
+    }
+    pub fn get(&self) -> Option<&T> {
+        unsafe { self.ptr.load(Ordering::Acquire).as_ref() }
+    }
+    // f may run on several threads at once; all but the first to finish
+    // have their value dropped
+    pub fn get_or_init(&self, f: impl FnOnce() -> T) -> &T {
+        if let Some(value) = self.get() { return value; }
+        match self.publish(Box::new(f())) {
+            Ok(value) => value,
+            Err((_, winner)) => winner,
+        }
+    }
+    pub fn set(&self, value: T) -> Result<(), T> {
+        self.publish(Box::new(value)).map(|_| ()).map_err(|(value, _)| *value)
+    }
+    pub fn get_mut(&mut self) -> Option<&mut T> { unsafe { load_mut(&mut self.ptr).as_mut() } }
+    pub fn into_inner(mut self) -> Option<T> {
+        let ptr = load_mut(&mut self.ptr);
+        mem::forget(self);
+        (!ptr.is_null()).then(|| *unsafe { Box::from_raw(ptr) })
+    }
+    fn publish(&self, value: Box<T>) -> Result<&T, (Box<T>, &T)> {
+        let new = Box::into_raw(value);
+        match self.ptr.compare_exchange(
+            ptr::null_mut(), new, Ordering::AcqRel, Ordering::Acquire
+        ) {
+            Ok(_) => Ok(unsafe { &*new }),
+            Err(winner) => Err((unsafe { Box::from_raw(new) }, unsafe { &*winner })),
+        }
+    }
+}
+
+impl<T> Default for OnceBox<T> {
+    fn default() -> Self { Self::new() }
+}
+
+impl<T> Drop for OnceBox<T> {
+    fn drop(&mut self) {
+        let ptr = load_mut(&mut self.ptr);
+        if !ptr.is_null() { unsafe { drop(Box::from_raw(ptr)); } }
+    }
+}
+
+// An Arc that can be swapped out while other threads clone it. A reader
+// announces the pointer in the hazard domain before it bumps the count,
+// so a replaced Arc is only released once nobody is between reading the
+// pointer and owning a count. The hazard pointers are plain core atomics,
+// like the rest of the domain.
+pub struct AtomicArc<T> {
+    ptr: atomic::AtomicPtr<T>,
+    domain: HazardDomain,
+}
+
+unsafe impl<T: Send + Sync> Send for AtomicArc<T> {}
+unsafe impl<T: Send + Sync> Sync for AtomicArc<T> {}
+
+unsafe fn drop_arc<T>(ptr: *mut ()) { drop(unsafe { Arc::from_raw(ptr as *const T) }); }
+
+impl<T: Send + Sync + 'static> AtomicArc<T> {
+    pub fn new(value: Arc<T>) -> Self {
+        let ptr = atomic::AtomicPtr::new(Arc::into_raw(value) as *mut T);
+        AtomicArc { ptr, domain: HazardDomain::new() }
+    }
+    // registers with the domain on every call, which reuses an idle record
+    // once the threads have each had one
+    pub fn load_clone(&self) -> Arc<T> {
+        let handle = self.domain.register();
+        let protected = handle.protect(&self.ptr);
+        unsafe {
+            Arc::increment_strong_count(protected.as_ptr());
+            Arc::from_raw(protected.as_ptr())
+        }
+    }
+    pub fn store(&self, value: Arc<T>) {
+        let old = self.ptr.swap(Arc::into_raw(value) as *mut T, atomic::Ordering::AcqRel);
+        self.retire(old);
+    }
+    // the replaced Arc comes back with a count of its own; the one the
+    // atomic held is retired like in store
+    pub fn swap(&self, value: Arc<T>) -> Arc<T> {
+        let old = self.ptr.swap(Arc::into_raw(value) as *mut T, atomic::Ordering::AcqRel);
+        let replaced = unsafe {
+            Arc::increment_strong_count(old);
+            Arc::from_raw(old)
+        };
+        self.retire(old);
+        replaced
+    }
+    // Installs new if the atomic still holds current, or hands new back.
+    // The caller's count on current keeps its address from being reused,
+    // so a match can't be a different Arc at the same place.
+    pub fn compare_exchange(&self, current: &Arc<T>, new: Arc<T>) -> Result<(), Arc<T>> {
+        let current = Arc::as_ptr(current) as *mut T;
+        let new = Arc::into_raw(new) as *mut T;
+        match self.ptr.compare_exchange(
+            current, new, atomic::Ordering::AcqRel, atomic::Ordering::Acquire
+        ) {
+            Ok(old) => {
+                self.retire(old);
+                Ok(())
+            },
+            Err(_) => Err(unsafe { Arc::from_raw(new) }),
+        }
+    }
+    fn retire(&self, old: *mut T) {
+        let handle = self.domain.register();
+        unsafe { handle.retire_with(old as *mut (), drop_arc::<T>); }
+    }
+    pub fn into_inner(mut self) -> Arc<T> {
+        let ptr = mem::replace(self.ptr.get_mut(), ptr::null_mut());
+        unsafe { Arc::from_raw(ptr) }
+    }
+}
+
+impl<T> Drop for AtomicArc<T> {
+    // the domain's own drop releases whatever is still retired
+    fn drop(&mut self) {
+        let ptr = *self.ptr.get_mut();
+        if !ptr.is_null() { unsafe { drop(Arc::from_raw(ptr)); } }
+    }
+}
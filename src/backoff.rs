@@ -1,23 +1,156 @@
+use core::{cmp::min, time::Duration};
+#[cfg(feature = "std")]
 use rand::random;
-use std::{time::Duration, cmp::min, thread::sleep};
 
-pub struct Backoff {
+#[cfg(feature = "std")]
+use crate::clock::Clock;
+use crate::sync::{spin_loop, yield_now};
+
+#[cfg(feature = "std")]
+pub(crate) struct Backoff<'a, C: Clock> {
     limit: Duration,
-    max_limit: Duration
+    max_limit: Duration,
+    clock: &'a C,
 }
 
-impl Backoff {
-    pub fn new(min: Duration, max: Duration) -> Self {
-        Backoff { limit: min, max_limit: max }
+#[cfg(feature = "std")]
+impl<'a, C: Clock> Backoff<'a, C> {
+    pub fn new_with_clock(min: Duration, max: Duration, clock: &'a C) -> Self {
+        Backoff { limit: min, max_limit: max, clock }
     }
     pub fn backoff(&mut self) {
         let delay = random_duration(self.limit);
         self.limit = min(2 * self.limit, self.max_limit);
-        sleep(delay);
+        self.clock.sleep(delay);
     }
 }
 
+#[cfg(feature = "std")]
 fn random_duration(limit: Duration) -> Duration {
     let nanos = random::<u64>() % limit.as_nanos() as u64;
     Duration::from_nanos(nanos)
 }
+
+// what a SpinBackoff does once spinning and yielding haven't been enough
+pub trait Sleeper {
+    fn sleep(&self, duration: Duration);
+}
+
+#[cfg(feature = "std")]
+impl<C: Clock> Sleeper for C {
+    fn sleep(&self, duration: Duration) { Clock::sleep(self, duration) }
+}
+
+// without an OS to sleep on, sleeping means going on yielding
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ThreadSleeper;
+
+impl Sleeper for ThreadSleeper {
+    fn sleep(&self, _duration: Duration) {
+        #[cfg(all(feature = "std", not(loom)))]
+        std::thread::sleep(_duration);
+        #[cfg(any(not(feature = "std"), loom))]
+        yield_now();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Spin,
+    Yield,
+    Sleep,
+}
+
+// Waits that get dearer the longer they go on: the first steps spin,
+// doubling the spins each time, the next ones yield, and after that
+// backoff sleeps, doubling the sleep up to a cap. The defaults spin 1023
+// times in all before the first yield.
+pub struct SpinBackoff<S: Sleeper = ThreadSleeper> {
+    step: u32,
+    spin_limit: u32,
+    yield_limit: u32,
+    min_sleep: Duration,
+    max_sleep: Duration,
+    sleeper: S,
+}
+
+const SPIN_LIMIT: u32 = 10;
+const YIELD_LIMIT: u32 = 20;
+const MIN_SLEEP: Duration = Duration::from_micros(10);
+const MAX_SLEEP: Duration = Duration::from_millis(1);
+
+impl SpinBackoff {
+    pub const fn new() -> Self { SpinBackoff::with_sleeper(ThreadSleeper) }
+}
+
+impl Default for SpinBackoff {
+    fn default() -> Self { Self::new() }
+}
+
+impl<S: Sleeper> SpinBackoff<S> {
+    pub const fn with_sleeper(sleeper: S) -> Self {
+        SpinBackoff {
+            step: 0,
+            spin_limit: SPIN_LIMIT,
+            yield_limit: YIELD_LIMIT,
+            min_sleep: MIN_SLEEP,
+            max_sleep: MAX_SLEEP,
+            sleeper,
+        }
+    }
+    // spins for the first spin_limit steps and yields until yield_limit
+    pub fn with_limits(mut self, spin_limit: u32, yield_limit: u32) -> Self {
+        assert!(spin_limit <= yield_limit, "spin limit is past the yield limit");
+        self.spin_limit = spin_limit;
+        self.yield_limit = yield_limit;
+        self
+    }
+    pub fn with_sleeps(mut self, min: Duration, max: Duration) -> Self {
+        assert!(min <= max, "minimum sleep is longer than the maximum");
+        self.min_sleep = min;
+        self.max_sleep = max;
+        self
+    }
+    pub fn reset(&mut self) { self.step = 0; }
+    pub fn phase(&self) -> Phase {
+        if self.step < self.spin_limit { Phase::Spin }
+        else if self.step < self.yield_limit { Phase::Yield }
+        else { Phase::Sleep }
+    }
+    // past spinning and yielding: a caller that has a way to block until
+    // it is woken should switch to it now
+    pub fn is_completed(&self) -> bool { self.phase() == Phase::Sleep }
+    pub fn backoff(&mut self) {
+        match self.phase() {
+            Phase::Spin => self.spin(),
+            Phase::Yield => yield_now(),
+            Phase::Sleep => self.sleeper.sleep(self.sleep_at(self.step)),
+        }
+        self.step = self.step.saturating_add(1);
+    }
+    // for waits that must not sleep: goes on yielding where backoff would
+    // start sleeping
+    pub fn snooze(&mut self) {
+        match self.phase() {
+            Phase::Spin => self.spin(),
+            Phase::Yield | Phase::Sleep => yield_now(),
+        }
+        self.step = min(self.step + 1, self.yield_limit);
+    }
+    // how long the last backoff slept, if it got as far as sleeping
+    pub fn last_sleep(&self) -> Option<Duration> {
+        (self.step > self.yield_limit).then(|| self.sleep_at(self.step - 1))
+    }
+    fn sleep_at(&self, step: u32) -> Duration {
+        let doublings = min(step - self.yield_limit, 31);
+        min(self.min_sleep.saturating_mul(1 << doublings), self.max_sleep)
+    }
+    // under loom every spin_loop is a yield, and a scheduling point of its
+    // own, so one per step keeps a model's branches in check
+    #[cfg(not(loom))]
+    fn spin(&self) {
+        for _ in 0..1u32 << min(self.step, 31) { spin_loop(); }
+    }
+    #[cfg(loom)]
+    fn spin(&self) { spin_loop(); }
+}
@@ -0,0 +1,101 @@
+// Cycles through the locks, sets and queues with chaos pauses enabled until
+// the given number of seconds (default 10) has passed. Rerun a failure with
+// CHAOS_SEED set to the seed it prints.
+
+use std::panic;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use concurrent::chaos;
+use concurrent::hashset::StripedHashSet;
+use concurrent::listset::{
+    CoarseListSet, FineListSet, LazyListSet, LockFreeListSet, RefCountListSet, RwListSet,
+    SkipListSet, StdSet,
+};
+use concurrent::lock::{ArrayLock, BackoffLock, CLHLock, Lock, RwSpinLock, TASLock, TTASLock};
+use concurrent::queue::MsQueue;
+use concurrent::testing::linearizability::check_set;
+
+const THREADS: usize = 4;
+const ACQUIRES: usize = 200;
+
+fn check_exclusion<L: Lock>(name: &str, lock: L) {
+    let inside = AtomicBool::new(false);
+    let entered = AtomicUsize::new(0);
+    thread::scope(|s| for _ in 0..THREADS {
+        let (lock, inside, entered) = (&lock, &inside, &entered);
+        s.spawn(move || for _ in 0..ACQUIRES {
+            let _guard = lock.acquire();
+            assert!(!inside.swap(true, Ordering::Relaxed), "{}: two holders at once", name);
+            entered.fetch_add(1, Ordering::Relaxed);
+            inside.store(false, Ordering::Relaxed);
+        });
+    });
+    assert_eq!(entered.into_inner(), THREADS * ACQUIRES, "{}: lost acquires", name);
+}
+
+// per-producer order has to survive any interleaving of enqueues and
+// dequeues
+fn check_fifo() {
+    let queue = MsQueue::new();
+    thread::scope(|s| {
+        for producer in 0..THREADS / 2 {
+            let queue = &queue;
+            s.spawn(move || for i in 0..ACQUIRES { queue.enqueue((producer, i)); });
+        }
+        for _ in THREADS / 2..THREADS {
+            let queue = &queue;
+            s.spawn(move || {
+                let mut last = [None; THREADS / 2];
+                for _ in 0..ACQUIRES {
+                    if let Some((producer, i)) = queue.dequeue() {
+                        assert!(last[producer] < Some(i), "MsQueue: out of order");
+                        last[producer] = Some(i);
+                    }
+                }
+            });
+        }
+    });
+}
+
+fn soak_round() {
+    check_exclusion("TASLock", TASLock::new());
+    check_exclusion("TTASLock", TTASLock::new());
+    check_exclusion("BackoffLock", BackoffLock::new());
+    check_exclusion("ArrayLock", ArrayLock::new(THREADS));
+    check_exclusion("CLHLock", CLHLock::new());
+    check_fifo();
+    check_set(|| CoarseListSet::new(CLHLock::new()), THREADS, 4, 10);
+    check_set(|| CoarseListSet::new(TTASLock::new()).yield_every(1), THREADS, 4, 10);
+    check_set(|| CoarseListSet::new(CLHLock::new()).combining(), THREADS, 4, 10);
+    check_set(FineListSet::<u64, CLHLock>::new, THREADS, 4, 10);
+    check_set(LazyListSet::<u64, TTASLock>::new, THREADS, 4, 10);
+    check_set(LockFreeListSet::<u64>::new, THREADS, 4, 10);
+    check_set(|| RefCountListSet::new(ArrayLock::new(THREADS)), THREADS, 4, 10);
+    check_set(|| RwListSet::new(RwSpinLock::new()), THREADS, 4, 10);
+    check_set(|| SkipListSet::new(CLHLock::new(), 4), THREADS, 4, 10);
+    check_set(|| StripedHashSet::<u64, TTASLock>::new(2), THREADS, 4, 10);
+    check_set(|| StdSet::new(TTASLock::new()), THREADS, 4, 10);
+}
+
+fn main() {
+    let seconds = std::env::args().nth(1)
+        .map(|arg| arg.parse().expect("duration must be a number of seconds"))
+        .unwrap_or(10);
+    let seed = chaos::seed();
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        eprintln!("soak failed; reproduce with CHAOS_SEED={}", seed);
+        std::process::exit(1);
+    }));
+    println!("soaking for {}s with CHAOS_SEED={}", seconds, seed);
+    let deadline = Instant::now() + Duration::from_secs(seconds);
+    let mut rounds = 0;
+    while Instant::now() < deadline {
+        soak_round();
+        rounds += 1;
+    }
+    println!("{} rounds passed", rounds);
+}
@@ -0,0 +1,14 @@
+use alloc::alloc::{alloc, Layout};
+use alloc::boxed::Box;
+
+// gives the value back if the allocation fails
+pub fn try_new<T>(value: T) -> Result<Box<T>, T> {
+    let layout = Layout::new::<T>();
+    if layout.size() == 0 { return Ok(Box::new(value)); }
+    let ptr = unsafe { alloc(layout) } as *mut T;
+    if ptr.is_null() { return Err(value); }
+    unsafe {
+        ptr.write(value);
+        Ok(Box::from_raw(ptr))
+    }
+}
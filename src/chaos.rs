@@ -0,0 +1,76 @@
+// Randomized pause points at the windows where a badly timed preemption
+// would expose a bug. Without the chaos feature maybe_pause is empty and
+// inlines away. With it, CHAOS_SEED picks the seed (a random one is used
+// otherwise) so a failing run can be repeated; each thread draws from its
+// own stream derived from the seed and the order threads first pause in.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Site {
+    TtasSwap,
+    ArrayRelease,
+    ClhEnqueue,
+    ClhRelease,
+    MsQueueLink,
+}
+
+#[cfg(not(feature = "chaos"))]
+#[inline(always)]
+pub fn maybe_pause(_site: Site) {}
+
+#[cfg(feature = "chaos")]
+pub use enabled::{maybe_pause, seed};
+
+#[cfg(feature = "chaos")]
+mod enabled {
+    use std::cell::Cell;
+    use std::hint::spin_loop;
+    use std::sync::OnceLock;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::thread;
+    use std::time::Duration;
+
+    use super::Site;
+
+    static SEED: OnceLock<u64> = OnceLock::new();
+    static STREAMS: AtomicU64 = AtomicU64::new(0);
+
+    thread_local! {
+        static STATE: Cell<u64> = const { Cell::new(0) };
+    }
+
+    pub fn seed() -> u64 {
+        *SEED.get_or_init(|| match std::env::var("CHAOS_SEED") {
+            Ok(seed) => seed.parse().expect("CHAOS_SEED must be a u64"),
+            Err(_) => rand::random(),
+        })
+    }
+
+    // splitmix64 for the stream seeds, xorshift64* within a stream
+    fn next() -> u64 {
+        STATE.with(|state| {
+            let mut x = state.get();
+            if x == 0 {
+                let stream = STREAMS.fetch_add(1, Ordering::Relaxed);
+                let mut z = seed().wrapping_add(stream.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+                z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+                z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+                x = (z ^ (z >> 31)) | 1;
+            }
+            x ^= x >> 12;
+            x ^= x << 25;
+            x ^= x >> 27;
+            state.set(x);
+            x.wrapping_mul(0x2545_f491_4f6c_dd1d)
+        })
+    }
+
+    pub fn maybe_pause(_site: Site) {
+        let bits = next();
+        if bits & 7 != 0 { return; }
+        match (bits >> 8) % 3 {
+            0 => thread::yield_now(),
+            1 => for _ in 0..(bits >> 16) % 1024 { spin_loop(); },
+            _ => thread::sleep(Duration::from_micros((bits >> 16) % 50)),
+        }
+    }
+}
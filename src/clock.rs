@@ -0,0 +1,59 @@
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub trait Clock: Sync {
+    fn now(&self) -> Instant;
+    fn sleep(&self, duration: Duration);
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> Instant { (**self).now() }
+    fn sleep(&self, duration: Duration) { (**self).sleep(duration) }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RealClock;
+
+impl Clock for RealClock {
+    fn now(&self) -> Instant { Instant::now() }
+    fn sleep(&self, duration: Duration) { thread::sleep(duration) }
+}
+
+// time only moves when advance is called; sleepers block until it has
+// moved far enough
+pub struct TestClock {
+    start: Instant,
+    elapsed: Mutex<Duration>,
+    advanced: Condvar,
+}
+
+impl TestClock {
+    pub fn new() -> Self {
+        TestClock {
+            start: Instant::now(),
+            elapsed: Mutex::new(Duration::ZERO),
+            advanced: Condvar::new(),
+        }
+    }
+    pub fn elapsed(&self) -> Duration { *self.elapsed.lock().unwrap() }
+    pub fn advance(&self, by: Duration) {
+        *self.elapsed.lock().unwrap() += by;
+        self.advanced.notify_all();
+    }
+}
+
+impl Default for TestClock {
+    fn default() -> Self { Self::new() }
+}
+
+impl Clock for TestClock {
+    fn now(&self) -> Instant { self.start + self.elapsed() }
+    fn sleep(&self, duration: Duration) {
+        let mut elapsed = self.elapsed.lock().unwrap();
+        let deadline = *elapsed + duration;
+        while *elapsed < deadline {
+            elapsed = self.advanced.wait(elapsed).unwrap();
+        }
+    }
+}
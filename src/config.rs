@@ -0,0 +1,64 @@
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cell::UnsafeCell;
+
+use crate::atomic::AtomicArc;
+use crate::lock::Lock;
+
+type Callback<T> = dyn Fn(&Arc<T>) + Send + Sync;
+
+// Readers clone the current version out of an AtomicArc and keep it for
+// as long as they like, while writers build the next one on the side and
+// swap it in. The lock only guards the list of callbacks.
+pub struct Rcu<T, L: Lock> {
+    current: AtomicArc<T>,
+    callbacks: UnsafeCell<Vec<Arc<Callback<T>>>>,
+    lock: L,
+}
+
+unsafe impl<T: Send + Sync, L: Lock> Sync for Rcu<T, L> {}
+
+impl<T: Send + Sync + 'static, L: Lock> Rcu<T, L> {
+    pub fn new(value: T, lock: L) -> Self {
+        Rcu {
+            current: AtomicArc::new(Arc::new(value)),
+            callbacks: UnsafeCell::new(Vec::new()),
+            lock,
+        }
+    }
+    pub fn read(&self) -> Arc<T> { self.current.load_clone() }
+    // returns the version that was replaced
+    pub fn replace(&self, value: T) -> Arc<T> {
+        let new = Arc::new(value);
+        let old = self.current.swap(new.clone());
+        self.notify(&new);
+        old
+    }
+    // f may run several times if other updates get in first; returns the
+    // version this update installed
+    pub fn update(&self, f: impl Fn(&T) -> T) -> Arc<T> {
+        loop {
+            // holding old keeps its allocation alive, so the exchange
+            // cannot be fooled by a new version reusing the address
+            let old = self.read();
+            let new = Arc::new(f(&old));
+            if self.current.compare_exchange(&old, new.clone()).is_ok() {
+                self.notify(&new);
+                return new;
+            }
+        }
+    }
+    // callbacks run on the writer's thread after its swap, with no lock
+    // held; concurrent writers may deliver their versions out of order
+    pub fn on_change(&self, callback: impl Fn(&Arc<T>) + Send + Sync + 'static) {
+        let _guard = self.lock.acquire();
+        unsafe { &mut *self.callbacks.get() }.push(Arc::new(callback));
+    }
+    fn notify(&self, value: &Arc<T>) {
+        let callbacks = {
+            let _guard = self.lock.acquire();
+            unsafe { &*self.callbacks.get() }.clone()
+        };
+        for callback in callbacks { callback(value); }
+    }
+}
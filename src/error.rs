@@ -0,0 +1,92 @@
+use core::{error::Error, fmt};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BorrowError {
+    ThreadCapacityExceeded,
+    Timeout,
+}
+
+impl fmt::Display for BorrowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BorrowError::ThreadCapacityExceeded => {
+                write!(f, "too many threads trying to acquire the lock")
+            },
+            BorrowError::Timeout => write!(f, "timed out waiting for room in the lock"),
+        }
+    }
+}
+
+impl Error for BorrowError {}
+
+// a timed acquire gave up before the lock came free
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimedOut;
+
+impl fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "timed out waiting for the lock")
+    }
+}
+
+impl Error for TimedOut {}
+
+// the node for an element could not be allocated; the element is handed
+// back rather than dropped
+#[derive(Clone, PartialEq, Eq)]
+pub struct InsertError<T> {
+    element: T,
+}
+
+impl<T> InsertError<T> {
+    pub fn new(element: T) -> Self { InsertError { element } }
+    pub fn element(&self) -> &T { &self.element }
+    pub fn into_inner(self) -> T { self.element }
+}
+
+// like std's SendError, doesn't ask T to be Debug
+impl<T> fmt::Debug for InsertError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InsertError").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for InsertError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "memory allocation failed")
+    }
+}
+
+impl<T> Error for InsertError<T> {}
+
+// the other end of a channel is gone: the sender was dropped without
+// sending
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Closed;
+
+impl fmt::Display for Closed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "channel closed: sender dropped without sending")
+    }
+}
+
+impl Error for Closed {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    Empty,
+    Disconnected,
+}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryRecvError::Empty => write!(f, "nothing has been sent yet"),
+            TryRecvError::Disconnected => {
+                write!(f, "sender dropped without sending, or the value was already received")
+            },
+        }
+    }
+}
+
+impl Error for TryRecvError {}
@@ -1,11 +1,16 @@
-use std::hash::{Hash, Hasher};
+use core::hash::{Hash, Hasher};
 
 pub trait Hashable { fn hash(&self) -> u64; }
 
 impl<H: Hash> Hashable for H {
     fn hash(&self) -> u64 {
-        use std::collections::hash_map::DefaultHasher;
-        let mut hasher = DefaultHasher::new();
+        #[cfg(feature = "std")]
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        // core only has the older SipHash-2-4; keys only ever have to agree
+        // within one build, so either does
+        #[cfg(not(feature = "std"))]
+        #[allow(deprecated)]
+        let mut hasher = core::hash::SipHasher::new();
         self.hash(&mut hasher);
         hasher.finish()
     }
@@ -22,6 +27,7 @@ impl<T: Hash> Hashed<T> {
         Hashed { item, key }
     }
     pub fn get(self) -> T { self.item }
+    pub fn item(&self) -> &T { &self.item }
 }
 
 impl<T: Hash> Hashable for Hashed<T> {
@@ -0,0 +1,155 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cell::UnsafeCell;
+use core::hash::Hash;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(feature = "rayon")]
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::{hash::Hashable, listset::{ConcurrentSet, MutSet, SeqListSet, Set}, lock::Lock};
+
+#[cfg(feature = "std")]
+mod expiring;
+
+#[cfg(feature = "std")]
+pub use expiring::ExpiringHashSet;
+
+// resize once the average bucket holds more than this many elements
+const MAX_LOAD: usize = 4;
+
+// Each bucket is guarded by the stripe at the same index modulo the stripe
+// count. The table starts with one bucket per stripe and only ever doubles,
+// so hash % buckets and hash % stripes always agree on which stripe covers
+// a key, and holding that stripe means the table cannot be resized under us.
+pub struct StripedHashSet<T: Hash, L: Lock> {
+    table: UnsafeCell<Vec<UnsafeCell<SeqListSet<T>>>>,
+    locks: Box<[L]>,
+    size: AtomicUsize,
+}
+
+unsafe impl<T: Hash + Send, L: Lock + Send> Send for StripedHashSet<T, L> {}
+unsafe impl<T: Hash + Send, L: Lock> Sync for StripedHashSet<T, L> {}
+
+impl<T: Hash, L: Lock + Default> StripedHashSet<T, L> {
+    pub fn new(stripes: usize) -> Self {
+        assert!(stripes > 0, "StripedHashSet needs at least one stripe");
+        StripedHashSet {
+            table: UnsafeCell::new(Self::empty_table(stripes)),
+            locks: (0..stripes).map(|_| L::default()).collect(),
+            size: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl<T: Hash, L: Lock> StripedHashSet<T, L> {
+    // approximate while other threads are adding or removing
+    pub fn len(&self) -> usize { self.size.load(Ordering::Relaxed) }
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+    pub fn stripes(&self) -> usize { self.locks.len() }
+    pub fn buckets(&self) -> usize {
+        let _guards = self.acquire_all();
+        unsafe { &*self.table.get() }.len()
+    }
+    fn empty_table(buckets: usize) -> Vec<UnsafeCell<SeqListSet<T>>> {
+        (0..buckets).map(|_| UnsafeCell::new(SeqListSet::new())).collect()
+    }
+    // the bucket for key, with its stripe held; the table length is only
+    // read once the stripe is held, so it is never stale
+    fn bucket(&self, key: u64) -> (L::Guard<'_>, *mut SeqListSet<T>, usize) {
+        let guard = self.locks[(key % self.locks.len() as u64) as usize].acquire();
+        let table = unsafe { &*self.table.get() };
+        let bucket = table[(key % table.len() as u64) as usize].get();
+        (guard, bucket, table.len())
+    }
+    // runs f on the bucket for key with its stripe held, then counts
+    // whatever f added or removed, resizing if the set grew too full
+    fn with_bucket<R>(&self, key: u64, f: impl FnOnce(&mut SeqListSet<T>) -> R) -> R {
+        let (guard, bucket, buckets) = self.bucket(key);
+        let bucket = unsafe { &mut *bucket };
+        let before = bucket.len();
+        let result = f(bucket);
+        let after = bucket.len();
+        if after <= before {
+            self.size.fetch_sub(before - after, Ordering::Relaxed);
+            return result;
+        }
+        let size = self.size.fetch_add(after - before, Ordering::Relaxed) + after - before;
+        drop(guard);
+        if size / buckets > MAX_LOAD { self.resize(buckets); }
+        result
+    }
+    // the buckets stripe covers, with the stripe held
+    #[cfg(feature = "std")]
+    fn stripe(&self, stripe: usize) -> (L::Guard<'_>, Vec<*mut SeqListSet<T>>) {
+        let guard = self.locks[stripe].acquire();
+        let table = unsafe { &*self.table.get() };
+        let buckets = table.iter().skip(stripe).step_by(self.locks.len()).map(UnsafeCell::get);
+        (guard, buckets.collect())
+    }
+    fn acquire_all(&self) -> Vec<L::Guard<'_>> {
+        self.locks.iter().map(Lock::acquire).collect()
+    }
+    fn resize(&self, old_len: usize) {
+        let _guards = self.acquire_all();
+        let table = unsafe { &mut *self.table.get() };
+        // someone else got here first
+        if table.len() != old_len { return; }
+        let old = core::mem::replace(table, Self::empty_table(old_len * 2));
+        for bucket in old {
+            for item in bucket.into_inner() {
+                let key = Hashable::hash(&item);
+                let index = (key % table.len() as u64) as usize;
+                table[index].get_mut().add(item);
+            }
+        }
+    }
+}
+
+// Each worker holds one stripe at a time and goes through every bucket it
+// covers before letting go, so writers only wait on the stripe being
+// visited. No worker holds two stripes, so there is no order to get wrong
+// against a resize taking all of them; a resize runs between stripes and
+// never moves an element to another stripe, so each one is visited once.
+#[cfg(feature = "rayon")]
+impl<T: Hash + Send + Sync, L: Lock> StripedHashSet<T, L> {
+    pub fn par_for_each(&self, f: impl Fn(&T) + Sync) {
+        (0..self.stripes()).into_par_iter().for_each(|stripe| {
+            self.for_each_bucket(stripe, |bucket| bucket.iter().for_each(&f));
+        });
+    }
+    pub fn par_drain_filter(&self, pred: impl Fn(&T) -> bool + Sync) -> Vec<T> {
+        (0..self.stripes()).into_par_iter().flat_map_iter(|stripe| {
+            let mut drained = Vec::new();
+            self.for_each_bucket(stripe, |bucket| drained.append(&mut bucket.drain_filter(&pred)));
+            self.size.fetch_sub(drained.len(), Ordering::Relaxed);
+            drained
+        }).collect()
+    }
+    // calls f on each bucket covered by stripe, with the stripe held
+    fn for_each_bucket(&self, stripe: usize, mut f: impl FnMut(&mut SeqListSet<T>)) {
+        let (_guard, buckets) = self.stripe(stripe);
+        for bucket in buckets { f(unsafe { &mut *bucket }); }
+    }
+}
+
+impl<T: Hash, L: Lock> Set<T> for StripedHashSet<T, L> {
+    fn contains(&self, element: T) -> bool {
+        let (_guard, bucket, _) = self.bucket(Hashable::hash(&element));
+        unsafe { &*bucket }.contains(element)
+    }
+}
+
+impl<T: Hash, L: Lock> ConcurrentSet<T> for StripedHashSet<T, L> {
+    fn add(&self, element: T) -> bool {
+        self.with_bucket(Hashable::hash(&element), |bucket| bucket.add(element))
+    }
+    fn remove(&self, element: T) -> bool {
+        self.with_bucket(Hashable::hash(&element), |bucket| bucket.remove(element))
+    }
+}
+
+impl<T: Hash, L: Lock> MutSet<T> for StripedHashSet<T, L> {
+    fn add(&mut self, element: T) -> bool { ConcurrentSet::add(&*self, element) }
+    fn remove(&mut self, element: T) -> bool { ConcurrentSet::remove(&*self, element) }
+}
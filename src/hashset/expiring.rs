@@ -0,0 +1,82 @@
+use std::hash::Hash;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use crate::{clock::{Clock, RealClock}, hash::Hashable, listset::{ConcurrentSet, Expiring, Set}};
+use crate::lock::Lock;
+
+use super::StripedHashSet;
+
+// ExpiringSet over a StripedHashSet: an expired entry is unlinked under
+// its stripe when an operation runs into it, or when sweep gets to it.
+pub struct ExpiringHashSet<T: Hash, L: Lock, C: Clock = RealClock> {
+    set: StripedHashSet<Expiring<T>, L>,
+    clock: C,
+}
+
+impl<T: Hash, L: Lock + Default> ExpiringHashSet<T, L> {
+    pub fn new(stripes: usize) -> Self { ExpiringHashSet::with_clock(stripes, RealClock) }
+}
+
+impl<T: Hash, L: Lock + Default, C: Clock> ExpiringHashSet<T, L, C> {
+    pub fn with_clock(stripes: usize, clock: C) -> Self {
+        ExpiringHashSet { set: StripedHashSet::new(stripes), clock }
+    }
+}
+
+impl<T: Hash, L: Lock, C: Clock> ExpiringHashSet<T, L, C> {
+    // fails if a live entry is already there; an expired one is replaced
+    pub fn add_with_ttl(&self, element: T, ttl: Duration) -> bool {
+        let expires = self.clock.now() + ttl;
+        self.insert(Expiring::new(element, Some(expires)))
+    }
+    fn insert(&self, entry: Expiring<T>) -> bool {
+        let now = self.clock.now();
+        self.set.with_bucket(Hashable::hash(&entry), |bucket| bucket.insert_expiring(entry, now))
+    }
+    // Goes through one stripe at a time, holding it for a chunk of a
+    // bucket at most, and returns how many expired entries it unlinked. A
+    // resize between chunks moves entries between the stripe's buckets,
+    // so the stripe is started over.
+    pub fn sweep(&self) -> usize {
+        let now = self.clock.now();
+        let mut removed = 0;
+        for stripe in 0..self.set.stripes() {
+            let (mut at, mut from, mut covered) = (0, 0, None);
+            loop {
+                let (_guard, buckets) = self.set.stripe(stripe);
+                if covered.is_some_and(|covered| covered != buckets.len()) { (at, from) = (0, 0); }
+                covered = Some(buckets.len());
+                let Some(&bucket) = buckets.get(at) else { break };
+                let (chunk_removed, next) = unsafe { &mut *bucket }.sweep_chunk(from, now);
+                self.set.size.fetch_sub(chunk_removed, Ordering::Relaxed);
+                removed += chunk_removed;
+                match next {
+                    Some(key) => from = key,
+                    None => (at, from) = (at + 1, 0),
+                }
+            }
+        }
+        removed
+    }
+    // counts expired entries that have not been unlinked yet
+    pub fn len(&self) -> usize { self.set.len() }
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+}
+
+impl<T: Hash, L: Lock, C: Clock> Set<T> for ExpiringHashSet<T, L, C> {
+    fn contains(&self, element: T) -> bool {
+        let (key, now) = (Hashable::hash(&element), self.clock.now());
+        self.set.with_bucket(key, |bucket| bucket.find_live(key, now))
+    }
+}
+
+impl<T: Hash, L: Lock, C: Clock> ConcurrentSet<T> for ExpiringHashSet<T, L, C> {
+    fn add(&self, element: T) -> bool {
+        self.insert(Expiring::new(element, None))
+    }
+    fn remove(&self, element: T) -> bool {
+        let (key, now) = (Hashable::hash(&element), self.clock.now());
+        self.set.with_bucket(key, |bucket| bucket.remove_live(key, now))
+    }
+}
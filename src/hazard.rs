@@ -0,0 +1,183 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cell::{Cell, UnsafeCell};
+use core::marker::PhantomData;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, AtomicPtr, Ordering, fence};
+
+// two are enough to walk a list or a queue hand over hand
+pub const SLOTS: usize = 2;
+// a handle scans the hazards once it has this many retired pointers
+const SCAN_THRESHOLD: usize = 64;
+
+struct Retired {
+    ptr: *mut (),
+    drop: unsafe fn(*mut ()),
+}
+
+unsafe fn drop_box<T>(ptr: *mut ()) { drop(unsafe { Box::from_raw(ptr as *mut T) }); }
+
+// One per handle, kept on a list that only grows until the domain is
+// dropped. A record outlives the handle that used it: the next handle to
+// claim it takes over whatever it still had retired.
+#[repr(align(64))]
+struct Record {
+    hazards: [AtomicPtr<()>; SLOTS],
+    active: AtomicBool,
+    retired: UnsafeCell<Vec<Retired>>,
+    next: *mut Record,
+}
+
+// Hazard pointers: a reader announces the pointer it is about to use and
+// checks it is still reachable, and nothing that is announced gets freed.
+// Retired pointers are freed by their retiring handle's scans once no
+// record announces them, or when the domain is dropped.
+pub struct HazardDomain {
+    records: AtomicPtr<Record>,
+}
+
+unsafe impl Send for HazardDomain {}
+unsafe impl Sync for HazardDomain {}
+
+// a thread's way into the domain; not Send, since the record it holds is
+// only ever used by one thread at a time
+pub struct HazardHandle<'a> {
+    domain: &'a HazardDomain,
+    record: &'a Record,
+    in_use: [Cell<bool>; SLOTS],
+    _not_send: PhantomData<*mut ()>,
+}
+
+pub struct Protected<'a, T> {
+    handle: &'a HazardHandle<'a>,
+    slot: usize,
+    ptr: *mut T,
+}
+
+impl HazardDomain {
+    pub fn new() -> Self {
+        HazardDomain { records: AtomicPtr::new(ptr::null_mut()) }
+    }
+    pub fn register(&self) -> HazardHandle<'_> {
+        let mut curr = self.records.load(Ordering::Acquire);
+        while let Some(record) = unsafe { curr.as_ref() } {
+            if !record.active.load(Ordering::Relaxed)
+                && !record.active.swap(true, Ordering::Acquire) {
+                return HazardHandle::new(self, record);
+            }
+            curr = record.next;
+        }
+        let record = Box::into_raw(Box::new(Record {
+            hazards: Default::default(),
+            active: AtomicBool::new(true),
+            retired: UnsafeCell::new(Vec::new()),
+            next: ptr::null_mut(),
+        }));
+        let mut head = self.records.load(Ordering::Relaxed);
+        loop {
+            unsafe { (*record).next = head; }
+            match self.records.compare_exchange_weak(
+                head, record, Ordering::Release, Ordering::Relaxed
+            ) {
+                Ok(_) => return HazardHandle::new(self, unsafe { &*record }),
+                Err(current) => head = current,
+            }
+        }
+    }
+    fn is_protected(&self, ptr: *mut ()) -> bool {
+        let mut curr = self.records.load(Ordering::Acquire);
+        while let Some(record) = unsafe { curr.as_ref() } {
+            if record.hazards.iter().any(|hazard| hazard.load(Ordering::SeqCst) == ptr) {
+                return true;
+            }
+            curr = record.next;
+        }
+        false
+    }
+}
+
+impl Default for HazardDomain {
+    fn default() -> Self { Self::new() }
+}
+
+impl Drop for HazardDomain {
+    fn drop(&mut self) {
+        let mut curr = *self.records.get_mut();
+        while !curr.is_null() {
+            let record = unsafe { Box::from_raw(curr) };
+            for retired in record.retired.into_inner() {
+                unsafe { (retired.drop)(retired.ptr); }
+            }
+            curr = record.next;
+        }
+    }
+}
+
+impl<'a> HazardHandle<'a> {
+    fn new(domain: &'a HazardDomain, record: &'a Record) -> Self {
+        HazardHandle { domain, record, in_use: Default::default(), _not_send: PhantomData }
+    }
+    // loads src into a free slot, re-reading until the announced pointer
+    // is still the current one; panics if every slot is taken
+    pub fn protect<T>(&self, src: &AtomicPtr<T>) -> Protected<'_, T> {
+        let slot = self.in_use.iter().position(|in_use| !in_use.get())
+            .expect("all hazard slots are in use");
+        self.in_use[slot].set(true);
+        let hazard = &self.record.hazards[slot];
+        let mut ptr = src.load(Ordering::Acquire);
+        loop {
+            hazard.store(ptr as *mut (), Ordering::SeqCst);
+            let current = src.load(Ordering::SeqCst);
+            if current == ptr { return Protected { handle: self, slot, ptr }; }
+            ptr = current;
+        }
+    }
+    /// # Safety
+    /// ptr must come from Box::into_raw, must already be unreachable for
+    /// anyone who has not protected it, and must not be retired twice.
+    pub unsafe fn retire<T: Send + 'static>(&self, ptr: *mut T) {
+        unsafe { self.retire_with(ptr as *mut (), drop_box::<T>); }
+    }
+    // for pointers that aren't boxes: drop is what frees ptr once nothing
+    // announces it, on whichever thread scans
+    pub(crate) unsafe fn retire_with(&self, ptr: *mut (), drop: unsafe fn(*mut ())) {
+        let retired = unsafe { &mut *self.record.retired.get() };
+        retired.push(Retired { ptr, drop });
+        if retired.len() >= SCAN_THRESHOLD { self.scan(); }
+    }
+    // frees what no record announces and returns how many that was
+    pub fn scan(&self) -> usize {
+        fence(Ordering::SeqCst);
+        let retired = unsafe { &mut *self.record.retired.get() };
+        let before = retired.len();
+        let (free, keep) = core::mem::take(retired).into_iter()
+            .partition::<Vec<_>, _>(|retired| !self.domain.is_protected(retired.ptr));
+        *retired = keep;
+        for retired in free {
+            unsafe { (retired.drop)(retired.ptr); }
+        }
+        before - retired.len()
+    }
+    pub fn pending(&self) -> usize { unsafe { &*self.record.retired.get() }.len() }
+}
+
+impl Drop for HazardHandle<'_> {
+    // whatever is still protected stays with the record for its next owner
+    // or the domain's drop
+    fn drop(&mut self) {
+        self.scan();
+        self.record.active.store(false, Ordering::Release);
+    }
+}
+
+impl<T> Protected<'_, T> {
+    pub fn as_ptr(&self) -> *mut T { self.ptr }
+    pub fn as_ref(&self) -> Option<&T> { unsafe { self.ptr.as_ref() } }
+}
+
+impl<T> Drop for Protected<'_, T> {
+    fn drop(&mut self) {
+        self.handle.record.hazards[self.slot].store(ptr::null_mut(), Ordering::Release);
+        self.handle.in_use[self.slot].set(false);
+    }
+}
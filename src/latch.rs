@@ -0,0 +1,29 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::backoff::SpinBackoff;
+
+// One-shot: once the count reaches zero it stays there. Every count_down
+// is a release and every successful wait an acquire, so whatever a thread
+// wrote before counting down is visible once wait returns.
+pub struct CountDownLatch {
+    count: AtomicUsize,
+}
+
+impl CountDownLatch {
+    pub fn new(count: usize) -> Self {
+        CountDownLatch { count: AtomicUsize::new(count) }
+    }
+    pub fn count(&self) -> usize { self.count.load(Ordering::Relaxed) }
+    // a no-op once the count is zero
+    pub fn count_down(&self) {
+        let _ = self.count.fetch_update(Ordering::Release, Ordering::Relaxed, |count| {
+            count.checked_sub(1)
+        });
+    }
+    pub fn try_wait(&self) -> bool { self.count.load(Ordering::Acquire) == 0 }
+    // spins for a while, then yields between checks
+    pub fn wait(&self) {
+        let mut backoff = SpinBackoff::new();
+        while !self.try_wait() { backoff.snooze(); }
+    }
+}
@@ -1,5 +1,41 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod atomic;
+pub mod backoff;
+pub mod chaos;
+#[cfg(feature = "std")]
+pub mod clock;
+pub mod config;
+pub mod error;
+pub mod hashset;
+pub mod hazard;
+#[cfg(feature = "std")]
+pub mod latch;
 pub mod listset;
 pub mod lock;
+#[cfg(feature = "std")]
+pub mod metrics;
+#[cfg(feature = "std")]
+pub mod oneshot;
+pub mod pad;
+#[cfg(feature = "std")]
+pub mod queue;
+#[cfg(feature = "std")]
+pub mod quiescence;
+#[cfg(all(feature = "std", target_pointer_width = "64"))]
+pub mod stack;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(all(feature = "trace-atomics", not(loom)))]
+pub mod trace;
 
-mod backoff;
+mod boxed;
 mod hash;
+#[cfg(feature = "std")]
+mod protected;
+mod sync;
+
+#[cfg(feature = "std")]
+pub use protected::run_protected;
@@ -1,6 +1,38 @@
-use std::hash::Hash;
+use alloc::boxed::Box;
+use alloc::vec::{self, Vec};
+use core::cell::{Cell, UnsafeCell};
+use core::hash::Hash;
+use core::iter::FusedIterator;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, AtomicU8, AtomicUsize, Ordering};
 
-use crate::{lock::Lock, hash::{Hashed, Hashable}};
+use crate::{boxed, error::InsertError, lock::Lock, hash::{Hashed, Hashable}};
+
+#[cfg(feature = "std")]
+mod expiring;
+mod fine;
+mod lazy;
+mod lockfree;
+mod refcount;
+mod rwset;
+#[cfg(feature = "std")]
+mod skiplist;
+#[cfg(feature = "std")]
+mod stdset;
+
+#[cfg(feature = "std")]
+pub use expiring::ExpiringSet;
+#[cfg(feature = "std")]
+pub(crate) use expiring::Expiring;
+pub use fine::FineListSet;
+pub use lazy::LazyListSet;
+pub use lockfree::LockFreeListSet;
+pub use refcount::RefCountListSet;
+pub use rwset::RwListSet;
+#[cfg(feature = "std")]
+pub use skiplist::SkipListSet;
+#[cfg(feature = "std")]
+pub use stdset::StdSet;
 
 pub trait Set<T> {
     fn contains(&self, element: T) -> bool;
@@ -11,6 +43,21 @@ pub trait MutSet<T>: Set<T> {
     fn remove(&mut self, element: T) -> bool;
 }
 
+pub trait ConcurrentSet<T>: Set<T> {
+    fn add(&self, element: T) -> bool;
+    fn remove(&self, element: T) -> bool;
+}
+
+// When the marking sets unlink a removed node. Deferred only marks it,
+// leaving it in the list until a purge, or a writer that has to get past
+// it, unlinks it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnlinkPolicy {
+    #[default]
+    Eager,
+    Deferred,
+}
+
 struct Node<T: Hash> {
     item: Hashed<T>,
     next: Link<T>,
@@ -18,20 +65,32 @@ struct Node<T: Hash> {
 
 type Link<T> = Option<Box<Node<T>>>;
 
+// dropping a Box<Node> drops its next link first, which recurses once per
+// node; anything that owns a whole chain should drop it through here
+fn drop_chain<T: Hash>(mut link: Link<T>) {
+    while let Some(mut node) = link {
+        link = node.next.take();
+    }
+}
+
 impl<T: Hash> Node<T> {
     fn insert(at: &mut Link<T>, item: T) {
         let item = Hashed::new(item);
         let new_node = Node { item, next: at.take() };
         *at = Some(Box::new(new_node));
     }
-    fn remove(at: &mut Link<T>) -> Result<T, &'static str> {
-        match at.take() {
-            Some(node) => {
-                *at = node.next;
-                Ok(node.item.get())
-            },
-            None => Err("cannot remove from empty list"),
-        }
+    fn try_insert(at: &mut Link<T>, item: T) -> Result<(), InsertError<T>> {
+        let item = Hashed::new(item);
+        let mut new_node = boxed::try_new(Node { item, next: None })
+            .map_err(|node| InsertError::new(node.item.get()))?;
+        new_node.next = at.take();
+        *at = Some(new_node);
+        Ok(())
+    }
+    fn remove(at: &mut Link<T>) -> Option<T> {
+        let node = at.take()?;
+        *at = node.next;
+        Some(node.item.get())
     }
     fn find(from: &Link<T>, key: u64) -> (&Link<T>, bool) {
         match from {
@@ -69,14 +128,118 @@ impl<T: Hash> Hashable for Node<T> {
     fn hash(&self) -> u64 { self.item.hash() }
 }
 
-pub struct SeqListSet<T: Hash> { head: Link<T> }
+pub struct SeqListSet<T: Hash> {
+    head: Link<T>,
+    len: usize,
+}
 
 impl<T: Hash> SeqListSet<T> {
     pub fn new() -> Self {
-        SeqListSet { head: None }
+        SeqListSet { head: None, len: 0 }
+    }
+    pub fn len(&self) -> usize { self.len }
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+    pub fn make_key(&self, h: &impl Hash) -> u64 { Hashable::hash(h) }
+    pub fn contains_by_key(&self, key: u64, eq: impl Fn(&T) -> bool) -> bool {
+        self.get_by_key(key, eq).is_some()
+    }
+    pub fn get_by_key(&self, key: u64, eq: impl Fn(&T) -> bool) -> Option<&T> {
+        match Node::find(&self.head, key) {
+            (Some(node), true) => Some(node.item.item()).filter(|item| eq(item)),
+            _ => None,
+        }
+    }
+    pub fn try_add(&mut self, element: T) -> Result<bool, InsertError<T>> {
+        let key = Hashable::hash(&element);
+        let (node, present) = Node::find_mut(&mut self.head, key);
+        if !present {
+            Node::try_insert(node, element)?;
+            self.len += 1;
+        }
+        Ok(!present)
+    }
+    pub fn clear(&mut self) {
+        drop_chain(self.head.take());
+        self.len = 0;
+    }
+    // removes and returns the elements pred picks, in key order
+    pub fn drain_filter(&mut self, mut pred: impl FnMut(&T) -> bool) -> Vec<T> {
+        let mut drained = Vec::new();
+        let mut at = &mut self.head;
+        while let Some(node) = at.as_deref() {
+            if pred(node.item.item()) {
+                drained.push(Node::remove(at).expect("SeqListSet in invalid state"));
+            } else {
+                at = &mut at.as_mut().expect("SeqListSet in invalid state").next;
+            }
+        }
+        self.len -= drained.len();
+        drained
+    }
+    // in key order, which is hash order rather than insertion order
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { next: &self.head, len: self.len }
     }
 }
 
+pub struct Iter<'a, T: Hash> {
+    next: &'a Link<T>,
+    len: usize,
+}
+
+impl<'a, T: Hash> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.next.as_ref()?;
+        self.next = &node.next;
+        self.len -= 1;
+        Some(node.item.item())
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) { (self.len, Some(self.len)) }
+}
+
+impl<T: Hash> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T: Hash> FusedIterator for Iter<'_, T> {}
+
+impl<'a, T: Hash> IntoIterator for &'a SeqListSet<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter { self.iter() }
+}
+
+impl<T: Hash> IntoIterator for SeqListSet<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+    fn into_iter(self) -> Self::IntoIter { IntoIter { set: self } }
+}
+
+pub struct IntoIter<T: Hash> {
+    set: SeqListSet<T>,
+}
+
+impl<T: Hash> Iterator for IntoIter<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = Node::remove(&mut self.set.head)?;
+        self.set.len -= 1;
+        Some(item)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) { (self.set.len, Some(self.set.len)) }
+}
+
+impl<T: Hash> ExactSizeIterator for IntoIter<T> {}
+
+impl<T: Hash> FusedIterator for IntoIter<T> {}
+
+impl<T: Hash> Default for SeqListSet<T> {
+    fn default() -> Self { Self::new() }
+}
+
+impl<T: Hash> Drop for SeqListSet<T> {
+    fn drop(&mut self) { drop_chain(self.head.take()); }
+}
+
 impl<T: Hash> Set<T> for SeqListSet<T> {
     fn contains(&self, element: T) -> bool {
         let key = Hashable::hash(&element);
@@ -89,36 +252,261 @@ impl<T: Hash> MutSet<T> for SeqListSet<T> {
     fn add(&mut self, element: T) -> bool {
         let key = Hashable::hash(&element);
         let (node, present) = Node::find_mut(&mut self.head, key);
-        if !present { Node::insert(node, element); }
+        if !present {
+            Node::insert(node, element);
+            self.len += 1;
+        }
         !present
     }
     fn remove(&mut self, element: T) -> bool {
         let key = Hashable::hash(&element);
         let (node, present) = Node::find_mut(&mut self.head, key);
-        if present { assert!(Node::remove(node).is_ok()); }
+        if present {
+            assert!(Node::remove(node).is_some());
+            self.len -= 1;
+        }
         present
     }
 }
 
 pub struct CoarseListSet<T: Hash, L: Lock> {
-    seq: SeqListSet<T>,
+    seq: UnsafeCell<SeqListSet<T>>,
     lock: L,
+    yield_every: Option<usize>,
+    // bumped under the lock whenever nodes may have been freed
+    removals: AtomicUsize,
+    combining: bool,
+    pending: AtomicPtr<Op<T>>,
+}
+
+// A pending add or remove, published on a stack for whoever holds the lock
+// next. It lives on the submitter's stack frame: the submitter does not
+// return until the result is in, and the combiner reads next before it
+// writes the result, so nothing touches an op after it completes.
+struct Op<T> {
+    element: UnsafeCell<Option<T>>,
+    remove: bool,
+    result: AtomicU8,
+    next: Cell<*mut Op<T>>,
+}
+
+const PENDING: u8 = 0;
+const UNCHANGED: u8 = 1;
+const CHANGED: u8 = 2;
+// hashing the element panicked on the combiner's thread
+const PANICKED: u8 = 3;
+
+// Applying an op runs the element's Hash, which may panic. If it does,
+// the ops after it go back on pending for their owners to apply once they
+// get the lock, except the combiner's own, which goes away with its
+// frame, and the op that panicked is marked so its owner panics too.
+struct Requeue<'a, T: Hash, L: Lock> {
+    set: &'a CoarseListSet<T, L>,
+    own: *const Op<T>,
+    applying: *mut Op<T>,
+}
+
+impl<T: Hash, L: Lock> Drop for Requeue<'_, T, L> {
+    fn drop(&mut self) {
+        let Some(op) = (unsafe { self.applying.as_ref() }) else { return };
+        let mut curr = op.next.get();
+        if !ptr::eq(op, self.own) { op.result.store(PANICKED, Ordering::Release); }
+        while let Some(op) = unsafe { curr.as_ref() } {
+            let next = op.next.get();
+            if !ptr::eq(op, self.own) { self.set.publish(curr); }
+            curr = next;
+        }
+    }
+}
+
+unsafe impl<T: Hash + Send, L: Lock> Sync for CoarseListSet<T, L> {}
+
+impl<T: Hash, L: Lock> CoarseListSet<T, L> {
+    pub fn new(lock: L) -> Self {
+        CoarseListSet {
+            seq: UnsafeCell::new(SeqListSet::new()),
+            lock,
+            yield_every: None,
+            removals: AtomicUsize::new(0),
+            combining: false,
+            pending: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+    // Long searches release and re-acquire the lock after every `nodes`
+    // nodes, so an operation is no longer a single critical section. A
+    // search resumes where it stopped unless something was removed in the
+    // meantime, in which case it starts over from the head.
+    pub fn yield_every(self, nodes: usize) -> Self {
+        assert!(nodes > 0, "cannot yield after zero nodes");
+        CoarseListSet { yield_every: Some(nodes), ..self }
+    }
+    // Writers publish their operation before taking the lock, and every
+    // lock holder applies all published operations before releasing, so
+    // a writer often finds its work already done by the time it gets in.
+    pub fn combining(self) -> Self {
+        CoarseListSet { combining: true, ..self }
+    }
+    fn combine(&self, element: T, remove: bool) -> bool {
+        let op = Op {
+            element: UnsafeCell::new(Some(element)),
+            remove,
+            result: AtomicU8::new(PENDING),
+            next: Cell::new(ptr::null_mut()),
+        };
+        self.publish(&op as *const Op<T> as *mut Op<T>);
+        let _guard = self.lock.acquire();
+        // an earlier holder may have applied it while we waited
+        if op.result.load(Ordering::Acquire) == PENDING { self.apply_pending(&op); }
+        let result = op.result.load(Ordering::Acquire);
+        assert!(result != PANICKED, "hashing the element panicked while another thread applied it");
+        result == CHANGED
+    }
+    fn publish(&self, op: *mut Op<T>) {
+        let mut head = self.pending.load(Ordering::Relaxed);
+        loop {
+            unsafe { (*op).next.set(head); }
+            match self.pending.compare_exchange_weak(
+                head, op, Ordering::Release, Ordering::Relaxed
+            ) {
+                Ok(_) => break,
+                Err(current) => head = current,
+            }
+        }
+    }
+    // must be called with the lock held
+    fn apply_pending(&self, own: &Op<T>) {
+        let seq = unsafe { &mut *self.seq.get() };
+        let mut curr = self.pending.swap(ptr::null_mut(), Ordering::Acquire);
+        let mut requeue = Requeue { set: self, own, applying: ptr::null_mut() };
+        while let Some(op) = unsafe { curr.as_ref() } {
+            requeue.applying = curr;
+            curr = op.next.get();
+            let element = unsafe { (*op.element.get()).take() }
+                .expect("CoarseListSet in invalid state");
+            let changed = if op.remove {
+                self.removals.fetch_add(1, Ordering::Relaxed);
+                seq.remove(element)
+            } else {
+                seq.add(element)
+            };
+            requeue.applying = ptr::null_mut();
+            op.result.store(if changed { CHANGED } else { UNCHANGED }, Ordering::Release);
+        }
+    }
+    // returns with the lock held, pointing at the link where key belongs
+    fn find_yielding(&self, key: u64, every: usize) -> (L::Guard<'_>, *mut Link<T>, bool) {
+        let head = unsafe { ptr::addr_of_mut!((*self.seq.get()).head) };
+        let mut guard = self.lock.acquire();
+        let mut removals = self.removals.load(Ordering::Relaxed);
+        let (mut at, mut visited) = (head, 0);
+        loop {
+            if visited == every {
+                drop(guard);
+                guard = self.lock.acquire();
+                visited = 0;
+                let now = self.removals.load(Ordering::Relaxed);
+                if now != removals { (at, removals) = (head, now); }
+                continue;
+            }
+            match unsafe { &mut *at } {
+                Some(node) if node.hash() < key => {
+                    at = &mut node.next;
+                    visited += 1;
+                },
+                Some(node) if node.hash() == key => return (guard, at, true),
+                _ => return (guard, at, false),
+            }
+        }
+    }
+    pub fn make_key(&self, h: &impl Hash) -> u64 { Hashable::hash(h) }
+    pub fn contains_by_key(&self, key: u64, eq: impl Fn(&T) -> bool) -> bool {
+        let _guard = self.lock.acquire();
+        unsafe { &*self.seq.get() }.contains_by_key(key, eq)
+    }
+    pub fn get_by_key(&self, key: u64, eq: impl Fn(&T) -> bool) -> Option<T>
+    where T: Clone {
+        let _guard = self.lock.acquire();
+        unsafe { &*self.seq.get() }.get_by_key(key, eq).cloned()
+    }
+    pub fn try_add(&self, element: T) -> Result<bool, InsertError<T>> {
+        let _guard = self.lock.acquire();
+        unsafe { &mut *self.seq.get() }.try_add(element)
+    }
+    pub fn len(&self) -> usize {
+        let _guard = self.lock.acquire();
+        unsafe { &*self.seq.get() }.len()
+    }
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+    // copies the elements out under the lock; the copy is what makes the
+    // result double-ended and independent of later changes to the set
+    pub fn snapshot(&self) -> vec::IntoIter<T> where T: Clone {
+        let _guard = self.lock.acquire();
+        let items: Vec<T> = unsafe { &*self.seq.get() }.iter().cloned().collect();
+        items.into_iter()
+    }
+    pub fn clear(&self) {
+        let _guard = self.lock.acquire();
+        self.removals.fetch_add(1, Ordering::Relaxed);
+        unsafe { &mut *self.seq.get() }.clear()
+    }
+    // runs f as a single critical section; if f panics the lock is still
+    // released and the set keeps whatever changes f had made
+    pub fn transact<R>(&self, f: impl FnOnce(&mut SeqListSet<T>) -> R) -> R {
+        let _guard = self.lock.acquire();
+        self.removals.fetch_add(1, Ordering::Relaxed);
+        f(unsafe { &mut *self.seq.get() })
+    }
 }
 
 impl<T: Hash, L: Lock> Set<T> for CoarseListSet<T, L> {
     fn contains(&self, element: T) -> bool {
+        if let Some(every) = self.yield_every {
+            let key = Hashable::hash(&element);
+            return self.find_yielding(key, every).2;
+        }
+        let _guard = self.lock.acquire();
+        unsafe { &*self.seq.get() }.contains(element)
+    }
+}
+
+impl<T: Hash, L: Lock> ConcurrentSet<T> for CoarseListSet<T, L> {
+    fn add(&self, element: T) -> bool {
+        if self.combining { return self.combine(element, false); }
+        if let Some(every) = self.yield_every {
+            let key = Hashable::hash(&element);
+            let (_guard, at, present) = self.find_yielding(key, every);
+            if !present {
+                Node::insert(unsafe { &mut *at }, element);
+                unsafe { (*self.seq.get()).len += 1; }
+            }
+            return !present;
+        }
+        let _guard = self.lock.acquire();
+        unsafe { &mut *self.seq.get() }.add(element)
+    }
+    fn remove(&self, element: T) -> bool {
+        if self.combining { return self.combine(element, true); }
+        if let Some(every) = self.yield_every {
+            let key = Hashable::hash(&element);
+            let (_guard, at, present) = self.find_yielding(key, every);
+            if present {
+                self.removals.fetch_add(1, Ordering::Relaxed);
+                assert!(Node::remove(unsafe { &mut *at }).is_some());
+                unsafe { (*self.seq.get()).len -= 1; }
+            }
+            return present;
+        }
         let _guard = self.lock.acquire();
-        self.seq.contains(element)
+        self.removals.fetch_add(1, Ordering::Relaxed);
+        unsafe { &mut *self.seq.get() }.remove(element)
     }
 }
 
 impl<T: Hash, L: Lock> MutSet<T> for CoarseListSet<T, L> {
     fn add(&mut self, element: T) -> bool {
-        let _guard = self.lock.acquire();
-        self.seq.add(element)
+        self.seq.get_mut().add(element)
     }
     fn remove(&mut self, element: T) -> bool {
-        let _guard = self.lock.acquire();
-        self.seq.remove(element)
+        self.seq.get_mut().remove(element)
     }
 }
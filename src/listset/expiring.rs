@@ -0,0 +1,146 @@
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+use crate::{clock::{Clock, RealClock}, lock::Lock, hash::{Hashed, Hashable}};
+
+use super::{CoarseListSet, ConcurrentSet, Node, SeqListSet, Set};
+
+const SWEEP_CHUNK: usize = 64;
+
+// hashes as the bare item, so an entry is found by its element alone
+pub(crate) struct Expiring<T> {
+    item: T,
+    expires: Option<Instant>,
+}
+
+impl<T: Hash> Hash for Expiring<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) { self.item.hash(state) }
+}
+
+impl<T> Expiring<T> {
+    pub(crate) fn new(item: T, expires: Option<Instant>) -> Self { Expiring { item, expires } }
+    fn expired(&self, now: Instant) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+}
+
+// The list operations behind the expiring sets, for whichever lock covers
+// the list. They keep len in step with what they link and unlink.
+impl<T: Hash> SeqListSet<Expiring<T>> {
+    // fails if a live entry is already there; an expired one is replaced
+    pub(crate) fn insert_expiring(&mut self, entry: Expiring<T>, now: Instant) -> bool {
+        let key = Hashable::hash(&entry);
+        match Node::find_mut(&mut self.head, key) {
+            (Some(node), true) if node.item.item().expired(now) => {
+                node.item = Hashed::new(entry);
+                true
+            },
+            (_, true) => false,
+            (link, false) => {
+                Node::insert(link, entry);
+                self.len += 1;
+                true
+            },
+        }
+    }
+    // unlinks the entry for key if it has expired; returns whether a live
+    // entry is left
+    pub(crate) fn find_live(&mut self, key: u64, now: Instant) -> bool {
+        match Node::find_mut(&mut self.head, key) {
+            (link, true) if link.as_ref().is_some_and(|node| node.item.item().expired(now)) => {
+                Node::remove(link);
+                self.len -= 1;
+                false
+            },
+            (_, present) => present,
+        }
+    }
+    pub(crate) fn remove_live(&mut self, key: u64, now: Instant) -> bool {
+        let live = self.find_live(key, now);
+        if live {
+            Node::remove(Node::find_mut(&mut self.head, key).0);
+            self.len -= 1;
+        }
+        live
+    }
+    // Unlinks the expired entries among at most SWEEP_CHUNK, starting at
+    // the first key no less than from. Returns how many it unlinked and
+    // the key of the first entry it didn't get to, if any.
+    pub(crate) fn sweep_chunk(&mut self, from: u64, now: Instant) -> (usize, Option<u64>) {
+        let (mut at, _) = Node::find_mut(&mut self.head, from);
+        let mut removed = 0;
+        for _ in 0..SWEEP_CHUNK {
+            let expired = match at {
+                Some(node) => node.item.item().expired(now),
+                None => break,
+            };
+            if expired {
+                Node::remove(at);
+                removed += 1;
+            } else {
+                at = &mut at.as_mut().expect("ExpiringSet in invalid state").next;
+            }
+        }
+        let next = at.as_ref().map(|node| node.hash());
+        self.len -= removed;
+        (removed, next)
+    }
+}
+
+// Expired entries count as absent straight away; they are unlinked when an
+// operation runs into one or when sweep gets to them.
+pub struct ExpiringSet<T: Hash, L: Lock, C: Clock = RealClock> {
+    set: CoarseListSet<Expiring<T>, L>,
+    clock: C,
+}
+
+impl<T: Hash, L: Lock> ExpiringSet<T, L> {
+    pub fn new(lock: L) -> Self { ExpiringSet::with_clock(lock, RealClock) }
+}
+
+impl<T: Hash, L: Lock, C: Clock> ExpiringSet<T, L, C> {
+    pub fn with_clock(lock: L, clock: C) -> Self {
+        ExpiringSet { set: CoarseListSet::new(lock), clock }
+    }
+    // fails if a live entry is already there; an expired one is replaced
+    pub fn add_with_ttl(&self, element: T, ttl: Duration) -> bool {
+        let expires = self.clock.now() + ttl;
+        self.insert(Expiring::new(element, Some(expires)))
+    }
+    fn insert(&self, entry: Expiring<T>) -> bool {
+        let now = self.clock.now();
+        self.set.transact(|seq| seq.insert_expiring(entry, now))
+    }
+    // walks the set a chunk at a time, letting other operations in between
+    // chunks, and returns how many expired entries it unlinked
+    pub fn sweep(&self) -> usize {
+        let now = self.clock.now();
+        let (mut removed, mut from) = (0, Some(0));
+        while let Some(key) = from {
+            let (chunk_removed, next) = self.set.transact(|seq| seq.sweep_chunk(key, now));
+            removed += chunk_removed;
+            from = next;
+        }
+        removed
+    }
+    // counts expired entries that have not been unlinked yet
+    pub fn len(&self) -> usize { self.set.len() }
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+}
+
+impl<T: Hash, L: Lock, C: Clock> Set<T> for ExpiringSet<T, L, C> {
+    fn contains(&self, element: T) -> bool {
+        let (key, now) = (Hashable::hash(&element), self.clock.now());
+        self.set.transact(|seq| seq.find_live(key, now))
+    }
+}
+
+impl<T: Hash, L: Lock, C: Clock> ConcurrentSet<T> for ExpiringSet<T, L, C> {
+    fn add(&self, element: T) -> bool {
+        self.insert(Expiring::new(element, None))
+    }
+    fn remove(&self, element: T) -> bool {
+        let (key, now) = (Hashable::hash(&element), self.clock.now());
+        self.set.transact(|seq| seq.remove_live(key, now))
+    }
+}
@@ -0,0 +1,125 @@
+use alloc::boxed::Box;
+use core::cell::UnsafeCell;
+use core::hash::Hash;
+use core::ptr;
+
+use crate::{atomic::SnapshotPair, lock::Lock, hash::{Hashed, Hashable}};
+
+use super::{ConcurrentSet, MutSet, Set};
+
+// next is only read or written with the node's own lock held
+struct Node<T: Hash, L: Lock> {
+    item: Option<Hashed<T>>,
+    next: UnsafeCell<*mut Node<T, L>>,
+    lock: L,
+}
+
+impl<T: Hash, L: Lock> Node<T, L> {
+    fn key(&self) -> u64 {
+        self.item.as_ref().expect("FineListSet in invalid state").hash()
+    }
+}
+
+type Window<'a, T, L> = (
+    &'a Node<T, L>,
+    <L as Lock>::Guard<'a>,
+    Option<(&'a Node<T, L>, <L as Lock>::Guard<'a>)>,
+);
+
+// Hand-over-hand locking: a thread holds the lock of the node it stands
+// on while taking the next one, so nobody can unlink a node out from
+// under it, and a node being unlinked cannot have anyone waiting on it.
+pub struct FineListSet<T: Hash, L: Lock> {
+    head: Node<T, L>,
+    // updated with the predecessor locked, so removes never overtake adds
+    size: SnapshotPair,
+}
+
+unsafe impl<T: Hash + Send, L: Lock + Send> Send for FineListSet<T, L> {}
+// a node's lock is made by whichever thread adds it and dropped by
+// whichever removes it
+unsafe impl<T: Hash + Send, L: Lock + Send> Sync for FineListSet<T, L> {}
+
+impl<T: Hash, L: Lock + Default> FineListSet<T, L> {
+    pub fn new() -> Self {
+        let head = Node { item: None, next: UnsafeCell::new(ptr::null_mut()), lock: L::default() };
+        FineListSet { head, size: SnapshotPair::new() }
+    }
+}
+
+impl<T: Hash, L: Lock + Default> Default for FineListSet<T, L> {
+    fn default() -> Self { Self::new() }
+}
+
+impl<T: Hash, L: Lock> FineListSet<T, L> {
+    pub fn len(&self) -> usize { self.size.len() }
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+    // returns the last node below key and the one after it, both locked
+    fn find(&self, key: u64) -> Window<'_, T, L> {
+        let mut pred = &self.head;
+        let mut pred_guard = pred.lock.acquire();
+        loop {
+            let curr = match unsafe { (*pred.next.get()).as_ref() } {
+                Some(curr) => curr,
+                None => return (pred, pred_guard, None),
+            };
+            let curr_guard = curr.lock.acquire();
+            if curr.key() >= key { return (pred, pred_guard, Some((curr, curr_guard))); }
+            (pred, pred_guard) = (curr, curr_guard);
+        }
+    }
+}
+
+impl<T: Hash, L: Lock> Drop for FineListSet<T, L> {
+    fn drop(&mut self) {
+        let mut curr = *self.head.next.get_mut();
+        while !curr.is_null() {
+            let mut node = unsafe { Box::from_raw(curr) };
+            curr = *node.next.get_mut();
+        }
+    }
+}
+
+impl<T: Hash, L: Lock> Set<T> for FineListSet<T, L> {
+    fn contains(&self, element: T) -> bool {
+        let key = Hashable::hash(&element);
+        let (_, _pred_guard, curr) = self.find(key);
+        curr.is_some_and(|(curr, _)| curr.key() == key)
+    }
+}
+
+impl<T: Hash, L: Lock + Default> ConcurrentSet<T> for FineListSet<T, L> {
+    fn add(&self, element: T) -> bool {
+        let key = Hashable::hash(&element);
+        let (pred, _pred_guard, curr) = self.find(key);
+        if curr.as_ref().is_some_and(|(curr, _)| curr.key() == key) { return false; }
+        let next = curr.map_or(ptr::null_mut(), |(curr, _)| curr as *const _ as *mut _);
+        let node = Node {
+            item: Some(Hashed::new(element)),
+            next: UnsafeCell::new(next),
+            lock: L::default(),
+        };
+        unsafe { *pred.next.get() = Box::into_raw(Box::new(node)); }
+        self.size.record_add();
+        true
+    }
+    fn remove(&self, element: T) -> bool {
+        let key = Hashable::hash(&element);
+        let (pred, _pred_guard, curr) = self.find(key);
+        let (curr, curr_guard) = match curr {
+            Some((curr, guard)) if curr.key() == key => (curr, guard),
+            _ => return false,
+        };
+        unsafe { *pred.next.get() = *curr.next.get(); }
+        self.size.record_remove();
+        // reaching curr takes pred's lock, which we still hold
+        drop(curr_guard);
+        unsafe { drop(Box::from_raw(curr as *const Node<T, L> as *mut Node<T, L>)); }
+        true
+    }
+}
+
+impl<T: Hash, L: Lock + Default> MutSet<T> for FineListSet<T, L> {
+    fn add(&mut self, element: T) -> bool { ConcurrentSet::add(&*self, element) }
+    fn remove(&mut self, element: T) -> bool { ConcurrentSet::remove(&*self, element) }
+}
@@ -0,0 +1,223 @@
+use alloc::boxed::Box;
+use core::cell::UnsafeCell;
+use core::hash::Hash;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+
+use crate::{atomic::SnapshotPair, lock::Lock, hash::{Hashed, Hashable}};
+
+use super::{ConcurrentSet, MutSet, Set, UnlinkPolicy};
+
+struct Node<T: Hash, L: Lock> {
+    item: Option<Hashed<T>>,
+    next: AtomicPtr<Node<T, L>>,
+    marked: AtomicBool,
+    lock: L,
+    // only set once the node is unlinked; next stays intact for readers
+    next_retired: UnsafeCell<*mut Node<T, L>>,
+}
+
+impl<T: Hash, L: Lock> Node<T, L> {
+    fn new(item: Option<Hashed<T>>, next: *mut Self, lock: L) -> Self {
+        Node {
+            item,
+            next: AtomicPtr::new(next),
+            marked: AtomicBool::new(false),
+            lock,
+            next_retired: UnsafeCell::new(ptr::null_mut()),
+        }
+    }
+    fn key(&self) -> u64 {
+        self.item.as_ref().expect("LazyListSet in invalid state").hash()
+    }
+}
+
+// The lazy list: searches take no locks, writers lock the two nodes they
+// touch and then check nothing changed in between, and a remove marks the
+// node before unlinking it so contains can trust the mark alone. Unlocked
+// readers may still be standing on an unlinked node, so removed nodes are
+// only freed once nobody else can be reading, on reclaim or drop.
+pub struct LazyListSet<T: Hash, L: Lock> {
+    head: Node<T, L>,
+    retired: AtomicPtr<Node<T, L>>,
+    // updated with both nodes locked, so removes never overtake adds
+    size: SnapshotPair,
+    policy: UnlinkPolicy,
+}
+
+unsafe impl<T: Hash + Send, L: Lock + Send> Send for LazyListSet<T, L> {}
+// as in FineListSet, nodes and their locks are made and dropped on
+// whichever threads add and reclaim them
+unsafe impl<T: Hash + Send + Sync, L: Lock + Send> Sync for LazyListSet<T, L> {}
+
+impl<T: Hash, L: Lock + Default> LazyListSet<T, L> {
+    pub fn new() -> Self {
+        LazyListSet {
+            head: Node::new(None, ptr::null_mut(), L::default()),
+            retired: AtomicPtr::new(ptr::null_mut()),
+            size: SnapshotPair::new(),
+            policy: UnlinkPolicy::Eager,
+        }
+    }
+}
+
+impl<T: Hash, L: Lock + Default> Default for LazyListSet<T, L> {
+    fn default() -> Self { Self::new() }
+}
+
+impl<T: Hash, L: Lock> LazyListSet<T, L> {
+    pub fn with_unlink_policy(mut self, policy: UnlinkPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+    pub fn len(&self) -> usize { self.size.len() }
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+    // frees removed nodes; needs &mut, since that rules out readers
+    pub fn reclaim(&mut self) -> usize {
+        let mut curr = *self.retired.get_mut();
+        *self.retired.get_mut() = ptr::null_mut();
+        let mut freed = 0;
+        while !curr.is_null() {
+            let node = unsafe { Box::from_raw(curr) };
+            curr = node.next_retired.into_inner();
+            freed += 1;
+        }
+        freed
+    }
+    // unlinks up to max marked nodes, returning how many it unlinked
+    pub fn purge(&self, max: usize) -> usize { self.unlink_marked(None, max) }
+    // Unlinks marked nodes from the front of the list up to the first
+    // unmarked node at or above key, or all the way with no key. A marked
+    // node's next never changes again, so with both nodes locked and pred
+    // still unmarked and pointing at it, it can be skipped over.
+    fn unlink_marked(&self, key: Option<u64>, max: usize) -> usize {
+        let mut unlinked = 0;
+        'retry: while unlinked < max {
+            let mut pred = &self.head;
+            while let Some(curr) = unsafe { pred.next.load(Ordering::Acquire).as_ref() } {
+                if !curr.marked.load(Ordering::Acquire) {
+                    if key.is_some_and(|key| curr.key() >= key) { break; }
+                    pred = curr;
+                    continue;
+                }
+                let _pred_guard = pred.lock.acquire();
+                let _curr_guard = curr.lock.acquire();
+                if pred.marked.load(Ordering::Acquire)
+                    || !ptr::eq(pred.next.load(Ordering::Acquire), curr) {
+                    continue 'retry;
+                }
+                pred.next.store(curr.next.load(Ordering::Acquire), Ordering::Release);
+                self.retire(curr);
+                unlinked += 1;
+                if unlinked == max { break; }
+            }
+            break;
+        }
+        unlinked
+    }
+    // the last node below key and the one after it, without locking
+    fn search(&self, key: u64) -> (&Node<T, L>, Option<&Node<T, L>>) {
+        let mut pred = &self.head;
+        loop {
+            match unsafe { pred.next.load(Ordering::Acquire).as_ref() } {
+                Some(curr) if curr.key() < key => pred = curr,
+                curr => return (pred, curr),
+            }
+        }
+    }
+    fn valid(pred: &Node<T, L>, curr: Option<&Node<T, L>>) -> bool {
+        let curr_ptr = curr.map_or(ptr::null(), |curr| curr as *const _);
+        !pred.marked.load(Ordering::Acquire)
+            && curr.is_none_or(|curr| !curr.marked.load(Ordering::Acquire))
+            && ptr::eq(pred.next.load(Ordering::Acquire), curr_ptr)
+    }
+    // with deferred unlinking a writer can keep finding the same marked
+    // node in its window, so it unlinks what is in its way before retrying
+    fn help_unlink(&self, key: u64) {
+        if self.policy == UnlinkPolicy::Deferred { self.unlink_marked(Some(key), usize::MAX); }
+    }
+    fn retire(&self, node: &Node<T, L>) {
+        let node_ptr = node as *const Node<T, L> as *mut Node<T, L>;
+        let mut head = self.retired.load(Ordering::Relaxed);
+        loop {
+            unsafe { *node.next_retired.get() = head; }
+            match self.retired.compare_exchange_weak(
+                head, node_ptr, Ordering::Release, Ordering::Relaxed
+            ) {
+                Ok(_) => return,
+                Err(current) => head = current,
+            }
+        }
+    }
+}
+
+impl<T: Hash, L: Lock> Drop for LazyListSet<T, L> {
+    fn drop(&mut self) {
+        self.reclaim();
+        let mut curr = *self.head.next.get_mut();
+        while !curr.is_null() {
+            let mut node = unsafe { Box::from_raw(curr) };
+            curr = *node.next.get_mut();
+        }
+    }
+}
+
+impl<T: Hash, L: Lock> Set<T> for LazyListSet<T, L> {
+    // wait-free: never takes a lock and never retries
+    fn contains(&self, element: T) -> bool {
+        let key = Hashable::hash(&element);
+        let (_, curr) = self.search(key);
+        curr.is_some_and(|curr| curr.key() == key && !curr.marked.load(Ordering::Acquire))
+    }
+}
+
+impl<T: Hash, L: Lock + Default> ConcurrentSet<T> for LazyListSet<T, L> {
+    fn add(&self, element: T) -> bool {
+        let key = Hashable::hash(&element);
+        loop {
+            let (pred, curr) = self.search(key);
+            let pred_guard = pred.lock.acquire();
+            let curr_guard = curr.map(|curr| curr.lock.acquire());
+            if !Self::valid(pred, curr) {
+                drop((curr_guard, pred_guard));
+                self.help_unlink(key);
+                continue;
+            }
+            if curr.is_some_and(|curr| curr.key() == key) { return false; }
+            let next = curr.map_or(ptr::null_mut(), |curr| curr as *const _ as *mut _);
+            let node = Node::new(Some(Hashed::new(element)), next, L::default());
+            pred.next.store(Box::into_raw(Box::new(node)), Ordering::Release);
+            self.size.record_add();
+            return true;
+        }
+    }
+    fn remove(&self, element: T) -> bool {
+        let key = Hashable::hash(&element);
+        loop {
+            let (pred, curr) = self.search(key);
+            let pred_guard = pred.lock.acquire();
+            let curr_guard = curr.map(|curr| curr.lock.acquire());
+            if !Self::valid(pred, curr) {
+                drop((curr_guard, pred_guard));
+                self.help_unlink(key);
+                continue;
+            }
+            let curr = match curr {
+                Some(curr) if curr.key() == key => curr,
+                _ => return false,
+            };
+            curr.marked.store(true, Ordering::Release);
+            if self.policy == UnlinkPolicy::Eager {
+                pred.next.store(curr.next.load(Ordering::Acquire), Ordering::Release);
+                self.retire(curr);
+            }
+            self.size.record_remove();
+            return true;
+        }
+    }
+}
+
+impl<T: Hash, L: Lock + Default> MutSet<T> for LazyListSet<T, L> {
+    fn add(&mut self, element: T) -> bool { ConcurrentSet::add(&*self, element) }
+    fn remove(&mut self, element: T) -> bool { ConcurrentSet::remove(&*self, element) }
+}
@@ -0,0 +1,238 @@
+use alloc::boxed::Box;
+use core::cell::UnsafeCell;
+use core::hash::Hash;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+use crate::{atomic::AtomicMarkable, hash::{Hashed, Hashable}};
+
+use super::{ConcurrentSet, MutSet, Set, UnlinkPolicy};
+
+struct Node<T: Hash> {
+    item: Option<Hashed<T>>,
+    // marked once the node is logically removed
+    next: AtomicMarkable<Node<T>>,
+    // only set once the node is unlinked; next stays intact for readers
+    next_retired: UnsafeCell<*mut Node<T>>,
+}
+
+impl<T: Hash> Node<T> {
+    fn new(item: Option<Hashed<T>>, next: *mut Self) -> Self {
+        Node {
+            item,
+            next: AtomicMarkable::new(next, false),
+            next_retired: UnsafeCell::new(ptr::null_mut()),
+        }
+    }
+    fn key(&self) -> u64 {
+        self.item.as_ref().expect("LockFreeListSet in invalid state").hash()
+    }
+}
+
+struct Window<'a, T: Hash> {
+    pred: &'a Node<T>,
+    curr: *mut Node<T>,
+}
+
+// Harris and Michael's list: a remove first marks the node's next pointer,
+// which stops anyone linking after it, and then tries to swing the
+// predecessor past it; searches finish any unlinking they run into. The
+// thread whose CAS unlinks a node retires it, and retired nodes are only
+// freed on reclaim or drop, when no other thread can be reading.
+pub struct LockFreeListSet<T: Hash> {
+    head: Node<T>,
+    retired: AtomicPtr<Node<T>>,
+    policy: UnlinkPolicy,
+}
+
+unsafe impl<T: Hash + Send> Send for LockFreeListSet<T> {}
+unsafe impl<T: Hash + Send + Sync> Sync for LockFreeListSet<T> {}
+
+impl<T: Hash> LockFreeListSet<T> {
+    pub fn new() -> Self {
+        LockFreeListSet {
+            head: Node::new(None, ptr::null_mut()),
+            retired: AtomicPtr::new(ptr::null_mut()),
+            policy: UnlinkPolicy::Eager,
+        }
+    }
+    pub fn with_unlink_policy(mut self, policy: UnlinkPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+    // walks the list, so only exact when nothing is changing
+    pub fn len(&self) -> usize {
+        let mut len = 0;
+        let (mut curr, _) = self.head.next.load(Ordering::Acquire);
+        while let Some(node) = unsafe { curr.as_ref() } {
+            let (next, marked) = node.next.load(Ordering::Acquire);
+            if !marked { len += 1; }
+            curr = next;
+        }
+        len
+    }
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+    // frees unlinked nodes; needs &mut, since that rules out readers
+    pub fn reclaim(&mut self) -> usize {
+        let mut curr = *self.retired.get_mut();
+        *self.retired.get_mut() = ptr::null_mut();
+        let mut freed = 0;
+        while !curr.is_null() {
+            let node = unsafe { Box::from_raw(curr) };
+            curr = node.next_retired.into_inner();
+            freed += 1;
+        }
+        freed
+    }
+    // unlinks up to max marked nodes, returning how many it unlinked; the
+    // CAS only succeeds while pred is unmarked, so it can't drop a live node
+    pub fn purge(&self, max: usize) -> usize {
+        let mut purged = 0;
+        'retry: while purged < max {
+            let mut pred = &self.head;
+            let (mut curr, _) = pred.next.load(Ordering::Acquire);
+            while let Some(node) = unsafe { curr.as_ref() } {
+                let (succ, marked) = node.next.load(Ordering::Acquire);
+                if !marked {
+                    (pred, curr) = (node, succ);
+                    continue;
+                }
+                if pred.next.compare_exchange(
+                    (curr, false), (succ, false), Ordering::AcqRel, Ordering::Acquire
+                ).is_err() {
+                    continue 'retry;
+                }
+                self.retire(node);
+                purged += 1;
+                if purged == max { break; }
+                curr = succ;
+            }
+            break;
+        }
+        purged
+    }
+    // the last unmarked node below key and the first node at or above it,
+    // unlinking marked nodes on the way
+    fn find(&self, key: u64) -> Window<'_, T> {
+        'retry: loop {
+            let mut pred = &self.head;
+            let (mut curr, _) = pred.next.load(Ordering::Acquire);
+            loop {
+                let node = match unsafe { curr.as_ref() } {
+                    Some(node) => node,
+                    None => return Window { pred, curr },
+                };
+                let (succ, marked) = node.next.load(Ordering::Acquire);
+                if marked {
+                    // fails if pred was marked or changed meanwhile
+                    if pred.next.compare_exchange(
+                        (curr, false), (succ, false), Ordering::AcqRel, Ordering::Acquire
+                    ).is_err() {
+                        continue 'retry;
+                    }
+                    self.retire(node);
+                    curr = succ;
+                } else {
+                    if node.key() >= key { return Window { pred, curr }; }
+                    (pred, curr) = (node, succ);
+                }
+            }
+        }
+    }
+    fn retire(&self, node: &Node<T>) {
+        let node_ptr = node as *const Node<T> as *mut Node<T>;
+        let mut head = self.retired.load(Ordering::Relaxed);
+        loop {
+            unsafe { *node.next_retired.get() = head; }
+            match self.retired.compare_exchange_weak(
+                head, node_ptr, Ordering::Release, Ordering::Relaxed
+            ) {
+                Ok(_) => return,
+                Err(current) => head = current,
+            }
+        }
+    }
+}
+
+impl<T: Hash> Default for LockFreeListSet<T> {
+    fn default() -> Self { Self::new() }
+}
+
+impl<T: Hash> Drop for LockFreeListSet<T> {
+    fn drop(&mut self) {
+        self.reclaim();
+        let (mut curr, _) = self.head.next.get_mut();
+        while !curr.is_null() {
+            let mut node = unsafe { Box::from_raw(curr) };
+            curr = node.next.get_mut().0;
+        }
+    }
+}
+
+impl<T: Hash> Set<T> for LockFreeListSet<T> {
+    // never writes, so it never helps and never retries
+    fn contains(&self, element: T) -> bool {
+        let key = Hashable::hash(&element);
+        let (mut curr, _) = self.head.next.load(Ordering::Acquire);
+        while let Some(node) = unsafe { curr.as_ref() } {
+            let (next, marked) = node.next.load(Ordering::Acquire);
+            if node.key() >= key { return node.key() == key && !marked; }
+            curr = next;
+        }
+        false
+    }
+}
+
+impl<T: Hash> ConcurrentSet<T> for LockFreeListSet<T> {
+    fn add(&self, element: T) -> bool {
+        let key = Hashable::hash(&element);
+        let node = Node::new(Some(Hashed::new(element)), ptr::null_mut());
+        let node = Box::into_raw(Box::new(node));
+        loop {
+            let Window { pred, curr } = self.find(key);
+            if unsafe { curr.as_ref() }.is_some_and(|curr| curr.key() == key) {
+                unsafe { drop(Box::from_raw(node)); }
+                return false;
+            }
+            unsafe { (*node).next.store(curr, false, Ordering::Relaxed); }
+            if pred.next.compare_exchange(
+                (curr, false), (node, false), Ordering::AcqRel, Ordering::Acquire
+            ).is_ok() {
+                return true;
+            }
+        }
+    }
+    fn remove(&self, element: T) -> bool {
+        let key = Hashable::hash(&element);
+        loop {
+            let Window { pred, curr } = self.find(key);
+            let node = match unsafe { curr.as_ref() } {
+                Some(node) if node.key() == key => node,
+                _ => return false,
+            };
+            let (succ, marked) = node.next.load(Ordering::Acquire);
+            if marked { continue; }
+            // the mark is the linearization point of the remove
+            if node.next.compare_exchange(
+                (succ, false), (succ, true), Ordering::AcqRel, Ordering::Acquire
+            ).is_err() {
+                continue;
+            }
+            if self.policy == UnlinkPolicy::Deferred { return true; }
+            if pred.next.compare_exchange(
+                (curr, false), (succ, false), Ordering::AcqRel, Ordering::Acquire
+            ).is_ok() {
+                self.retire(node);
+            } else {
+                // someone got in between; a search unlinks it for us
+                self.find(key);
+            }
+            return true;
+        }
+    }
+}
+
+impl<T: Hash> MutSet<T> for LockFreeListSet<T> {
+    fn add(&mut self, element: T) -> bool { ConcurrentSet::add(&*self, element) }
+    fn remove(&mut self, element: T) -> bool { ConcurrentSet::remove(&*self, element) }
+}
@@ -0,0 +1,149 @@
+use alloc::boxed::Box;
+use core::hash::Hash;
+use core::hint::spin_loop;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, AtomicU32, Ordering};
+
+use crate::{atomic::SnapshotPair, lock::Lock, hash::{Hashed, Hashable}};
+
+use super::{ConcurrentSet, Set};
+
+struct Node<T: Hash> {
+    item: Hashed<T>,
+    next: AtomicPtr<Node<T>>,
+    readers: AtomicU32,
+}
+
+impl<T: Hash> Hashable for Node<T> {
+    fn hash(&self) -> u64 { self.item.hash() }
+}
+
+// Readers hold a count on the node they are standing on (or on the head)
+// and only give it up after counting themselves on the next node, so a
+// writer that waits for the predecessor and then the node itself to drain
+// knows no reader can still reach the unlinked node. An add waits for its
+// predecessor too: a reader counted there may have loaded the old next
+// pointer, skipping the new node, and a later remove of that old successor
+// would only wait on the new node. Those waits are not bounded: a steady
+// stream of readers passing a node can keep a writer, and every writer
+// queued behind its lock, waiting indefinitely.
+pub struct RefCountListSet<T: Hash, L: Lock> {
+    head: AtomicPtr<Node<T>>,
+    head_readers: AtomicU32,
+    lock: L,
+    // updated under the lock, so removes never overtake their adds
+    size: SnapshotPair,
+}
+
+unsafe impl<T: Hash + Send, L: Lock + Send> Send for RefCountListSet<T, L> {}
+unsafe impl<T: Hash + Send + Sync, L: Lock> Sync for RefCountListSet<T, L> {}
+
+impl<T: Hash, L: Lock> RefCountListSet<T, L> {
+    pub fn new(lock: L) -> Self {
+        RefCountListSet {
+            head: AtomicPtr::new(ptr::null_mut()),
+            head_readers: AtomicU32::new(0),
+            lock,
+            size: SnapshotPair::new(),
+        }
+    }
+    pub fn len(&self) -> usize { self.size.len() }
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+    pub fn make_key(&self, h: &impl Hash) -> u64 { Hashable::hash(h) }
+    pub fn contains_by_key(&self, key: u64, eq: impl Fn(&T) -> bool) -> bool {
+        self.visit(key, |item| eq(item)).unwrap_or(false)
+    }
+    pub fn get_by_key(&self, key: u64, eq: impl Fn(&T) -> bool) -> Option<T>
+    where T: Clone {
+        self.visit(key, |item| eq(item).then(|| item.clone())).flatten()
+    }
+    // calls f on the item stored under key while counted on its node
+    fn visit<R>(&self, key: u64, f: impl FnOnce(&T) -> R) -> Option<R> {
+        let mut readers = &self.head_readers;
+        readers.fetch_add(1, Ordering::SeqCst);
+        let mut curr = self.head.load(Ordering::SeqCst);
+        let result = loop {
+            let node = match unsafe { curr.as_ref() } {
+                Some(node) => node,
+                None => break None,
+            };
+            node.readers.fetch_add(1, Ordering::SeqCst);
+            readers.fetch_sub(1, Ordering::SeqCst);
+            readers = &node.readers;
+            if node.hash() > key { break None; }
+            if node.hash() == key { break Some(f(node.item.item())); }
+            curr = node.next.load(Ordering::SeqCst);
+        };
+        readers.fetch_sub(1, Ordering::SeqCst);
+        result
+    }
+    // must be called with the lock held: only writers change the links
+    fn find(&self, key: u64) -> (&AtomicPtr<Node<T>>, &AtomicU32, *mut Node<T>) {
+        let (mut next, mut readers) = (&self.head, &self.head_readers);
+        loop {
+            let curr = next.load(Ordering::Acquire);
+            match unsafe { curr.as_ref() } {
+                Some(node) if node.hash() < key => {
+                    (next, readers) = (&node.next, &node.readers);
+                },
+                _ => return (next, readers, curr),
+            }
+        }
+    }
+}
+
+impl<T: Hash, L: Lock> Drop for RefCountListSet<T, L> {
+    fn drop(&mut self) {
+        let mut curr = *self.head.get_mut();
+        while !curr.is_null() {
+            let node = unsafe { Box::from_raw(curr) };
+            curr = node.next.load(Ordering::Relaxed);
+        }
+    }
+}
+
+impl<T: Hash, L: Lock> Set<T> for RefCountListSet<T, L> {
+    fn contains(&self, element: T) -> bool {
+        let key = Hashable::hash(&element);
+        self.visit(key, |_| ()).is_some()
+    }
+}
+
+impl<T: Hash, L: Lock> ConcurrentSet<T> for RefCountListSet<T, L> {
+    fn add(&self, element: T) -> bool {
+        let key = Hashable::hash(&element);
+        let _guard = self.lock.acquire();
+        let (next, pred_readers, curr) = self.find(key);
+        if unsafe { curr.as_ref() }.is_some_and(|node| node.hash() == key) {
+            return false;
+        }
+        let node = Node {
+            item: Hashed::new(element),
+            next: AtomicPtr::new(curr),
+            readers: AtomicU32::new(0),
+        };
+        next.store(Box::into_raw(Box::new(node)), Ordering::SeqCst);
+        self.size.record_add();
+        // readers that passed the predecessor before the store must have
+        // moved on to curr, where a remove of curr will wait for them
+        while pred_readers.load(Ordering::SeqCst) != 0 { spin_loop(); }
+        true
+    }
+    fn remove(&self, element: T) -> bool {
+        let key = Hashable::hash(&element);
+        let _guard = self.lock.acquire();
+        let (next, pred_readers, curr) = self.find(key);
+        let node = match unsafe { curr.as_ref() } {
+            Some(node) if node.hash() == key => node,
+            _ => return false,
+        };
+        next.store(node.next.load(Ordering::Acquire), Ordering::SeqCst);
+        self.size.record_remove();
+        // a reader that loaded curr before the unlink may not have counted
+        // itself on it yet, but it is still counted on the predecessor
+        while pred_readers.load(Ordering::SeqCst) != 0 { spin_loop(); }
+        while node.readers.load(Ordering::SeqCst) != 0 { spin_loop(); }
+        unsafe { drop(Box::from_raw(curr)); }
+        true
+    }
+}
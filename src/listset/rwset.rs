@@ -0,0 +1,48 @@
+use core::cell::UnsafeCell;
+use core::hash::Hash;
+
+use crate::lock::RwLock;
+
+use super::{ConcurrentSet, MutSet, SeqListSet, Set};
+
+// like CoarseListSet, but lookups share the lock with each other
+pub struct RwListSet<T: Hash, L: RwLock> {
+    seq: UnsafeCell<SeqListSet<T>>,
+    lock: L,
+}
+
+unsafe impl<T: Hash + Send + Sync, L: RwLock> Sync for RwListSet<T, L> {}
+
+impl<T: Hash, L: RwLock> RwListSet<T, L> {
+    pub fn new(lock: L) -> Self {
+        RwListSet { seq: UnsafeCell::new(SeqListSet::new()), lock }
+    }
+    pub fn len(&self) -> usize {
+        let _guard = self.lock.acquire_read();
+        unsafe { &*self.seq.get() }.len()
+    }
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+}
+
+impl<T: Hash, L: RwLock> Set<T> for RwListSet<T, L> {
+    fn contains(&self, element: T) -> bool {
+        let _guard = self.lock.acquire_read();
+        unsafe { &*self.seq.get() }.contains(element)
+    }
+}
+
+impl<T: Hash, L: RwLock> ConcurrentSet<T> for RwListSet<T, L> {
+    fn add(&self, element: T) -> bool {
+        let _guard = self.lock.acquire_write();
+        unsafe { &mut *self.seq.get() }.add(element)
+    }
+    fn remove(&self, element: T) -> bool {
+        let _guard = self.lock.acquire_write();
+        unsafe { &mut *self.seq.get() }.remove(element)
+    }
+}
+
+impl<T: Hash, L: RwLock> MutSet<T> for RwListSet<T, L> {
+    fn add(&mut self, element: T) -> bool { ConcurrentSet::add(&*self, element) }
+    fn remove(&mut self, element: T) -> bool { ConcurrentSet::remove(&*self, element) }
+}
@@ -0,0 +1,182 @@
+use std::cell::UnsafeCell;
+use std::hash::Hash;
+
+use rand::random;
+
+use crate::{lock::Lock, hash::{Hashed, Hashable}};
+
+use super::{ConcurrentSet, Set};
+
+struct Node<T: Hash> {
+    item: Hashed<T>,
+    next: Vec<Option<usize>>,
+}
+
+// nodes live in an arena and link to each other by index; None is the
+// head when used as a predecessor and the end of the level as a successor
+struct SeqSkipList<T: Hash> {
+    head: Vec<Option<usize>>,
+    nodes: Vec<Option<Node<T>>>,
+    free: Vec<usize>,
+    seed: u64,
+}
+
+impl<T: Hash> SeqSkipList<T> {
+    fn new(max_height: usize, seed: u64) -> Self {
+        assert!(max_height > 0, "skiplist needs at least one level");
+        SeqSkipList {
+            head: vec![None; max_height],
+            nodes: Vec::new(),
+            free: Vec::new(),
+            seed: seed | 1,
+        }
+    }
+    fn node(&self, index: usize) -> &Node<T> {
+        self.nodes[index].as_ref().expect("SkipListSet in invalid state")
+    }
+    fn next(&self, at: Option<usize>, level: usize) -> Option<usize> {
+        match at {
+            Some(index) => self.node(index).next[level],
+            None => self.head[level],
+        }
+    }
+    fn set_next(&mut self, at: Option<usize>, level: usize, to: Option<usize>) {
+        match at {
+            Some(index) => {
+                let node = self.nodes[index].as_mut()
+                    .expect("SkipListSet in invalid state");
+                node.next[level] = to;
+            },
+            None => self.head[level] = to,
+        }
+    }
+    // xorshift64*, so a fixed seed gives a reproducible shape
+    fn random_height(&mut self) -> usize {
+        self.seed ^= self.seed >> 12;
+        self.seed ^= self.seed << 25;
+        self.seed ^= self.seed >> 27;
+        let bits = self.seed.wrapping_mul(0x2545_f491_4f6c_dd1d);
+        (bits.trailing_ones() as usize + 1).min(self.head.len())
+    }
+    fn find(&self, key: u64) -> (Vec<Option<usize>>, Option<usize>) {
+        let mut preds = vec![None; self.head.len()];
+        let mut pred = None;
+        for level in (0..self.head.len()).rev() {
+            while let Some(next) = self.next(pred, level) {
+                if self.node(next).item.hash() >= key { break; }
+                pred = Some(next);
+            }
+            preds[level] = pred;
+        }
+        let found = self.next(pred, 0)
+            .filter(|&index| self.node(index).item.hash() == key);
+        (preds, found)
+    }
+    fn level(&self, level: usize) -> Vec<usize> {
+        let mut indices = Vec::new();
+        let mut at = None;
+        while let Some(next) = self.next(at, level) {
+            indices.push(next);
+            at = Some(next);
+        }
+        indices
+    }
+    fn contains(&self, key: u64) -> bool { self.find(key).1.is_some() }
+    fn len(&self) -> usize { self.nodes.len() - self.free.len() }
+    fn add(&mut self, element: T) -> bool {
+        let key = Hashable::hash(&element);
+        let (preds, found) = self.find(key);
+        if found.is_some() { return false; }
+        let height = self.random_height();
+        let next = (0..height).map(|level| self.next(preds[level], level))
+            .collect();
+        let node = Node { item: Hashed::new(element), next };
+        let index = match self.free.pop() {
+            Some(index) => { self.nodes[index] = Some(node); index },
+            None => { self.nodes.push(Some(node)); self.nodes.len() - 1 },
+        };
+        for (level, &pred) in preds.iter().enumerate().take(height) {
+            self.set_next(pred, level, Some(index));
+        }
+        true
+    }
+    fn remove(&mut self, key: u64) -> bool {
+        let (preds, found) = self.find(key);
+        let index = match found {
+            Some(index) => index,
+            None => return false,
+        };
+        let node = self.nodes[index].take().expect("SkipListSet in invalid state");
+        for (level, &next) in node.next.iter().enumerate() {
+            self.set_next(preds[level], level, next);
+        }
+        self.free.push(index);
+        true
+    }
+}
+
+pub struct SkipListSet<T: Hash, L: Lock> {
+    seq: UnsafeCell<SeqSkipList<T>>,
+    lock: L,
+}
+
+unsafe impl<T: Hash + Send, L: Lock> Sync for SkipListSet<T, L> {}
+
+impl<T: Hash, L: Lock> SkipListSet<T, L> {
+    pub fn new(lock: L, max_height: usize) -> Self {
+        Self::with_seed(lock, max_height, random())
+    }
+    pub fn with_seed(lock: L, max_height: usize, seed: u64) -> Self {
+        SkipListSet { seq: UnsafeCell::new(SeqSkipList::new(max_height, seed)), lock }
+    }
+    pub fn len(&self) -> usize {
+        let _guard = self.lock.acquire();
+        unsafe { &*self.seq.get() }.len()
+    }
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+    // how many nodes each level links, bottom level first
+    pub fn level_lens(&self) -> Vec<usize> {
+        let _guard = self.lock.acquire();
+        let seq = unsafe { &*self.seq.get() };
+        (0..seq.head.len()).map(|level| seq.level(level).len()).collect()
+    }
+    // panics unless every level is in strictly increasing key order and
+    // links exactly the nodes tall enough to reach it
+    #[cfg(feature = "testing")]
+    pub fn check_invariants(&self) {
+        let _guard = self.lock.acquire();
+        let seq = unsafe { &*self.seq.get() };
+        let mut below = seq.level(0);
+        assert_eq!(below.len(), seq.len(), "level 0 misses nodes");
+        for level in 0..seq.head.len() {
+            let indices = seq.level(level);
+            let keys: Vec<_> = indices.iter().map(|&index| seq.node(index).item.hash()).collect();
+            assert!(keys.windows(2).all(|pair| pair[0] < pair[1]), "level {} out of order", level);
+            let reaching: Vec<_> = below.iter().copied()
+                .filter(|&index| seq.node(index).next.len() > level)
+                .collect();
+            assert_eq!(indices, reaching, "level {} doesn't link the nodes that reach it", level);
+            below = indices;
+        }
+    }
+}
+
+impl<T: Hash, L: Lock> Set<T> for SkipListSet<T, L> {
+    fn contains(&self, element: T) -> bool {
+        let key = Hashable::hash(&element);
+        let _guard = self.lock.acquire();
+        unsafe { &*self.seq.get() }.contains(key)
+    }
+}
+
+impl<T: Hash, L: Lock> ConcurrentSet<T> for SkipListSet<T, L> {
+    fn add(&self, element: T) -> bool {
+        let _guard = self.lock.acquire();
+        unsafe { &mut *self.seq.get() }.add(element)
+    }
+    fn remove(&self, element: T) -> bool {
+        let key = Hashable::hash(&element);
+        let _guard = self.lock.acquire();
+        unsafe { &mut *self.seq.get() }.remove(key)
+    }
+}
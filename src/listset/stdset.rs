@@ -0,0 +1,44 @@
+use std::cell::UnsafeCell;
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use crate::lock::Lock;
+
+use super::{ConcurrentSet, Set};
+
+pub struct StdSet<T, L: Lock> {
+    set: UnsafeCell<HashSet<T>>,
+    lock: L,
+}
+
+unsafe impl<T: Send, L: Lock> Sync for StdSet<T, L> {}
+
+impl<T: Hash + Eq, L: Lock> StdSet<T, L> {
+    pub fn new(lock: L) -> Self {
+        StdSet { set: UnsafeCell::new(HashSet::new()), lock }
+    }
+    pub fn len(&self) -> usize {
+        let _guard = self.lock.acquire();
+        unsafe { &*self.set.get() }.len()
+    }
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+    pub fn into_inner(self) -> HashSet<T> { self.set.into_inner() }
+}
+
+impl<T: Hash + Eq, L: Lock> Set<T> for StdSet<T, L> {
+    fn contains(&self, element: T) -> bool {
+        let _guard = self.lock.acquire();
+        unsafe { &*self.set.get() }.contains(&element)
+    }
+}
+
+impl<T: Hash + Eq, L: Lock> ConcurrentSet<T> for StdSet<T, L> {
+    fn add(&self, element: T) -> bool {
+        let _guard = self.lock.acquire();
+        unsafe { &mut *self.set.get() }.insert(element)
+    }
+    fn remove(&self, element: T) -> bool {
+        let _guard = self.lock.acquire();
+        unsafe { &mut *self.set.get() }.remove(&element)
+    }
+}
@@ -1,27 +1,116 @@
-use std::sync::atomic::{AtomicBool, Ordering, AtomicUsize, AtomicPtr};
-use std::time::Duration;
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+#[cfg(feature = "std")]
+use std::panic::{self, AssertUnwindSafe};
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
 
-use crate::backoff::Backoff;
+#[cfg(feature = "std")]
+use crate::{backoff::Backoff, clock::RealClock, error::TimedOut};
+use crate::{
+    atomic::AtomicMarkable, backoff::SpinBackoff, chaos::{self, Site}, error::BorrowError,
+    pad::CachePadded,
+    sync::{atomic::{AtomicBool, AtomicUsize, Ordering}, const_fn, spin_loop, yield_now},
+};
+
+#[cfg(feature = "std")]
+mod backoff;
+#[cfg(feature = "std")]
+mod condvar;
+mod mutex;
+#[cfg(all(feature = "lock_api", not(loom)))]
+mod raw;
+mod rwlock;
+#[cfg(feature = "stats")]
+mod stats;
+
+#[cfg(feature = "std")]
+pub use backoff::BackoffLock;
+#[cfg(feature = "std")]
+pub use condvar::Condvar;
+#[cfg(feature = "std")]
+pub(crate) use condvar::WaitQueue;
+pub use mutex::{Mutex, MutexGuard};
+pub use rwlock::{RwLock, RwPolicy, RwSpinLock, RwSpinReadGuard, RwSpinWriteGuard};
+#[cfg(feature = "stats")]
+pub use stats::{Instrumented, InstrumentedGuard, LockStats, StatsSnapshot, WAIT_BUCKETS};
 
 pub trait Lock: Sized + Sync {
     type Guard<'a> where Self: 'a;
     fn acquire(&self) -> Self::Guard<'_>;
+    fn acquire_owned(self: &Arc<Self>) -> OwnedGuard<Self> where Self: 'static {
+        let lock = self.clone();
+        // the Arc stored next to the guard keeps the lock where it is for
+        // as long as the guard exists, which is all 'static has to mean
+        let guard = unsafe { &*Arc::as_ptr(&lock) }.acquire();
+        OwnedGuard { guard: ManuallyDrop::new(guard), lock }
+    }
+}
+
+// A single attempt that never waits for the current holder. It may fail
+// even though the lock is free at that moment, but it returns right away.
+pub trait TryLock: Lock {
+    fn try_acquire(&self) -> Option<Self::Guard<'_>>;
+    // retries try_acquire, spinning at first and then yielding, until
+    // the timeout has passed
+    #[cfg(feature = "std")]
+    fn acquire_timeout(&self, timeout: Duration) -> Result<Self::Guard<'_>, TimedOut> {
+        let deadline = Instant::now() + timeout;
+        let mut backoff = SpinBackoff::new();
+        loop {
+            if let Some(guard) = self.try_acquire() { return Ok(guard); }
+            if Instant::now() >= deadline { return Err(TimedOut); }
+            backoff.snooze();
+        }
+    }
+}
+
+// not tied to a borrow of the lock, so it can be moved into threads that
+// are not scoped
+pub struct OwnedGuard<L: Lock + 'static> {
+    guard: ManuallyDrop<L::Guard<'static>>,
+    lock: Arc<L>,
+}
+
+impl<L: Lock + 'static> OwnedGuard<L> {
+    pub fn lock(&self) -> &Arc<L> { &self.lock }
+}
+
+impl<L: Lock + 'static> Drop for OwnedGuard<L> {
+    fn drop(&mut self) {
+        // release before the Arc can let go of the lock
+        unsafe { ManuallyDrop::drop(&mut self.guard); }
+    }
 }
 
 pub struct TASLock { locked: AtomicBool }
 pub struct TASGuard<'a> { lock: &'a TASLock }
 
 impl TASLock {
-    pub fn new() -> Self {
-        TASLock { locked: AtomicBool::new(false) }
+    const_fn! {
+        pub fn new() -> Self { TASLock { locked: AtomicBool::new(false) } }
     }
 }
 
+impl Default for TASLock {
+    fn default() -> Self { Self::new() }
+}
+
 impl Lock for TASLock {
     type Guard<'a> = TASGuard<'a>;
     fn acquire(&self) -> Self::Guard<'_> {
-        while self.locked.swap(true, Ordering::Acquire) {};
-        TASGuard { lock: &self }
+        while self.locked.swap(true, Ordering::Acquire) { spin_loop(); }
+        TASGuard { lock: self }
+    }
+}
+
+impl TryLock for TASLock {
+    fn try_acquire(&self) -> Option<Self::Guard<'_>> {
+        (!self.locked.swap(true, Ordering::Acquire)).then(|| TASGuard { lock: self })
     }
 }
 
@@ -34,139 +123,479 @@ impl Drop for TASGuard<'_> {
 pub struct TTASLock(TASLock);
 
 impl TTASLock {
-    pub fn new() -> Self { TTASLock(TASLock::new()) }
-    fn try_lock(&self) -> bool {
-        while self.0.locked.load(Ordering::Acquire) {};
+    const_fn! {
+        pub fn new() -> Self { TTASLock(TASLock::new()) }
+    }
+    // waits for the lock to look free, then tries once
+    fn wait_and_swap(&self) -> bool {
+        let mut backoff = SpinBackoff::new();
+        while self.0.locked.load(Ordering::Acquire) { backoff.snooze(); }
+        chaos::maybe_pause(Site::TtasSwap);
         !self.0.locked.swap(true, Ordering::Acquire)
     }
+    // calls heartbeat about every `every` while waiting, on this thread;
+    // heartbeat must not acquire this lock
+    #[cfg(feature = "std")]
+    pub fn acquire_with_heartbeat(&self, every: Duration, mut heartbeat: impl FnMut()) -> TASGuard<'_> {
+        let mut last = Instant::now();
+        loop {
+            if !self.0.locked.load(Ordering::Acquire)
+                && !self.0.locked.swap(true, Ordering::Acquire) {
+                return TASGuard { lock: &self.0 };
+            }
+            spin_loop();
+            if last.elapsed() >= every {
+                heartbeat();
+                last = Instant::now();
+            }
+        }
+    }
+}
+
+impl Default for TTASLock {
+    fn default() -> Self { Self::new() }
 }
 
 impl Lock for TTASLock {
     type Guard<'a> = TASGuard<'a>;
     fn acquire(&self) -> Self::Guard<'_> {
-        while !self.try_lock() {};
+        while !self.wait_and_swap() {};
         TASGuard { lock: &self.0 }
     }
 }
 
-pub struct BackoffLock {
-    ttas: TTASLock,
-    min_delay: Duration,
-    max_delay: Duration,
-}
-
-impl BackoffLock {
-    pub fn new() -> Self {
-        BackoffLock {
-            ttas: TTASLock::new(),
-            min_delay: Duration::from_millis(1),
-            max_delay: Duration::from_millis(1000),
-        }
+impl TryLock for TTASLock {
+    fn try_acquire(&self) -> Option<Self::Guard<'_>> {
+        if self.0.locked.load(Ordering::Relaxed) { return None; }
+        self.0.try_acquire()
     }
 }
 
-impl Lock for BackoffLock {
-    type Guard<'a> = TASGuard<'a>;
-    fn acquire(&self) -> Self::Guard<'_> {
-        let mut backoff = Backoff::new(self.min_delay, self.max_delay);
-        while !self.ttas.try_lock() { backoff.backoff(); }
-        TASGuard { lock: &self.ttas.0 }
-    }
-}
+// each waiter spins on its own flag, so every flag gets its own cache line
+pub type Flag = CachePadded<AtomicBool>;
 
-pub struct ArrayLock {
-    flags: Box<[AtomicBool]>,
+pub struct ArrayLock<F = Box<[Flag]>> {
+    flags: F,
     next_slot: AtomicUsize,
     guards_left: AtomicUsize,
 }
 
-pub struct ArrayGuard<'a> {
-    lock: &'a ArrayLock,
+#[cfg(feature = "std")]
+const CAPACITY_MIN_DELAY: Duration = Duration::from_micros(1);
+#[cfg(feature = "std")]
+const CAPACITY_MAX_DELAY: Duration = Duration::from_millis(1);
+
+// lives entirely inline, so it can be placed in a static
+pub type StaticArrayLock<const N: usize> = ArrayLock<[Flag; N]>;
+
+pub struct ArrayGuard<'a, F: Flags = Box<[Flag]>> {
+    lock: &'a ArrayLock<F>,
     slot: usize,
 }
 
+pub trait Flags: AsRef<[Flag]> + Sync {}
+
+impl<F: AsRef<[Flag]> + Sync> Flags for F {}
+
 impl ArrayLock {
-    // ArrayLock is only designed to work with a bounded number of threads
+    // ArrayLock is only designed to work with a bounded number of threads;
+    // the capacity is max_threads rounded up to a power of two
     pub fn new(max_threads: usize) -> Self {
-        let mut flags: Vec<AtomicBool> = Vec::with_capacity(max_threads);
-        flags.push(AtomicBool::new(true));
-        for _ in 1..max_threads { flags.push(AtomicBool::new(false)); }
+        let capacity = max_threads.next_power_of_two();
+        let mut flags: Vec<Flag> = Vec::with_capacity(capacity);
+        flags.push(Flag::new(AtomicBool::new(true)));
+        for _ in 1..capacity { flags.push(Flag::new(AtomicBool::new(false))); }
         ArrayLock {
             flags: flags.into_boxed_slice(),
             next_slot: AtomicUsize::new(0),
-            guards_left: AtomicUsize::new(max_threads),
+            guards_left: AtomicUsize::new(capacity),
         }
     }
-    pub fn capacity(&self) -> usize { self.flags.len() }
+}
+
+#[cfg(not(loom))]
+impl<const N: usize> StaticArrayLock<N> {
+    pub const fn new_static() -> Self {
+        assert!(N.is_power_of_two(), "StaticArrayLock needs a power of two slots");
+        let mut flags = [const { Flag::new(AtomicBool::new(false)) }; N];
+        flags[0] = Flag::new(AtomicBool::new(true));
+        ArrayLock {
+            flags,
+            next_slot: AtomicUsize::new(0),
+            guards_left: AtomicUsize::new(N),
+        }
+    }
+}
+
+// The slot counter runs freely and wraps at usize::MAX, which the modulo
+// only follows because the capacity is a power of two. A counter that
+// wrapped at the capacity instead would let try_acquire's compare-exchange
+// succeed on a stale slot once the others had gone all the way round.
+impl<F: Flags> ArrayLock<F> {
+    pub fn capacity(&self) -> usize { self.flags.as_ref().len() }
+    // starts the slot counter at slot rather than zero, so tests can get
+    // to the wrap without going through usize::MAX acquires
+    #[cfg(feature = "testing")]
+    pub fn starting_at(self, slot: usize) -> Self {
+        self.get_flag(0).store(false, Ordering::Relaxed);
+        self.get_flag(slot).store(true, Ordering::Relaxed);
+        self.next_slot.store(slot, Ordering::Relaxed);
+        self
+    }
     fn get_flag(&self, slot: usize) -> &AtomicBool {
         // index is always in bounds because of the modulo
-        unsafe { &self.flags.get_unchecked(slot % self.capacity()) }
+        unsafe { self.flags.as_ref().get_unchecked(slot % self.capacity()) }
+    }
+    pub fn acquire_checked(&self) -> Result<ArrayGuard<'_, F>, BorrowError> {
+        // using AcqRel on RMW operations ensures fairness
+        if !self.reserve_guard() { return Err(BorrowError::ThreadCapacityExceeded); }
+        let slot = self.next_slot.fetch_add(1, Ordering::AcqRel);
+        let mut backoff = SpinBackoff::new();
+        while !self.get_flag(slot).load(Ordering::Acquire) { backoff.snooze(); }
+        Ok(ArrayGuard { lock: self, slot })
+    }
+    // backs off while the lock is at capacity instead of failing
+    #[cfg(feature = "std")]
+    pub fn acquire_blocking(&self) -> ArrayGuard<'_, F> {
+        let mut backoff = Backoff::new_with_clock(
+            CAPACITY_MIN_DELAY, CAPACITY_MAX_DELAY, &RealClock
+        );
+        loop {
+            match self.acquire_checked() {
+                Ok(guard) => return guard,
+                Err(_) => backoff.backoff(),
+            }
+        }
+    }
+    // the timeout only covers waiting for room; once a slot is taken the
+    // call waits its turn like any other acquire
+    #[cfg(feature = "std")]
+    pub fn acquire_checked_timeout(
+        &self, timeout: Duration
+    ) -> Result<ArrayGuard<'_, F>, BorrowError> {
+        let deadline = Instant::now() + timeout;
+        let mut backoff = Backoff::new_with_clock(
+            CAPACITY_MIN_DELAY, CAPACITY_MAX_DELAY, &RealClock
+        );
+        loop {
+            match self.acquire_checked() {
+                Ok(guard) => return Ok(guard),
+                Err(_) if Instant::now() >= deadline => return Err(BorrowError::Timeout),
+                Err(_) => backoff.backoff(),
+            }
+        }
+    }
+    // never goes below zero, so a rejected acquire can't let another one
+    // through while it puts its guard back
+    fn reserve_guard(&self) -> bool {
+        self.guards_left.fetch_update(Ordering::AcqRel, Ordering::Acquire, |left| {
+            left.checked_sub(1)
+        }).is_ok()
     }
+    fn next(&self, slot: usize) -> usize { slot.wrapping_add(1) }
 }
 
-impl Lock for ArrayLock {
-    type Guard<'a> = ArrayGuard<'a>;
+impl<F: Flags> Lock for ArrayLock<F> {
+    type Guard<'a> = ArrayGuard<'a, F> where F: 'a;
     fn acquire(&self) -> Self::Guard<'_> {
-        // using AcqRel on RMW operations ensures fairness
-        if self.guards_left.fetch_sub(1, Ordering::AcqRel) == 0 {
-            self.guards_left.fetch_add(1, Ordering::Release);
-            panic!("too many threads trying to acquire ArrayLock");
+        self.acquire_checked().unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+impl<F: Flags> TryLock for ArrayLock<F> {
+    // only takes the next slot if its flag is already up, i.e. nobody
+    // holds the lock or is waiting for it
+    fn try_acquire(&self) -> Option<Self::Guard<'_>> {
+        let slot = self.next_slot.load(Ordering::Acquire);
+        if !self.get_flag(slot).load(Ordering::Acquire) { return None; }
+        if !self.reserve_guard() { return None; }
+        match self.next_slot.compare_exchange(
+            slot, self.next(slot), Ordering::AcqRel, Ordering::Relaxed
+        ) {
+            Ok(_) => Some(ArrayGuard { lock: self, slot }),
+            Err(_) => {
+                self.guards_left.fetch_add(1, Ordering::Release);
+                None
+            },
         }
-        let slot = self.next_slot.fetch_add(1, Ordering::AcqRel);
-        while !self.get_flag(slot).load(Ordering::Acquire) {};
-        ArrayGuard { lock: self, slot }
     }
 }
 
-impl Drop for ArrayGuard<'_> {
+impl<F: Flags> Drop for ArrayGuard<'_, F> {
     fn drop(&mut self) {
         self.lock.get_flag(self.slot).store(false, Ordering::Release);
+        chaos::maybe_pause(Site::ArrayRelease);
         // now self.slot is safe to be used by another thread
         self.lock.guards_left.fetch_add(1, Ordering::Release);
-        self.lock.get_flag(self.slot + 1).store(true, Ordering::Release);
+        chaos::maybe_pause(Site::ArrayRelease);
+        self.lock.get_flag(self.lock.next(self.slot)).store(true, Ordering::Release);
     }
 }
 
-pub struct CLHLock {
-    tail: AtomicPtr<AtomicBool>,
+// Each node can carry a value from the thread that releases it to the
+// thread that acquires next; CLHLock<()> is the plain lock.
+// The tail is marked once its node has been released, which is how
+// try_acquire can tell the lock is free without touching a node that a
+// successor may already have freed.
+pub struct CLHLock<T = ()> {
+    tail: AtomicMarkable<CLHNode<T>>,
+    waiting: AtomicUsize,
+    spin_limit: usize,
+    // values move between threads, so the lock is only Send/Sync for Send T
+    _values: PhantomData<*const T>,
 }
 
-pub struct CLHGuard<'a> {
-    lock: &'a CLHLock,
-    node: *mut AtomicBool,
+// aligned so the tail has a low bit to mark
+#[repr(align(2))]
+struct CLHNode<T> {
+    locked: AtomicBool,
+    value: UnsafeCell<Option<T>>,
 }
 
+pub struct CLHGuard<'a, T = ()> {
+    lock: &'a CLHLock<T>,
+    node: *mut CLHNode<T>,
+    inherited: Option<T>,
+}
+
+// CLHGuard holds a raw node pointer and so is not Send; converting it is
+// how a critical section gets handed to another thread. Releasing is a
+// single Release store on the node, which is correct from any thread.
+pub struct CLHSendGuard<'a, T = ()> { guard: CLHGuard<'a, T> }
+
+// A thread's own way into the lock that keeps the node it inherits from
+// its predecessor for its next acquire, as in the textbook CLH lock,
+// instead of freeing it and allocating a fresh one.
+pub struct CLHHandle<'a, T = ()> {
+    lock: &'a CLHLock<T>,
+    spare: Option<*mut CLHNode<T>>,
+}
+
+// a CLHHandle that can be moved into threads that are not scoped; its
+// guards are OwnedGuards
+pub struct OwnedCLHHandle<T: 'static = ()> {
+    handle: ManuallyDrop<CLHHandle<'static, T>>,
+    lock: Arc<CLHLock<T>>,
+}
+
+unsafe impl<T: Send> Send for CLHLock<T> {}
+unsafe impl<T: Send> Send for CLHHandle<'_, T> {}
+unsafe impl<T: Send> Sync for CLHLock<T> {}
+unsafe impl<T: Send> Send for CLHSendGuard<'_, T> {}
+
+impl<T> CLHNode<T> {
+    fn new(locked: bool) -> *mut Self {
+        let node = CLHNode { locked: AtomicBool::new(locked), value: UnsafeCell::new(None) };
+        Box::into_raw(Box::new(node))
+    }
+}
+
+// how long a waiter spins on its predecessor before it starts yielding,
+// so a preempted holder does not leave the whole queue burning CPU
+const CLH_SPIN_LIMIT: usize = 1 << 10;
+
 impl CLHLock {
-    pub fn new() -> Self {
-        let locked = AtomicBool::new(false);
-        let tail = Box::into_raw(Box::new(locked));
-        CLHLock { tail: AtomicPtr::new(tail) }
+    pub fn new() -> Self { CLHLock::with_handoff() }
+}
+
+impl<T> CLHLock<T> {
+    pub fn with_handoff() -> Self {
+        CLHLock {
+            tail: AtomicMarkable::new(CLHNode::new(false), true),
+            waiting: AtomicUsize::new(0),
+            spin_limit: CLH_SPIN_LIMIT,
+            _values: PhantomData,
+        }
     }
+    // usize::MAX never yields, for threads pinned to their own cores
+    pub fn with_spin_limit(mut self, spins: usize) -> Self {
+        self.spin_limit = spins;
+        self
+    }
+    // racy by design: only meant for load shedding decisions
+    pub fn queue_depth_hint(&self) -> usize {
+        self.waiting.load(Ordering::Relaxed)
+    }
+}
+
+impl<T: Send> CLHLock<T> {
+    pub fn handle(&self) -> CLHHandle<'_, T> { CLHHandle { lock: self, spare: None } }
+    pub fn handle_owned(self: &Arc<Self>) -> OwnedCLHHandle<T> where T: 'static {
+        let lock = self.clone();
+        // as in acquire_owned, the Arc keeps the lock alive for the handle
+        let handle = unsafe { &*Arc::as_ptr(&lock) }.handle();
+        OwnedCLHHandle { handle: ManuallyDrop::new(handle), lock }
+    }
+    pub fn acquire_if_shallow(&self, max_depth: usize) -> Option<CLHGuard<'_, T>> {
+        if self.queue_depth_hint() > max_depth { return None; }
+        Some(self.acquire())
+    }
+}
+
+impl<T> Default for CLHLock<T> {
+    fn default() -> Self { Self::with_handoff() }
 }
 
-impl Drop for CLHLock {
+impl<T> Drop for CLHLock<T> {
     fn drop(&mut self) {
-        let tail: *mut AtomicBool = *self.tail.get_mut();
+        // a value left with no one to acquire after it goes with the node
+        let (tail, _) = self.tail.get_mut();
         unsafe { drop(Box::from_raw(tail)); }
     }
 }
 
-impl Lock for CLHLock {
-    type Guard<'a> = CLHGuard<'a>;
-    fn acquire(&self) -> Self::Guard<'_> {
-        let locked = AtomicBool::new(true);
-        let node = Box::into_raw(Box::new(locked));
-        let prev = self.tail.swap(node, Ordering::SeqCst);
+impl<T: Send> CLHLock<T> {
+    fn acquire_waiting(&self, waiting: impl FnMut()) -> CLHGuard<'_, T> {
+        let (guard, prev) = self.enqueue(CLHNode::new(true), waiting);
+        unsafe { drop(Box::from_raw(prev)); }
+        guard
+    }
+    // Queues node, which must be locked and hold no value, and waits for
+    // the lock. Also returns the predecessor's node, which nobody else
+    // touches any more: it is the caller's to free or reuse.
+    fn enqueue(&self, node: *mut CLHNode<T>, mut waiting: impl FnMut())
+    -> (CLHGuard<'_, T>, *mut CLHNode<T>) {
+        self.waiting.fetch_add(1, Ordering::Relaxed);
+        let (prev, _) = self.tail.swap(node, false, Ordering::SeqCst);
+        chaos::maybe_pause(Site::ClhEnqueue);
         let prev_locked = unsafe {
-            prev.as_ref().expect("CLHLock in invalid state")
+            &prev.as_ref().expect("CLHLock in invalid state").locked
+        };
+        let mut spins = 0;
+        while prev_locked.load(Ordering::Acquire) {
+            if spins < self.spin_limit {
+                spins += 1;
+                spin_loop();
+            } else {
+                yield_now();
+            }
+            waiting();
+        }
+        self.waiting.fetch_sub(1, Ordering::Relaxed);
+        // the previous holder never touches its node after releasing
+        let inherited = unsafe { (*(*prev).value.get()).take() };
+        (CLHGuard { lock: self, node, inherited }, prev)
+    }
+    // Calls heartbeat about every `every` while queued, on this thread.
+    // heartbeat must not acquire this lock. If it panics the node is
+    // already in the queue, so the wait runs to completion and the lock
+    // is released before the panic carries on.
+    #[cfg(feature = "std")]
+    pub fn acquire_with_heartbeat(&self, every: Duration, mut heartbeat: impl FnMut()) -> CLHGuard<'_, T> {
+        let mut last = Instant::now();
+        let mut panicked = None;
+        let guard = self.acquire_waiting(|| {
+            if panicked.is_some() || last.elapsed() < every { return; }
+            panicked = panic::catch_unwind(AssertUnwindSafe(&mut heartbeat)).err();
+            last = Instant::now();
+        });
+        if let Some(payload) = panicked {
+            drop(guard);
+            panic::resume_unwind(payload);
+        }
+        guard
+    }
+}
+
+impl<T: Send> Lock for CLHLock<T> {
+    type Guard<'a> = CLHGuard<'a, T> where T: 'a;
+    fn acquire(&self) -> Self::Guard<'_> { self.acquire_waiting(|| {}) }
+}
+
+impl<T: Send> TryLock for CLHLock<T> {
+    // fails whenever anyone holds the lock or is queued for it
+    fn try_acquire(&self) -> Option<Self::Guard<'_>> {
+        let (tail, released) = self.tail.load(Ordering::Acquire);
+        if !released { return None; }
+        let node = CLHNode::new(true);
+        if self.tail.compare_exchange(
+            (tail, true), (node, false), Ordering::AcqRel, Ordering::Relaxed
+        ).is_err() {
+            unsafe { drop(Box::from_raw(node)); }
+            return None;
+        }
+        // the releaser marks the tail just before it clears its flag
+        let prev = unsafe { &*tail };
+        while prev.locked.load(Ordering::Acquire) { spin_loop(); }
+        let inherited = unsafe { Box::from_raw(tail) }.value.into_inner();
+        Some(CLHGuard { lock: self, node, inherited })
+    }
+}
+
+impl<'a, T: Send> CLHHandle<'a, T> {
+    // no allocation once the handle has a spare node
+    pub fn acquire(&mut self) -> CLHGuard<'a, T> {
+        let node = match self.spare.take() {
+            Some(node) => {
+                unsafe { (*node).locked.store(true, Ordering::Relaxed); }
+                node
+            },
+            None => CLHNode::new(true),
         };
-        while prev_locked.load(Ordering::Acquire) {}
-        CLHGuard { lock: &self, node }
+        let (guard, prev) = self.lock.enqueue(node, || {});
+        self.spare = Some(prev);
+        guard
+    }
+}
+
+impl<T: Send + 'static> OwnedCLHHandle<T> {
+    pub fn lock(&self) -> &Arc<CLHLock<T>> { &self.lock }
+    pub fn acquire(&mut self) -> OwnedGuard<CLHLock<T>> {
+        let guard = self.handle.acquire();
+        OwnedGuard { guard: ManuallyDrop::new(guard), lock: self.lock.clone() }
     }
 }
 
-impl Drop for CLHGuard<'_> {
+impl<T: 'static> Drop for OwnedCLHHandle<T> {
+    fn drop(&mut self) {
+        // free the spare node before the Arc can let go of the lock
+        unsafe { ManuallyDrop::drop(&mut self.handle); }
+    }
+}
+
+impl<T> Drop for CLHHandle<'_, T> {
+    fn drop(&mut self) {
+        // the spare is never in the queue, so nobody else can see it
+        if let Some(node) = self.spare.take() { unsafe { drop(Box::from_raw(node)); } }
+    }
+}
+
+impl<'a, T: Send> CLHGuard<'a, T> {
+    pub fn into_send_guard(self) -> CLHSendGuard<'a, T> {
+        CLHSendGuard { guard: self }
+    }
+    // goes to the back of the queue so everyone already waiting gets a
+    // turn first; skips the round trip when nobody seems to be waiting
+    pub fn yield_to_waiters(self) -> Self {
+        if self.lock.queue_depth_hint() == 0 { return self; }
+        let lock = self.lock;
+        drop(self);
+        lock.acquire()
+    }
+}
+
+impl<T> CLHGuard<'_, T> {
+    // what the previous holder bequeathed, if anything
+    pub fn inherited(&self) -> Option<&T> { self.inherited.as_ref() }
+    pub fn take_inherited(&mut self) -> Option<T> { self.inherited.take() }
+    // handed to the next thread to acquire; replaces any earlier bequest
+    pub fn bequeath(&mut self, value: T) {
+        unsafe { *(*self.node).value.get() = Some(value); }
+    }
+}
+
+impl<'a, T> CLHSendGuard<'a, T> {
+    pub fn into_inner(self) -> CLHGuard<'a, T> { self.guard }
+}
+
+impl<T> Drop for CLHGuard<'_, T> {
     fn drop(&mut self) {
-        todo!()
+        chaos::maybe_pause(Site::ClhRelease);
+        // marking first means the node cannot have been freed and reused
+        // by the time the mark goes on
+        let _ = self.lock.tail.compare_exchange(
+            (self.node, false), (self.node, true), Ordering::Release, Ordering::Relaxed
+        );
+        unsafe { (*self.node).locked.store(false, Ordering::Release); }
     }
 }
@@ -0,0 +1,172 @@
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::{
+    backoff::SpinBackoff, clock::{Clock, RealClock}, error::TimedOut,
+    sync::{atomic::{AtomicBool, AtomicU64, Ordering}, const_fn},
+};
+
+use super::{Lock, TASGuard, TTASLock, TryLock};
+
+pub struct BackoffLock<C: Clock = RealClock> {
+    pub(super) ttas: TTASLock,
+    min_delay: Duration,
+    max_delay: Duration,
+    adaptive: bool,
+    // in nanoseconds: how long the last acquire slept before it got the
+    // lock, or less once acquires stop having to sleep
+    estimate: AtomicU64,
+    clock: C,
+    deadlines: Deadlines,
+}
+
+// Timed waiters share one reading of the clock instead of each reading it
+// on every retry. One of them at a time keeps the time: it reads the clock
+// each round and publishes what it saw. The rest compare their deadlines
+// against that, and only read the clock themselves once it says they are
+// within the granularity of giving up. A lone waiter is the timekeeper,
+// so it times out as accurately as if it read the clock itself.
+struct Deadlines {
+    granularity: Duration,
+    // the first reading; now is kept as nanoseconds past it
+    origin: OnceLock<Instant>,
+    now: AtomicU64,
+    kept: AtomicBool,
+}
+
+// a sleep only follows a lost race for a lock that just looked free, so
+// the holder is usually gone long before a millisecond is up
+const MIN_DELAY: Duration = Duration::from_micros(10);
+const MAX_DELAY: Duration = Duration::from_millis(1);
+const GRANULARITY: Duration = Duration::from_millis(1);
+
+impl BackoffLock {
+    const_fn! {
+        pub fn new() -> Self { BackoffLock::with_clock(RealClock) }
+    }
+}
+
+impl<C: Clock> BackoffLock<C> {
+    const_fn! {
+        pub fn with_clock(clock: C) -> Self {
+            BackoffLock {
+                ttas: TTASLock::new(),
+                min_delay: MIN_DELAY,
+                max_delay: MAX_DELAY,
+                adaptive: false,
+                estimate: AtomicU64::new(0),
+                clock,
+                deadlines: Deadlines {
+                    granularity: GRANULARITY,
+                    origin: OnceLock::new(),
+                    now: AtomicU64::new(0),
+                    kept: AtomicBool::new(false),
+                },
+            }
+        }
+    }
+    pub const fn with_delays(mut self, min: Duration, max: Duration) -> Self {
+        assert!(min.as_nanos() <= max.as_nanos(), "minimum delay is longer than the maximum");
+        self.min_delay = min;
+        self.max_delay = max;
+        self
+    }
+    // how close to its deadline a timed waiter has to be, going by the
+    // shared reading, before it reads the clock itself
+    pub const fn with_granularity(mut self, granularity: Duration) -> Self {
+        self.deadlines.granularity = granularity;
+        self
+    }
+    // Starts each acquire's sleeps near the delay the last one needed
+    // rather than at the minimum. Every acquire that gets the lock without
+    // sleeping halves the estimate, so it falls off once contention does.
+    pub const fn adaptive(mut self) -> Self {
+        self.adaptive = true;
+        self
+    }
+    pub fn delay_estimate(&self) -> Duration {
+        Duration::from_nanos(self.estimate.load(Ordering::Relaxed))
+    }
+    fn start_delay(&self) -> Duration {
+        if !self.adaptive { return self.min_delay; }
+        self.delay_estimate().clamp(self.min_delay, self.max_delay)
+    }
+    fn update_estimate(&self, slept: Option<Duration>) {
+        if !self.adaptive { return; }
+        // racy, but it is only a hint and any recent value will do
+        let estimate = match slept {
+            Some(delay) => u64::try_from(delay.as_nanos()).unwrap_or(u64::MAX),
+            None => self.estimate.load(Ordering::Relaxed) / 2,
+        };
+        self.estimate.store(estimate, Ordering::Relaxed);
+    }
+    // reads the clock and shares the reading with the other timed waiters
+    fn read_clock(&self) -> Instant {
+        let now = self.clock.now();
+        let origin = *self.deadlines.origin.get_or_init(|| now);
+        let nanos = u64::try_from(now.saturating_duration_since(origin).as_nanos()).unwrap_or(u64::MAX);
+        self.deadlines.now.fetch_max(nanos, Ordering::Relaxed);
+        now
+    }
+    fn shared_now(&self) -> Instant {
+        let origin = *self.deadlines.origin.get().expect("BackoffLock in invalid state");
+        origin + Duration::from_nanos(self.deadlines.now.load(Ordering::Relaxed))
+    }
+}
+
+// a timed waiter; gives up keeping the time when it leaves
+struct TimedWaiter<'a, C: Clock> {
+    lock: &'a BackoffLock<C>,
+    keeper: bool,
+}
+
+impl<C: Clock> TimedWaiter<'_, C> {
+    fn expired(&mut self, deadline: Instant) -> bool {
+        let deadlines = &self.lock.deadlines;
+        if !self.keeper && !deadlines.kept.load(Ordering::Relaxed) {
+            self.keeper = !deadlines.kept.swap(true, Ordering::Relaxed);
+        }
+        if !self.keeper && self.lock.shared_now() + deadlines.granularity < deadline {
+            return false;
+        }
+        self.lock.read_clock() >= deadline
+    }
+}
+
+impl<C: Clock> Drop for TimedWaiter<'_, C> {
+    fn drop(&mut self) {
+        if self.keeper { self.lock.deadlines.kept.store(false, Ordering::Relaxed); }
+    }
+}
+
+impl Default for BackoffLock {
+    fn default() -> Self { Self::new() }
+}
+
+impl<C: Clock> Lock for BackoffLock<C> {
+    type Guard<'a> = TASGuard<'a> where C: 'a;
+    fn acquire(&self) -> Self::Guard<'_> {
+        // wait_and_swap already spins and yields while the lock is held,
+        // so losing the race for it goes straight to sleeping
+        let mut backoff = SpinBackoff::with_sleeper(&self.clock)
+            .with_limits(0, 0)
+            .with_sleeps(self.start_delay(), self.max_delay);
+        while !self.ttas.wait_and_swap() { backoff.backoff(); }
+        self.update_estimate(backoff.last_sleep());
+        TASGuard { lock: &self.ttas.0 }
+    }
+}
+
+impl<C: Clock> TryLock for BackoffLock<C> {
+    fn try_acquire(&self) -> Option<Self::Guard<'_>> { self.ttas.try_acquire() }
+    fn acquire_timeout(&self, timeout: Duration) -> Result<Self::Guard<'_>, TimedOut> {
+        let deadline = self.read_clock() + timeout;
+        let mut waiter = TimedWaiter { lock: self, keeper: false };
+        let mut backoff = SpinBackoff::new();
+        loop {
+            if let Some(guard) = self.try_acquire() { return Ok(guard); }
+            if waiter.expired(deadline) { return Err(TimedOut); }
+            backoff.snooze();
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering, fence};
+use std::thread::{self, Thread};
+
+use super::{Lock, Mutex, MutexGuard, TTASLock};
+
+pub(crate) struct Waiter {
+    notified: AtomicBool,
+    thread: Thread,
+}
+
+// Parked threads in arrival order. A waiter registers before it checks its
+// condition one last time and a notifier changes the condition before it
+// looks for waiters; the fences on both sides make sure at least one of
+// them sees the other, so a notify is never lost.
+pub(crate) struct WaitQueue {
+    waiters: Mutex<VecDeque<Arc<Waiter>>, TTASLock>,
+    len: AtomicUsize,
+}
+
+impl WaitQueue {
+    pub(crate) fn new() -> Self {
+        WaitQueue {
+            waiters: Mutex::new(VecDeque::new(), TTASLock::new()),
+            len: AtomicUsize::new(0),
+        }
+    }
+    pub(crate) fn register(&self) -> Arc<Waiter> {
+        let waiter = Arc::new(Waiter {
+            notified: AtomicBool::new(false),
+            thread: thread::current(),
+        });
+        let mut waiters = self.waiters.lock();
+        waiters.push_back(waiter.clone());
+        self.len.store(waiters.len(), Ordering::Relaxed);
+        drop(waiters);
+        fence(Ordering::SeqCst);
+        waiter
+    }
+    pub(crate) fn wait(&self, waiter: &Waiter) {
+        while !waiter.notified.load(Ordering::Acquire) { thread::park(); }
+    }
+    // for a waiter that no longer needs waking; if a notify already picked
+    // it, the notify is passed on so it is not wasted
+    pub(crate) fn cancel(&self, waiter: &Arc<Waiter>) {
+        let mut waiters = self.waiters.lock();
+        match waiters.iter().position(|other| Arc::ptr_eq(other, waiter)) {
+            Some(index) => {
+                waiters.remove(index);
+                self.len.store(waiters.len(), Ordering::Relaxed);
+            },
+            None => {
+                drop(waiters);
+                self.notify_one();
+            },
+        }
+    }
+    pub(crate) fn notify_one(&self) {
+        fence(Ordering::SeqCst);
+        if self.len.load(Ordering::Relaxed) == 0 { return; }
+        let mut waiters = self.waiters.lock();
+        let waiter = waiters.pop_front();
+        self.len.store(waiters.len(), Ordering::Relaxed);
+        drop(waiters);
+        if let Some(waiter) = waiter { Self::wake(&waiter); }
+    }
+    pub(crate) fn notify_all(&self) {
+        fence(Ordering::SeqCst);
+        if self.len.load(Ordering::Relaxed) == 0 { return; }
+        let mut waiters = self.waiters.lock();
+        let woken = std::mem::take(&mut *waiters);
+        self.len.store(0, Ordering::Relaxed);
+        drop(waiters);
+        woken.iter().for_each(|waiter| Self::wake(waiter));
+    }
+    fn wake(waiter: &Waiter) {
+        waiter.notified.store(true, Ordering::Release);
+        waiter.thread.unpark();
+    }
+}
+
+// Waiters queue up before they let go of the mutex, so a notify that
+// follows a change made under the mutex always finds them. Wakeups can
+// still be spurious as far as the caller's condition goes, which is what
+// wait_while is for.
+pub struct Condvar {
+    waiters: WaitQueue,
+}
+
+impl Condvar {
+    pub fn new() -> Self {
+        Condvar { waiters: WaitQueue::new() }
+    }
+    pub fn wait<'a, T, L: Lock>(&self, guard: MutexGuard<'a, T, L>) -> MutexGuard<'a, T, L> {
+        let waiter = self.waiters.register();
+        let mutex = guard.mutex;
+        drop(guard);
+        self.waiters.wait(&waiter);
+        mutex.lock()
+    }
+    pub fn wait_while<'a, T, L: Lock>(
+        &self, mut guard: MutexGuard<'a, T, L>, mut condition: impl FnMut(&mut T) -> bool,
+    ) -> MutexGuard<'a, T, L> {
+        while condition(&mut guard) { guard = self.wait(guard); }
+        guard
+    }
+    pub fn notify_one(&self) { self.waiters.notify_one(); }
+    pub fn notify_all(&self) { self.waiters.notify_all(); }
+}
+
+impl Default for Condvar {
+    fn default() -> Self { Self::new() }
+}
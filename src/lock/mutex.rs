@@ -0,0 +1,55 @@
+use core::cell::UnsafeCell;
+use core::ops::{Deref, DerefMut};
+
+use super::Lock;
+
+// ties the data to the lock, so the only way to reach it is through a guard
+pub struct Mutex<T, L: Lock> {
+    value: UnsafeCell<T>,
+    lock: L,
+}
+
+pub struct MutexGuard<'a, T, L: Lock + 'a> {
+    pub(super) mutex: &'a Mutex<T, L>,
+    _guard: L::Guard<'a>,
+}
+
+unsafe impl<T: Send, L: Lock> Sync for Mutex<T, L> {}
+// sharing the guard shares the value, not the right to mutate it
+unsafe impl<'a, T: Sync, L: Lock> Sync for MutexGuard<'a, T, L> where L::Guard<'a>: Sync {}
+
+impl<T, L: Lock> Mutex<T, L> {
+    pub fn new(value: T, lock: L) -> Self {
+        Mutex { value: UnsafeCell::new(value), lock }
+    }
+    pub fn lock(&self) -> MutexGuard<'_, T, L> {
+        MutexGuard { _guard: self.lock.acquire(), mutex: self }
+    }
+    pub fn get_mut(&mut self) -> &mut T { self.value.get_mut() }
+    pub fn into_inner(self) -> T { self.value.into_inner() }
+}
+
+impl<T: Default, L: Lock + Default> Default for Mutex<T, L> {
+    fn default() -> Self { Self::new(T::default(), L::default()) }
+}
+
+impl<T, L: Lock> Deref for MutexGuard<'_, T, L> {
+    type Target = T;
+    fn deref(&self) -> &T { unsafe { &*self.mutex.value.get() } }
+}
+
+impl<T, L: Lock> DerefMut for MutexGuard<'_, T, L> {
+    fn deref_mut(&mut self) -> &mut T { unsafe { &mut *self.mutex.value.get() } }
+}
+
+// Splits a guard into mutable borrows of the named fields, e.g.
+// `let (a, b) = project_guard!(guard => a, b);`. All of them come from a
+// single reborrow of the guard, so the borrow checker keeps them disjoint
+// and ends them before the guard can be dropped.
+#[macro_export]
+macro_rules! project_guard {
+    ($guard:expr => $($field:ident),+ $(,)?) => {{
+        let value = &mut *$guard;
+        ($(&mut value.$field,)+)
+    }};
+}
@@ -0,0 +1,55 @@
+use core::mem;
+use core::sync::atomic::Ordering;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "std")]
+use lock_api::RawMutexTimed;
+use lock_api::{GuardSend, RawMutex};
+
+#[cfg(feature = "std")]
+use super::BackoffLock;
+use super::{Lock, TASLock, TTASLock, TryLock};
+
+// lock_api unlocks with a bare method call rather than by dropping a
+// guard, so lock forgets the guard and unlock clears the flag the guard
+// would have cleared. That only works for the locks whose whole state is
+// one flag: the queue locks keep a node per acquire, which unlock would
+// have no way to find, and none of them can be built in a const INIT.
+macro_rules! raw_spin_mutex {
+    ($lock:ty, |$this:ident| $flag:expr) => {
+        unsafe impl RawMutex for $lock {
+            #[allow(clippy::declare_interior_mutable_const)]
+            const INIT: Self = <$lock>::new();
+            // the flag can be cleared from any thread
+            type GuardMarker = GuardSend;
+            fn lock(&self) { mem::forget(self.acquire()); }
+            fn try_lock(&self) -> bool { self.try_acquire().map(mem::forget).is_some() }
+            unsafe fn unlock(&self) {
+                let $this = self;
+                $flag.locked.store(false, Ordering::Release);
+            }
+            fn is_locked(&self) -> bool {
+                let $this = self;
+                $flag.locked.load(Ordering::Relaxed)
+            }
+        }
+
+        #[cfg(feature = "std")]
+        unsafe impl RawMutexTimed for $lock {
+            type Duration = Duration;
+            type Instant = Instant;
+            fn try_lock_for(&self, timeout: Duration) -> bool {
+                self.acquire_timeout(timeout).map(mem::forget).is_ok()
+            }
+            fn try_lock_until(&self, deadline: Instant) -> bool {
+                self.try_lock_for(deadline.saturating_duration_since(Instant::now()))
+            }
+        }
+    };
+}
+
+raw_spin_mutex!(TASLock, |lock| lock);
+raw_spin_mutex!(TTASLock, |lock| lock.0);
+#[cfg(feature = "std")]
+raw_spin_mutex!(BackoffLock, |lock| lock.ttas.0);
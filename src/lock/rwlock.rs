@@ -0,0 +1,165 @@
+use crate::sync::{atomic::{AtomicUsize, Ordering}, spin_loop};
+
+pub trait RwLock: Sized + Sync {
+    type ReadGuard<'a> where Self: 'a;
+    type WriteGuard<'a> where Self: 'a;
+    fn acquire_read(&self) -> Self::ReadGuard<'_>;
+    fn acquire_write(&self) -> Self::WriteGuard<'_>;
+}
+
+// Who goes first when readers and writers both want the lock. Under
+// ReaderPref a writer waits for a moment with no readers at all, which a
+// steady stream of overlapping readers may never give it. WriterPref holds
+// new readers back as soon as a writer waits, so a stream of writers can
+// keep readers out instead. PhaseFair alternates: readers that arrive while
+// a writer waits get in as soon as that writer is done, ahead of the
+// writers behind it, and so does everyone else.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RwPolicy {
+    ReaderPref,
+    #[default]
+    WriterPref,
+    PhaseFair,
+}
+
+const WRITER: usize = 1;
+// set by a writer that is waiting, so that new readers hold back and the
+// ones already inside can drain
+const WRITER_WAITING: usize = 2;
+const READER: usize = 4;
+
+// Under PhaseFair the two flag bits are a writer's phase instead: PRESENT
+// while a writer holds or is about to hold the lock, and the low bit of its
+// ticket. A reader that finds them set waits for them to change, which
+// happens when that writer leaves, even if the next writer has already set
+// its own.
+const PHASE: usize = 1;
+const PRESENT: usize = 2;
+const PHASE_BITS: usize = PHASE | PRESENT;
+
+// the reader count lives above the two flag bits; PhaseFair counts readers
+// in there and readers out in read_out, and serves writers in ticket order
+pub struct RwSpinLock {
+    state: AtomicUsize,
+    read_out: AtomicUsize,
+    write_in: AtomicUsize,
+    write_out: AtomicUsize,
+    policy: RwPolicy,
+}
+pub struct RwSpinReadGuard<'a> { lock: &'a RwSpinLock }
+pub struct RwSpinWriteGuard<'a> { lock: &'a RwSpinLock }
+
+impl RwSpinLock {
+    pub fn new() -> Self { Self::with_policy(RwPolicy::default()) }
+    pub fn with_policy(policy: RwPolicy) -> Self {
+        RwSpinLock {
+            state: AtomicUsize::new(0),
+            read_out: AtomicUsize::new(0),
+            write_in: AtomicUsize::new(0),
+            write_out: AtomicUsize::new(0),
+            policy,
+        }
+    }
+    pub fn policy(&self) -> RwPolicy { self.policy }
+    pub fn readers(&self) -> usize {
+        let read_out = self.read_out.load(Ordering::Relaxed);
+        self.state.load(Ordering::Relaxed).wrapping_sub(read_out) / READER
+    }
+    fn acquire_read_flags(&self, blocked_by: usize) {
+        let mut state = self.state.load(Ordering::Relaxed);
+        loop {
+            if state & blocked_by != 0 {
+                spin_loop();
+                state = self.state.load(Ordering::Relaxed);
+                continue;
+            }
+            match self.state.compare_exchange_weak(
+                state, state + READER, Ordering::Acquire, Ordering::Relaxed
+            ) {
+                Ok(_) => return,
+                Err(current) => state = current,
+            }
+        }
+    }
+    fn acquire_write_flags(&self) {
+        let mut state = self.state.load(Ordering::Relaxed);
+        loop {
+            // taking the lock clears the waiting bit; any other waiting
+            // writer sets it again on its next pass
+            if state & !WRITER_WAITING == 0 {
+                match self.state.compare_exchange_weak(
+                    state, WRITER, Ordering::Acquire, Ordering::Relaxed
+                ) {
+                    Ok(_) => return,
+                    Err(current) => { state = current; continue; },
+                }
+            }
+            if self.policy == RwPolicy::WriterPref && state & WRITER_WAITING == 0 {
+                self.state.fetch_or(WRITER_WAITING, Ordering::Relaxed);
+            }
+            spin_loop();
+            state = self.state.load(Ordering::Relaxed);
+        }
+    }
+    fn acquire_read_phase_fair(&self) {
+        let phase = self.state.fetch_add(READER, Ordering::Acquire) & PHASE_BITS;
+        if phase == 0 { return; }
+        while self.state.load(Ordering::Acquire) & PHASE_BITS == phase { spin_loop(); }
+    }
+    fn acquire_write_phase_fair(&self) {
+        let ticket = self.write_in.fetch_add(1, Ordering::Relaxed);
+        while self.write_out.load(Ordering::Acquire) != ticket { spin_loop(); }
+        // from here on new readers wait, and the ones counted in before
+        // this point have to leave
+        let readers_in = self.state.fetch_add(PRESENT | (ticket & PHASE), Ordering::Acquire);
+        let readers_in = readers_in & !PHASE_BITS;
+        while self.read_out.load(Ordering::Acquire) != readers_in { spin_loop(); }
+    }
+}
+
+impl Default for RwSpinLock {
+    fn default() -> Self { Self::new() }
+}
+
+impl RwLock for RwSpinLock {
+    type ReadGuard<'a> = RwSpinReadGuard<'a>;
+    type WriteGuard<'a> = RwSpinWriteGuard<'a>;
+    fn acquire_read(&self) -> Self::ReadGuard<'_> {
+        match self.policy {
+            RwPolicy::ReaderPref => self.acquire_read_flags(WRITER),
+            RwPolicy::WriterPref => self.acquire_read_flags(WRITER | WRITER_WAITING),
+            RwPolicy::PhaseFair => self.acquire_read_phase_fair(),
+        }
+        RwSpinReadGuard { lock: self }
+    }
+    fn acquire_write(&self) -> Self::WriteGuard<'_> {
+        match self.policy {
+            RwPolicy::PhaseFair => self.acquire_write_phase_fair(),
+            _ => self.acquire_write_flags(),
+        }
+        RwSpinWriteGuard { lock: self }
+    }
+}
+
+impl Drop for RwSpinReadGuard<'_> {
+    fn drop(&mut self) {
+        match self.lock.policy {
+            RwPolicy::PhaseFair => self.lock.read_out.fetch_add(READER, Ordering::Release),
+            _ => self.lock.state.fetch_sub(READER, Ordering::Release),
+        };
+    }
+}
+
+impl Drop for RwSpinWriteGuard<'_> {
+    fn drop(&mut self) {
+        match self.lock.policy {
+            // ends the phase, letting in the readers that waited on it
+            RwPolicy::PhaseFair => {
+                self.lock.state.fetch_and(!PHASE_BITS, Ordering::Release);
+                self.lock.write_out.fetch_add(1, Ordering::Release);
+            },
+            // keeps a waiting bit set by someone else
+            _ => { self.lock.state.fetch_and(!WRITER, Ordering::Release); },
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use core::fmt;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::error::TimedOut;
+use crate::metrics::{EventKind, EventRing};
+use crate::sync::{atomic::{AtomicU64, AtomicUsize, Ordering}, spin_loop};
+
+use super::{Lock, TASLock, TryLock};
+
+// bucket 0 holds waits under a microsecond, bucket i those from 2^(i-1) up
+// to 2^i microseconds, and the last one everything longer
+pub const WAIT_BUCKETS: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LockStats {
+    pub acquisitions: u64,
+    // acquisitions whose first try_acquire failed
+    pub contended: u64,
+    pub total_wait: Duration,
+    pub max_wait: Duration,
+    pub total_hold: Duration,
+    pub max_hold: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StatsSnapshot {
+    pub stats: LockStats,
+    pub wait_histogram: [u64; WAIT_BUCKETS],
+}
+
+// Counts what happens to the lock it wraps. Every acquire starts with one
+// try_acquire, so an acquisition counts as contended exactly when that
+// first attempt fails. The counters are relaxed and live apart from the
+// lock, so they only slow it down, never order it.
+//
+// There are two banks of counters. Recorders write to the active one;
+// snapshot_and_reset makes the other one active, waits for the recorders
+// still in the old one to finish, and then takes its counts. So every
+// event lands in exactly one interval, and nothing is recorded into a bank
+// while it is being emptied.
+//
+// with_events also logs each acquire, release, failed try and timeout to
+// a ring for dumping after the fact; without it there is no ring and
+// nothing but a check for None.
+pub struct Instrumented<L: TryLock> {
+    lock: L,
+    banks: Banks,
+    events: Option<EventRing>,
+}
+
+pub struct InstrumentedGuard<'a, L: TryLock + 'a> {
+    banks: &'a Banks,
+    events: Option<&'a EventRing>,
+    acquired: Instant,
+    _guard: L::Guard<'a>,
+}
+
+#[derive(Default)]
+struct Banks {
+    banks: [Counters; 2],
+    active: AtomicUsize,
+    // one reset at a time, so two can't flip the banks back and forth
+    resetting: TASLock,
+}
+
+#[derive(Default)]
+struct Counters {
+    acquisitions: AtomicU64,
+    contended: AtomicU64,
+    // in nanoseconds
+    total_wait: AtomicU64,
+    max_wait: AtomicU64,
+    total_hold: AtomicU64,
+    max_hold: AtomicU64,
+    wait_histogram: [AtomicU64; WAIT_BUCKETS],
+    // recorders writing to this bank right now
+    recorders: AtomicUsize,
+}
+
+impl<L: TryLock> Instrumented<L> {
+    pub fn new(lock: L) -> Self { Instrumented { lock, banks: Banks::default(), events: None } }
+    // capacity must be a power of two
+    pub fn with_events(self, capacity: usize) -> Self {
+        Instrumented { events: Some(EventRing::new(capacity)), ..self }
+    }
+    pub fn events(&self) -> Option<&EventRing> { self.events.as_ref() }
+    pub fn inner(&self) -> &L { &self.lock }
+    pub fn into_inner(self) -> L { self.lock }
+    pub fn stats(&self) -> LockStats { self.snapshot().stats }
+    // adds up both banks; while a reset runs the total may briefly count
+    // an event twice or not at all
+    pub fn snapshot(&self) -> StatsSnapshot {
+        let [first, second] = self.banks.banks.each_ref().map(Counters::read);
+        first.merge(second)
+    }
+    // every event recorded before this returns is in exactly one of the
+    // snapshots it hands out
+    pub fn snapshot_and_reset(&self) -> StatsSnapshot {
+        let _guard = self.banks.resetting.acquire();
+        let old = &self.banks.banks[self.banks.active.fetch_xor(1, Ordering::SeqCst)];
+        while old.recorders.load(Ordering::SeqCst) != 0 { spin_loop(); }
+        old.take()
+    }
+    pub fn reset_stats(&self) { self.snapshot_and_reset(); }
+    fn record_event(&self, kind: EventKind) {
+        if let Some(events) = &self.events { events.record(kind); }
+    }
+    fn guard<'a>(&'a self, guard: L::Guard<'a>, started: Instant, contended: bool)
+    -> InstrumentedGuard<'a, L> {
+        let acquired = Instant::now();
+        self.banks.record(|counters| {
+            counters.acquisitions.fetch_add(1, Ordering::Relaxed);
+            if contended { counters.contended.fetch_add(1, Ordering::Relaxed); }
+            let wait = acquired - started;
+            record(&counters.total_wait, &counters.max_wait, wait);
+            counters.wait_histogram[wait_bucket(wait)].fetch_add(1, Ordering::Relaxed);
+        });
+        self.record_event(EventKind::AcquireComplete);
+        let events = self.events.as_ref();
+        InstrumentedGuard { banks: &self.banks, events, acquired, _guard: guard }
+    }
+}
+
+impl<L: TryLock + Default> Default for Instrumented<L> {
+    fn default() -> Self { Self::new(L::default()) }
+}
+
+impl<L: TryLock> Lock for Instrumented<L> {
+    type Guard<'a> = InstrumentedGuard<'a, L> where L: 'a;
+    fn acquire(&self) -> Self::Guard<'_> {
+        let started = Instant::now();
+        self.record_event(EventKind::AcquireStart);
+        match self.lock.try_acquire() {
+            Some(guard) => self.guard(guard, started, false),
+            None => self.guard(self.lock.acquire(), started, true),
+        }
+    }
+}
+
+impl<L: TryLock> TryLock for Instrumented<L> {
+    // a failed attempt didn't acquire anything, so it isn't counted; it
+    // is only logged
+    fn try_acquire(&self) -> Option<Self::Guard<'_>> {
+        let started = Instant::now();
+        let guard = self.lock.try_acquire().map(|guard| self.guard(guard, started, false));
+        if guard.is_none() { self.record_event(EventKind::TryFail); }
+        guard
+    }
+    // waits the inner lock's way, rather than retrying try_acquire here and
+    // logging every failed attempt
+    fn acquire_timeout(&self, timeout: Duration) -> Result<Self::Guard<'_>, TimedOut> {
+        let started = Instant::now();
+        self.record_event(EventKind::AcquireStart);
+        if let Some(guard) = self.lock.try_acquire() {
+            return Ok(self.guard(guard, started, false));
+        }
+        match self.lock.acquire_timeout(timeout.saturating_sub(started.elapsed())) {
+            Ok(guard) => Ok(self.guard(guard, started, true)),
+            Err(timed_out) => {
+                self.record_event(EventKind::Timeout);
+                Err(timed_out)
+            },
+        }
+    }
+}
+
+impl<L: TryLock> Drop for InstrumentedGuard<'_, L> {
+    // runs before the inner guard is dropped, so the hold time ends just
+    // short of the release
+    fn drop(&mut self) {
+        let held = self.acquired.elapsed();
+        self.banks.record(|counters| record(&counters.total_hold, &counters.max_hold, held));
+        if let Some(events) = self.events { events.record(EventKind::Release); }
+    }
+}
+
+impl Banks {
+    // counts itself into the active bank first, and checks that bank is
+    // still the active one, so a reset either waits for it or it retries
+    fn record(&self, f: impl FnOnce(&Counters)) {
+        let counters = loop {
+            let active = self.active.load(Ordering::SeqCst);
+            let counters = &self.banks[active];
+            counters.recorders.fetch_add(1, Ordering::SeqCst);
+            if self.active.load(Ordering::SeqCst) == active { break counters; }
+            counters.recorders.fetch_sub(1, Ordering::SeqCst);
+        };
+        f(counters);
+        counters.recorders.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Counters {
+    fn read(&self) -> StatsSnapshot { self.snapshot(|counter| counter.load(Ordering::Relaxed)) }
+    fn take(&self) -> StatsSnapshot { self.snapshot(|counter| counter.swap(0, Ordering::Relaxed)) }
+    fn snapshot(&self, get: impl Fn(&AtomicU64) -> u64) -> StatsSnapshot {
+        let nanos = |counter: &AtomicU64| Duration::from_nanos(get(counter));
+        StatsSnapshot {
+            stats: LockStats {
+                acquisitions: get(&self.acquisitions),
+                contended: get(&self.contended),
+                total_wait: nanos(&self.total_wait),
+                max_wait: nanos(&self.max_wait),
+                total_hold: nanos(&self.total_hold),
+                max_hold: nanos(&self.max_hold),
+            },
+            wait_histogram: self.wait_histogram.each_ref().map(&get),
+        }
+    }
+}
+
+impl StatsSnapshot {
+    pub fn merge(self, other: StatsSnapshot) -> StatsSnapshot {
+        let (a, b) = (self.stats, other.stats);
+        let mut wait_histogram = self.wait_histogram;
+        for (bucket, count) in wait_histogram.iter_mut().zip(other.wait_histogram) {
+            *bucket += count;
+        }
+        StatsSnapshot {
+            stats: LockStats {
+                acquisitions: a.acquisitions + b.acquisitions,
+                contended: a.contended + b.contended,
+                total_wait: a.total_wait + b.total_wait,
+                max_wait: a.max_wait.max(b.max_wait),
+                total_hold: a.total_hold + b.total_hold,
+                max_hold: a.max_hold.max(b.max_hold),
+            },
+            wait_histogram,
+        }
+    }
+}
+
+// one "name value" pair per line, durations in nanoseconds
+impl fmt::Display for StatsSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stats = &self.stats;
+        writeln!(f, "acquisitions {}", stats.acquisitions)?;
+        writeln!(f, "contended {}", stats.contended)?;
+        writeln!(f, "total_wait_ns {}", stats.total_wait.as_nanos())?;
+        writeln!(f, "max_wait_ns {}", stats.max_wait.as_nanos())?;
+        writeln!(f, "total_hold_ns {}", stats.total_hold.as_nanos())?;
+        writeln!(f, "max_hold_ns {}", stats.max_hold.as_nanos())?;
+        write!(f, "wait_histogram_us")?;
+        for count in self.wait_histogram { write!(f, " {}", count)?; }
+        writeln!(f)
+    }
+}
+
+fn wait_bucket(wait: Duration) -> usize {
+    let micros = u64::try_from(wait.as_micros()).unwrap_or(u64::MAX);
+    (u64::BITS - micros.leading_zeros()).min(WAIT_BUCKETS as u32 - 1) as usize
+}
+
+fn record(total: &AtomicU64, max: &AtomicU64, elapsed: Duration) {
+    let nanos = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX);
+    total.fetch_add(nanos, Ordering::Relaxed);
+    max.fetch_max(nanos, Ordering::Relaxed);
+}
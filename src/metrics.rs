@@ -0,0 +1,120 @@
+use std::cell::Cell;
+use std::sync::atomic::{fence, AtomicU16, AtomicU64, Ordering};
+use std::time::Instant;
+
+use crate::clock::{Clock, RealClock};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    AcquireStart,
+    AcquireComplete,
+    Release,
+    TryFail,
+    Timeout,
+}
+
+const KINDS: [EventKind; 5] = [
+    EventKind::AcquireStart,
+    EventKind::AcquireComplete,
+    EventKind::Release,
+    EventKind::TryFail,
+    EventKind::Timeout,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventRecord {
+    pub seq: u64,
+    pub kind: EventKind,
+    pub thread: u16,
+    pub micros: u64,
+}
+
+// header: seq (40 bits) | kind (8) | thread (16), with 0 meaning empty and
+// all ones meaning a write is in progress
+// data: low 16 bits of seq | micros since the ring was made (48 bits)
+const WRITING: u64 = u64::MAX;
+const SEQ_BITS: u32 = 40;
+const MICROS_BITS: u32 = 48;
+
+struct Slot {
+    header: AtomicU64,
+    data: AtomicU64,
+}
+
+// Fixed-size log of the most recent events. Writers claim a sequence
+// number with a single fetch_add and overwrite whatever was in its slot;
+// each slot is a tiny seqlock, and the copy of the sequence number in the
+// data word catches the case where two writers a whole lap apart write
+// the same slot at once. Anything torn is left out of the dump.
+pub struct EventRing<C: Clock = RealClock> {
+    slots: Box<[Slot]>,
+    next: AtomicU64,
+    start: Instant,
+    clock: C,
+}
+
+thread_local! {
+    static THREAD_TAG: Cell<Option<u16>> = const { Cell::new(None) };
+}
+
+static NEXT_TAG: AtomicU16 = AtomicU16::new(0);
+
+fn thread_tag() -> u16 {
+    THREAD_TAG.with(|tag| match tag.get() {
+        Some(tag) => tag,
+        None => {
+            let new = NEXT_TAG.fetch_add(1, Ordering::Relaxed);
+            tag.set(Some(new));
+            new
+        },
+    })
+}
+
+impl EventRing {
+    pub fn new(capacity: usize) -> Self { EventRing::with_clock(capacity, RealClock) }
+}
+
+impl<C: Clock> EventRing<C> {
+    pub fn with_clock(capacity: usize, clock: C) -> Self {
+        assert!(capacity.is_power_of_two(), "ring capacity must be a power of two");
+        let slots = (0..capacity)
+            .map(|_| Slot { header: AtomicU64::new(0), data: AtomicU64::new(0) })
+            .collect();
+        EventRing { slots, next: AtomicU64::new(1), start: clock.now(), clock }
+    }
+    pub fn capacity(&self) -> usize { self.slots.len() }
+    pub fn record(&self, kind: EventKind) {
+        let seq = self.next.fetch_add(1, Ordering::Relaxed);
+        let micros = self.clock.now().duration_since(self.start).as_micros() as u64;
+        let header = (seq & ((1 << SEQ_BITS) - 1)) << 24
+            | (kind as u64) << 16 | thread_tag() as u64;
+        let data = (seq & 0xffff) << MICROS_BITS | micros & ((1 << MICROS_BITS) - 1);
+        let slot = &self.slots[seq as usize & (self.capacity() - 1)];
+        slot.header.store(WRITING, Ordering::Relaxed);
+        fence(Ordering::Release);
+        slot.data.store(data, Ordering::Relaxed);
+        slot.header.store(header, Ordering::Release);
+    }
+    // the surviving records in sequence order
+    pub fn dump(&self) -> Vec<EventRecord> {
+        let mut records: Vec<_> = self.slots.iter()
+            .filter_map(|slot| {
+                let header = slot.header.load(Ordering::Acquire);
+                let data = slot.data.load(Ordering::Relaxed);
+                fence(Ordering::Acquire);
+                if header == 0 || header == WRITING { return None; }
+                if slot.header.load(Ordering::Relaxed) != header { return None; }
+                let seq = header >> 24;
+                if data >> MICROS_BITS != seq & 0xffff { return None; }
+                Some(EventRecord {
+                    seq,
+                    kind: KINDS[(header >> 16 & 0xff) as usize],
+                    thread: header as u16,
+                    micros: data & ((1 << MICROS_BITS) - 1),
+                })
+            })
+            .collect();
+        records.sort_by_key(|record| record.seq);
+        records
+    }
+}
@@ -0,0 +1,91 @@
+use std::cell::UnsafeCell;
+use std::sync::Arc;
+
+use crate::error::{Closed, TryRecvError};
+use crate::sync::{atomic::{AtomicU8, Ordering}, spin_loop, yield_now};
+
+const EMPTY: u8 = 0;
+const SENT: u8 = 1;
+// the sender was dropped without sending
+const CLOSED: u8 = 2;
+const RECEIVER_GONE: u8 = 3;
+
+const SPIN_LIMIT: usize = 1 << 10;
+
+// The sender writes the value before it publishes SENT and the receiver
+// only reads it after seeing SENT, so the two never touch it at once.
+// Leaving EMPTY happens exactly once, which is what lets each side find
+// out the other is gone.
+struct Inner<T> {
+    state: AtomicU8,
+    value: UnsafeCell<Option<T>>,
+}
+
+unsafe impl<T: Send> Send for Inner<T> {}
+unsafe impl<T: Send> Sync for Inner<T> {}
+
+pub struct Sender<T> { inner: Arc<Inner<T>> }
+pub struct Receiver<T> { inner: Arc<Inner<T>> }
+
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let inner = Arc::new(Inner { state: AtomicU8::new(EMPTY), value: UnsafeCell::new(None) });
+    (Sender { inner: inner.clone() }, Receiver { inner })
+}
+
+impl<T> Sender<T> {
+    // gives the value back if the receiver is already gone
+    pub fn send(self, value: T) -> Result<(), T> {
+        unsafe { *self.inner.value.get() = Some(value); }
+        match self.inner.state.compare_exchange(
+            EMPTY, SENT, Ordering::Release, Ordering::Relaxed
+        ) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(unsafe { (*self.inner.value.get()).take() }
+                .expect("oneshot channel in invalid state")),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        // fails harmlessly once the value was sent
+        let _ = self.inner.state.compare_exchange(
+            EMPTY, CLOSED, Ordering::Relaxed, Ordering::Relaxed
+        );
+    }
+}
+
+impl<T> Receiver<T> {
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        match self.inner.state.load(Ordering::Acquire) {
+            EMPTY => Err(TryRecvError::Empty),
+            SENT => unsafe { (*self.inner.value.get()).take() }
+                .ok_or(TryRecvError::Disconnected),
+            _ => Err(TryRecvError::Disconnected),
+        }
+    }
+    // spins for a while, then yields between checks
+    pub fn recv(mut self) -> Result<T, Closed> {
+        let mut spins = 0;
+        loop {
+            match self.try_recv() {
+                Ok(value) => return Ok(value),
+                Err(TryRecvError::Disconnected) => return Err(Closed),
+                Err(TryRecvError::Empty) if spins < SPIN_LIMIT => {
+                    spin_loop();
+                    spins += 1;
+                },
+                Err(TryRecvError::Empty) => yield_now(),
+            }
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        // a value that was sent is dropped along with Inner
+        let _ = self.inner.state.compare_exchange(
+            EMPTY, RECEIVER_GONE, Ordering::Relaxed, Ordering::Relaxed
+        );
+    }
+}
@@ -0,0 +1,29 @@
+use core::ops::{Deref, DerefMut};
+
+// Keeps a value on its own cache line, so threads spinning on neighbouring
+// values don't invalidate each other's lines. Apple's aarch64 cores have
+// 128-byte lines.
+#[cfg_attr(target_arch = "aarch64", repr(align(128)))]
+#[cfg_attr(not(target_arch = "aarch64"), repr(align(64)))]
+#[derive(Debug, Default)]
+pub struct CachePadded<T> {
+    value: T,
+}
+
+impl<T> CachePadded<T> {
+    pub const fn new(value: T) -> Self { CachePadded { value } }
+    pub fn into_inner(self) -> T { self.value }
+}
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+    fn deref(&self) -> &T { &self.value }
+}
+
+impl<T> DerefMut for CachePadded<T> {
+    fn deref_mut(&mut self) -> &mut T { &mut self.value }
+}
+
+impl<T> From<T> for CachePadded<T> {
+    fn from(value: T) -> Self { Self::new(value) }
+}
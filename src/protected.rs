@@ -0,0 +1,35 @@
+use std::panic;
+use std::thread;
+
+use crate::latch::CountDownLatch;
+use crate::lock::{Lock, Mutex};
+
+// Runs f on `workers` scoped threads against one Mutex holding data,
+// starting them together, and returns the data with each worker's result
+// in worker order. If any worker panics, the first panic in worker order
+// is resumed once every thread has been joined.
+pub fn run_protected<T, L, R>(
+    data: T,
+    lock_factory: impl Fn() -> L,
+    workers: usize,
+    f: impl Fn(usize, &Mutex<T, L>) -> R + Sync,
+) -> (T, Vec<R>)
+where T: Send, L: Lock, R: Send {
+    let mutex = Mutex::new(data, lock_factory());
+    let start = CountDownLatch::new(workers);
+    let outcomes: Vec<_> = thread::scope(|s| {
+        let handles: Vec<_> = (0..workers).map(|worker| {
+            let (mutex, start, f) = (&mutex, &start, &f);
+            s.spawn(move || {
+                start.count_down();
+                start.wait();
+                f(worker, mutex)
+            })
+        }).collect();
+        handles.into_iter().map(|handle| handle.join()).collect()
+    });
+    let results = outcomes.into_iter()
+        .collect::<thread::Result<Vec<R>>>()
+        .unwrap_or_else(|payload| panic::resume_unwind(payload));
+    (mutex.into_inner(), results)
+}
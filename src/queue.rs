@@ -0,0 +1,128 @@
+use std::cell::UnsafeCell;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+use crate::lock::{Lock, WaitQueue};
+
+#[cfg(target_pointer_width = "64")]
+mod ms;
+
+#[cfg(target_pointer_width = "64")]
+pub use ms::MsQueue;
+
+struct Node<T> {
+    item: Option<T>,
+    next: AtomicPtr<Node<T>>,
+}
+
+impl<T> Node<T> {
+    fn new(item: Option<T>) -> *mut Self {
+        Box::into_raw(Box::new(Node { item, next: AtomicPtr::new(ptr::null_mut()) }))
+    }
+}
+
+// Michael and Scott's two-lock queue: head always points at a dummy node,
+// so producers and consumers only ever meet on the dummy's next pointer
+// and each end can have its own lock. Capacity is enforced by reserving a
+// slot in len before linking and releasing it after unlinking.
+pub struct TwoLockQueue<T, L: Lock> {
+    head: UnsafeCell<*mut Node<T>>,
+    tail: UnsafeCell<*mut Node<T>>,
+    head_lock: L,
+    tail_lock: L,
+    len: AtomicUsize,
+    capacity: usize,
+    not_full: WaitQueue,
+    not_empty: WaitQueue,
+}
+
+unsafe impl<T: Send, L: Lock + Send> Send for TwoLockQueue<T, L> {}
+unsafe impl<T: Send, L: Lock> Sync for TwoLockQueue<T, L> {}
+
+impl<T, L: Lock> TwoLockQueue<T, L> {
+    pub fn new(capacity: usize, head_lock: L, tail_lock: L) -> Self {
+        assert!(capacity > 0, "queue needs room for at least one item");
+        let dummy = Node::new(None);
+        TwoLockQueue {
+            head: UnsafeCell::new(dummy),
+            tail: UnsafeCell::new(dummy),
+            head_lock,
+            tail_lock,
+            len: AtomicUsize::new(0),
+            capacity,
+            not_full: WaitQueue::new(),
+            not_empty: WaitQueue::new(),
+        }
+    }
+    pub fn capacity(&self) -> usize { self.capacity }
+    // counts pushes that have reserved a slot but not linked their node yet
+    pub fn len(&self) -> usize { self.len.load(Ordering::Acquire) }
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+    pub fn try_push(&self, item: T) -> Result<(), T> {
+        let reserved = self.len.fetch_update(Ordering::AcqRel, Ordering::Acquire, |len| {
+            (len < self.capacity).then_some(len + 1)
+        });
+        if reserved.is_err() { return Err(item); }
+        let node = Node::new(Some(item));
+        let guard = self.tail_lock.acquire();
+        let tail = unsafe { &mut *self.tail.get() };
+        unsafe { (**tail).next.store(node, Ordering::Release); }
+        *tail = node;
+        drop(guard);
+        self.not_empty.notify_one();
+        Ok(())
+    }
+    pub fn try_pop(&self) -> Option<T> {
+        let item = {
+            let _guard = self.head_lock.acquire();
+            let head = unsafe { &mut *self.head.get() };
+            let next = unsafe { (**head).next.load(Ordering::Acquire) };
+            if next.is_null() { return None; }
+            // next becomes the new dummy
+            let item = unsafe { (*next).item.take() };
+            unsafe { drop(Box::from_raw(*head)); }
+            *head = next;
+            item
+        };
+        self.len.fetch_sub(1, Ordering::AcqRel);
+        self.not_full.notify_one();
+        Some(item.expect("TwoLockQueue in invalid state"))
+    }
+    // both park until the other side makes progress; each successful
+    // push or pop wakes at most one waiter on the other side
+    pub fn push(&self, mut item: T) {
+        loop {
+            item = match self.try_push(item) {
+                Ok(()) => return,
+                Err(rejected) => rejected,
+            };
+            let waiter = self.not_full.register();
+            item = match self.try_push(item) {
+                Ok(()) => { self.not_full.cancel(&waiter); return; },
+                Err(rejected) => rejected,
+            };
+            self.not_full.wait(&waiter);
+        }
+    }
+    pub fn pop(&self) -> T {
+        loop {
+            if let Some(item) = self.try_pop() { return item; }
+            let waiter = self.not_empty.register();
+            if let Some(item) = self.try_pop() {
+                self.not_empty.cancel(&waiter);
+                return item;
+            }
+            self.not_empty.wait(&waiter);
+        }
+    }
+}
+
+impl<T, L: Lock> Drop for TwoLockQueue<T, L> {
+    fn drop(&mut self) {
+        let mut curr = *self.head.get_mut();
+        while !curr.is_null() {
+            let node = unsafe { Box::from_raw(curr) };
+            curr = node.next.load(Ordering::Relaxed);
+        }
+    }
+}
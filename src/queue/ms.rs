@@ -0,0 +1,140 @@
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+use crate::{atomic::AtomicStamped, chaos::{self, Site}};
+
+struct Node<T> {
+    // a box of its own, so a dequeuer can read it before winning the head
+    // without racing the next owner of the node
+    item: AtomicPtr<T>,
+    next: AtomicStamped<Node<T>>,
+}
+
+// Michael and Scott's lock-free queue in its original form: stamped
+// pointers everywhere and a free list instead of freeing. A thread that
+// read a node just before it was recycled can still follow its pointers,
+// and any CAS it bases on them fails because the stamps have moved on.
+pub struct MsQueue<T> {
+    head: AtomicStamped<Node<T>>,
+    tail: AtomicStamped<Node<T>>,
+    free: AtomicStamped<Node<T>>,
+}
+
+unsafe impl<T: Send> Send for MsQueue<T> {}
+unsafe impl<T: Send> Sync for MsQueue<T> {}
+
+impl<T> MsQueue<T> {
+    pub fn new() -> Self {
+        let dummy = Self::new_node();
+        MsQueue {
+            head: AtomicStamped::new(dummy),
+            tail: AtomicStamped::new(dummy),
+            free: AtomicStamped::new(ptr::null_mut()),
+        }
+    }
+    pub fn enqueue(&self, item: T) {
+        let node = self.alloc();
+        let node_ref = unsafe { &*node };
+        node_ref.item.store(Box::into_raw(Box::new(item)), Ordering::Relaxed);
+        node_ref.next.swap(ptr::null_mut(), Ordering::Relaxed);
+        let (tail, stamp) = loop {
+            let (tail, stamp) = self.tail.load(Ordering::Acquire);
+            let (next, next_stamp) = unsafe { &*tail }.next.load(Ordering::Acquire);
+            if self.tail.load(Ordering::Acquire) != (tail, stamp) { continue; }
+            if !next.is_null() {
+                // the tail is lagging behind a finished enqueue
+                let _ = self.tail.compare_exchange(
+                    tail, stamp, next, Ordering::Release, Ordering::Relaxed
+                );
+                continue;
+            }
+            if unsafe { &*tail }.next.compare_exchange(
+                next, next_stamp, node, Ordering::Release, Ordering::Relaxed
+            ).is_ok() {
+                break (tail, stamp);
+            }
+        };
+        chaos::maybe_pause(Site::MsQueueLink);
+        let _ = self.tail.compare_exchange(tail, stamp, node, Ordering::Release, Ordering::Relaxed);
+    }
+    pub fn dequeue(&self) -> Option<T> {
+        loop {
+            let (head, stamp) = self.head.load(Ordering::Acquire);
+            let (tail, tail_stamp) = self.tail.load(Ordering::Acquire);
+            let (next, _) = unsafe { &*head }.next.load(Ordering::Acquire);
+            if self.head.load(Ordering::Acquire) != (head, stamp) { continue; }
+            if next.is_null() { return None; }
+            if head == tail {
+                let _ = self.tail.compare_exchange(
+                    tail, tail_stamp, next, Ordering::Release, Ordering::Relaxed
+                );
+                continue;
+            }
+            // next stays put until the head moves past it, which is the
+            // CAS below
+            let item = unsafe { &*next }.item.load(Ordering::Acquire);
+            if self.head.compare_exchange(
+                head, stamp, next, Ordering::AcqRel, Ordering::Relaxed
+            ).is_ok() {
+                self.release(head);
+                return Some(*unsafe { Box::from_raw(item) });
+            }
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        let (head, _) = self.head.load(Ordering::Acquire);
+        unsafe { &*head }.next.load(Ordering::Acquire).0.is_null()
+    }
+    fn new_node() -> *mut Node<T> {
+        Box::into_raw(Box::new(Node {
+            item: AtomicPtr::new(ptr::null_mut()),
+            next: AtomicStamped::new(ptr::null_mut()),
+        }))
+    }
+    // recycled nodes keep their stamps, which is what makes reuse safe
+    fn alloc(&self) -> *mut Node<T> {
+        loop {
+            let (node, stamp) = self.free.load(Ordering::Acquire);
+            if node.is_null() { return Self::new_node(); }
+            let (next, _) = unsafe { &*node }.next.load(Ordering::Relaxed);
+            if self.free.compare_exchange(
+                node, stamp, next, Ordering::Acquire, Ordering::Relaxed
+            ).is_ok() {
+                return node;
+            }
+        }
+    }
+    fn release(&self, node: *mut Node<T>) {
+        loop {
+            let (free, stamp) = self.free.load(Ordering::Relaxed);
+            unsafe { &*node }.next.swap(free, Ordering::Relaxed);
+            if self.free.compare_exchange(
+                free, stamp, node, Ordering::Release, Ordering::Relaxed
+            ).is_ok() {
+                return;
+            }
+        }
+    }
+}
+
+impl<T> Default for MsQueue<T> {
+    fn default() -> Self { Self::new() }
+}
+
+impl<T> Drop for MsQueue<T> {
+    fn drop(&mut self) {
+        // the dummy's item was taken by whoever dequeued it
+        let (dummy, _) = self.head.get_mut();
+        let (mut curr, _) = unsafe { Box::from_raw(dummy) }.next.get_mut();
+        while !curr.is_null() {
+            let mut node = unsafe { Box::from_raw(curr) };
+            unsafe { drop(Box::from_raw(*node.item.get_mut())); }
+            curr = node.next.get_mut().0;
+        }
+        let (mut curr, _) = self.free.get_mut();
+        while !curr.is_null() {
+            let mut node = unsafe { Box::from_raw(curr) };
+            curr = node.next.get_mut().0;
+        }
+    }
+}
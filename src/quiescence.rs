@@ -0,0 +1,93 @@
+use std::cell::UnsafeCell;
+use std::mem;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::lock::{Lock, TTASLock};
+
+// Quiescent-state reclamation: something retired at epoch e may be freed
+// once every registered thread has announced a quiescent point at an
+// epoch of at least e, since a thread holds no references across those
+// points. Threads that never register are not waited for, so they must
+// not read anything that gets retired here.
+pub struct Quiescence {
+    epoch: AtomicU64,
+    lock: TTASLock,
+    participants: UnsafeCell<Vec<Arc<Slot>>>,
+    garbage: UnsafeCell<Vec<(u64, Box<dyn Send>)>>,
+}
+
+// each thread bumps its own counter, so keep them on separate cache lines
+#[repr(align(64))]
+struct Slot { seen: AtomicU64 }
+
+pub struct Participant<'a> {
+    quiescence: &'a Quiescence,
+    slot: Arc<Slot>,
+}
+
+unsafe impl Sync for Quiescence {}
+
+impl Quiescence {
+    pub fn new() -> Self {
+        Quiescence {
+            epoch: AtomicU64::new(0),
+            lock: TTASLock::new(),
+            participants: UnsafeCell::new(Vec::new()),
+            garbage: UnsafeCell::new(Vec::new()),
+        }
+    }
+    pub fn register(&self) -> Participant<'_> {
+        let _guard = self.lock.acquire();
+        let seen = AtomicU64::new(self.epoch.load(Ordering::SeqCst));
+        let slot = Arc::new(Slot { seen });
+        unsafe { &mut *self.participants.get() }.push(slot.clone());
+        Participant { quiescence: self, slot }
+    }
+    // call after the value has been made unreachable
+    pub fn retire<T: Send + 'static>(&self, garbage: Box<T>) {
+        let _guard = self.lock.acquire();
+        let epoch = self.epoch.fetch_add(1, Ordering::SeqCst) + 1;
+        unsafe { &mut *self.garbage.get() }.push((epoch, garbage));
+    }
+    // frees whatever every participant has moved past and returns how
+    // many values that was; the drops happen after the lock is released
+    pub fn collect(&self) -> usize {
+        let ready: Vec<_> = {
+            let _guard = self.lock.acquire();
+            let safe = unsafe { &*self.participants.get() }.iter()
+                .map(|slot| slot.seen.load(Ordering::SeqCst))
+                .min().unwrap_or(u64::MAX);
+            let garbage = unsafe { &mut *self.garbage.get() };
+            let (ready, waiting) = mem::take(garbage).into_iter()
+                .partition(|&(epoch, _)| epoch <= safe);
+            *garbage = waiting;
+            ready
+        };
+        ready.len()
+    }
+    pub fn pending(&self) -> usize {
+        let _guard = self.lock.acquire();
+        unsafe { &*self.garbage.get() }.len()
+    }
+}
+
+impl Default for Quiescence {
+    fn default() -> Self { Self::new() }
+}
+
+impl Participant<'_> {
+    // announces that this thread holds no references into the structure
+    pub fn quiescent(&self) {
+        let epoch = self.quiescence.epoch.load(Ordering::SeqCst);
+        self.slot.seen.store(epoch, Ordering::SeqCst);
+    }
+}
+
+impl Drop for Participant<'_> {
+    fn drop(&mut self) {
+        let _guard = self.quiescence.lock.acquire();
+        let participants = unsafe { &mut *self.quiescence.participants.get() };
+        participants.retain(|slot| !Arc::ptr_eq(slot, &self.slot));
+    }
+}
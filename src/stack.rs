@@ -0,0 +1,174 @@
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::time::Duration;
+
+use rand::random;
+
+use crate::atomic::AtomicStamped;
+
+mod exchanger;
+
+pub use exchanger::Exchanger;
+
+struct Node<T> {
+    value: UnsafeCell<MaybeUninit<T>>,
+    next: AtomicPtr<Node<T>>,
+}
+
+// A Treiber stack of nodes. The stamp on the head makes a pop fail if the
+// head was popped and pushed back since it was read, so nodes can be
+// recycled; they are only freed once the owner has &mut.
+struct NodeStack<T> {
+    head: AtomicStamped<Node<T>>,
+}
+
+impl<T> NodeStack<T> {
+    fn new() -> Self { NodeStack { head: AtomicStamped::new(ptr::null_mut()) } }
+    fn try_push(&self, node: *mut Node<T>) -> bool {
+        let (head, stamp) = self.head.load(Ordering::Relaxed);
+        unsafe { (*node).next.store(head, Ordering::Relaxed); }
+        self.head.compare_exchange(head, stamp, node, Ordering::Release, Ordering::Relaxed)
+            .is_ok()
+    }
+    fn push(&self, node: *mut Node<T>) {
+        while !self.try_push(node) {}
+    }
+    // None if another thread got in first, Some(null) if empty
+    fn try_pop(&self) -> Option<*mut Node<T>> {
+        let (head, stamp) = self.head.load(Ordering::Acquire);
+        if head.is_null() { return Some(head); }
+        // head may be recycled under us, but then the stamp has moved on
+        let next = unsafe { (*head).next.load(Ordering::Relaxed) };
+        self.head.compare_exchange(head, stamp, next, Ordering::Acquire, Ordering::Relaxed)
+            .ok().map(|_| head)
+    }
+    fn pop(&self) -> *mut Node<T> {
+        loop {
+            if let Some(node) = self.try_pop() { return node; }
+        }
+    }
+    fn is_empty(&self) -> bool { self.head.load(Ordering::Relaxed).0.is_null() }
+}
+
+pub struct TreiberStack<T> {
+    items: NodeStack<T>,
+    free: NodeStack<T>,
+}
+
+unsafe impl<T: Send> Send for TreiberStack<T> {}
+unsafe impl<T: Send> Sync for TreiberStack<T> {}
+
+impl<T> TreiberStack<T> {
+    pub fn new() -> Self {
+        TreiberStack { items: NodeStack::new(), free: NodeStack::new() }
+    }
+    pub fn push(&self, value: T) {
+        let mut value = value;
+        loop {
+            match self.try_push(value) {
+                Ok(()) => return,
+                Err(back) => value = back,
+            }
+        }
+    }
+    pub fn pop(&self) -> Option<T> {
+        loop {
+            if let Some(value) = self.try_pop() { return value; }
+        }
+    }
+    pub fn is_empty(&self) -> bool { self.items.is_empty() }
+    // a single attempt; gives the value back if another thread got in first
+    fn try_push(&self, value: T) -> Result<(), T> {
+        let node = match self.free.pop() {
+            node if node.is_null() => Box::into_raw(Box::new(Node {
+                value: UnsafeCell::new(MaybeUninit::uninit()),
+                next: AtomicPtr::new(ptr::null_mut()),
+            })),
+            node => node,
+        };
+        unsafe { (*(*node).value.get()).write(value); }
+        if self.items.try_push(node) { return Ok(()); }
+        let value = unsafe { (*(*node).value.get()).assume_init_read() };
+        self.free.push(node);
+        Err(value)
+    }
+    // None if another thread got in first
+    fn try_pop(&self) -> Option<Option<T>> {
+        let node = self.items.try_pop()?;
+        if node.is_null() { return Some(None); }
+        let value = unsafe { (*(*node).value.get()).assume_init_read() };
+        self.free.push(node);
+        Some(Some(value))
+    }
+}
+
+impl<T> Default for TreiberStack<T> {
+    fn default() -> Self { Self::new() }
+}
+
+impl<T> Drop for TreiberStack<T> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+        loop {
+            let node = self.free.pop();
+            if node.is_null() { break; }
+            unsafe { drop(Box::from_raw(node)); }
+        }
+    }
+}
+
+const ELIMINATION_TIMEOUT: Duration = Duration::from_micros(50);
+
+// A push and a pop that meet in the elimination array cancel out without
+// touching the stack, so contention on the head turns into parallel
+// exchanges. Pushes offer Some(value) and pops offer None; an exchange
+// only counts if it paired one of each.
+pub struct EliminationStack<T> {
+    stack: TreiberStack<T>,
+    exchangers: Box<[Exchanger<Option<T>>]>,
+    timeout: Duration,
+}
+
+impl<T> EliminationStack<T> {
+    pub fn new(slots: usize) -> Self {
+        assert!(slots > 0, "EliminationStack needs at least one slot");
+        EliminationStack {
+            stack: TreiberStack::new(),
+            exchangers: (0..slots).map(|_| Exchanger::new()).collect(),
+            timeout: ELIMINATION_TIMEOUT,
+        }
+    }
+    // how long a thread waits for a partner before going back to the stack
+    pub fn timeout(self, timeout: Duration) -> Self {
+        EliminationStack { timeout, ..self }
+    }
+    pub fn slots(&self) -> usize { self.exchangers.len() }
+    pub fn push(&self, value: T) {
+        let mut value = value;
+        loop {
+            value = match self.stack.try_push(value) {
+                Ok(()) => return,
+                Err(value) => value,
+            };
+            match self.exchanger().exchange(Some(value), self.timeout) {
+                Ok(None) => return,
+                Ok(Some(back)) | Err(Some(back)) => value = back,
+                Err(None) => unreachable!("EliminationStack in invalid state"),
+            }
+        }
+    }
+    pub fn pop(&self) -> Option<T> {
+        loop {
+            if let Some(value) = self.stack.try_pop() { return value; }
+            if let Ok(Some(value)) = self.exchanger().exchange(None, self.timeout) {
+                return Some(value);
+            }
+        }
+    }
+    pub fn is_empty(&self) -> bool { self.stack.is_empty() }
+    fn exchanger(&self) -> &Exchanger<Option<T>> {
+        &self.exchangers[random::<usize>() % self.exchangers.len()]
+    }
+}
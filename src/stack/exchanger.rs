@@ -0,0 +1,93 @@
+use std::cell::UnsafeCell;
+use std::hint::spin_loop;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::atomic::AtomicMarkable;
+
+// lives on the waiting thread's stack for as long as it is in the slot;
+// the alignment leaves room for the mark
+#[repr(align(2))]
+struct Offer<T> {
+    item: UnsafeCell<Option<T>>,
+    reply: UnsafeCell<Option<T>>,
+    done: AtomicBool,
+}
+
+// The slot is empty (null), waiting (an unmarked offer) or busy (a marked
+// offer that a partner has claimed). Only the thread that put an offer in
+// the slot takes it out again, and it does not leave until any partner
+// that claimed the offer has finished with it.
+pub struct Exchanger<T> {
+    slot: AtomicMarkable<Offer<T>>,
+}
+
+unsafe impl<T: Send> Send for Exchanger<T> {}
+unsafe impl<T: Send> Sync for Exchanger<T> {}
+
+impl<T> Exchanger<T> {
+    pub fn new() -> Self {
+        Exchanger { slot: AtomicMarkable::new(ptr::null_mut(), false) }
+    }
+    // gives back the partner's item, or our own if nobody showed up in time
+    pub fn exchange(&self, item: T, timeout: Duration) -> Result<T, T> {
+        let deadline = Instant::now() + timeout;
+        let mut item = item;
+        loop {
+            match self.slot.load(Ordering::Acquire) {
+                (offer, _) if offer.is_null() => match self.offer(item, deadline) {
+                    Ok(reply) => return Ok(reply),
+                    Err(back) => item = back,
+                },
+                (offer, false) => {
+                    if self.slot.compare_exchange(
+                        (offer, false), (offer, true), Ordering::Acquire, Ordering::Relaxed
+                    ).is_ok() {
+                        let offer = unsafe { &*offer };
+                        let theirs = unsafe { (*offer.item.get()).take() };
+                        unsafe { *offer.reply.get() = Some(item); }
+                        offer.done.store(true, Ordering::Release);
+                        return Ok(theirs.expect("Exchanger in invalid state"));
+                    }
+                },
+                (_, true) => spin_loop(),
+            }
+            if Instant::now() >= deadline { return Err(item); }
+        }
+    }
+    fn offer(&self, item: T, deadline: Instant) -> Result<T, T> {
+        let offer = Offer {
+            item: UnsafeCell::new(Some(item)),
+            reply: UnsafeCell::new(None),
+            done: AtomicBool::new(false),
+        };
+        let offer_ptr = &offer as *const Offer<T> as *mut Offer<T>;
+        if self.slot.compare_exchange(
+            (ptr::null_mut(), false), (offer_ptr, false), Ordering::Release, Ordering::Relaxed
+        ).is_err() {
+            return Err(offer.item.into_inner().expect("Exchanger in invalid state"));
+        }
+        while Instant::now() < deadline {
+            if offer.done.load(Ordering::Acquire) { return Ok(self.complete(offer)); }
+            spin_loop();
+        }
+        // withdrawing fails if a partner claimed the offer in the meantime,
+        // in which case its reply is on the way
+        if self.slot.compare_exchange(
+            (offer_ptr, false), (ptr::null_mut(), false), Ordering::Relaxed, Ordering::Relaxed
+        ).is_ok() {
+            return Err(offer.item.into_inner().expect("Exchanger in invalid state"));
+        }
+        while !offer.done.load(Ordering::Acquire) { spin_loop(); }
+        Ok(self.complete(offer))
+    }
+    fn complete(&self, offer: Offer<T>) -> T {
+        self.slot.store(ptr::null_mut(), false, Ordering::Release);
+        offer.reply.into_inner().expect("Exchanger in invalid state")
+    }
+}
+
+impl<T> Default for Exchanger<T> {
+    fn default() -> Self { Self::new() }
+}
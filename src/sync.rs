@@ -0,0 +1,46 @@
+// The atomics the locks are built on. Building with RUSTFLAGS="--cfg loom"
+// swaps in loom's versions, which lets tests/loom.rs explore every
+// interleaving and every reordering the memory model allows. The
+// trace-atomics feature swaps in ones that log each operation instead (see
+// trace.rs); without it these are plain re-exports.
+
+#[cfg(not(loom))]
+pub(crate) use core::hint::spin_loop;
+#[cfg(all(not(loom), not(feature = "trace-atomics")))]
+pub(crate) use core::sync::atomic;
+#[cfg(all(not(loom), feature = "trace-atomics"))]
+pub(crate) use crate::trace::atomic;
+
+#[cfg(loom)]
+pub(crate) use loom::hint::spin_loop;
+#[cfg(loom)]
+pub(crate) use loom::sync::atomic;
+
+// loom's atomics have with_mut instead of get_mut
+#[cfg(not(loom))]
+pub(crate) fn load_mut<T>(ptr: &mut atomic::AtomicPtr<T>) -> *mut T { *ptr.get_mut() }
+#[cfg(loom)]
+pub(crate) fn load_mut<T>(ptr: &mut atomic::AtomicPtr<T>) -> *mut T { ptr.with_mut(|ptr| *ptr) }
+
+// without an OS to hand the time slice to, waiting just goes on spinning
+pub(crate) fn yield_now() {
+    #[cfg(loom)]
+    loom::thread::yield_now();
+    #[cfg(all(not(loom), feature = "std"))]
+    std::thread::yield_now();
+    #[cfg(all(not(loom), not(feature = "std")))]
+    spin_loop();
+}
+
+// loom's atomics can't be created in a const fn, so the constructors lose
+// their const in model-checking builds
+macro_rules! const_fn {
+    ($(#[$attr:meta])* $vis:vis fn $($rest:tt)*) => {
+        #[cfg(not(loom))]
+        $(#[$attr])* $vis const fn $($rest)*
+        #[cfg(loom)]
+        $(#[$attr])* $vis fn $($rest)*
+    };
+}
+
+pub(crate) use const_fn;
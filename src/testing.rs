@@ -0,0 +1,3 @@
+pub mod drop_counter;
+pub mod linearizability;
+pub mod lock_suite;
@@ -0,0 +1,148 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+#[derive(Default)]
+struct Counts {
+    created: AtomicUsize,
+    dropped: AtomicUsize,
+}
+
+pub struct Counted<T> {
+    value: T,
+    counts: Arc<Counts>,
+}
+
+impl<T> Drop for Counted<T> {
+    fn drop(&mut self) {
+        self.counts.dropped.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl<T: Clone> Clone for Counted<T> {
+    fn clone(&self) -> Self {
+        self.counts.created.fetch_add(1, Ordering::SeqCst);
+        Counted { value: self.value.clone(), counts: self.counts.clone() }
+    }
+}
+
+impl<T> Deref for Counted<T> {
+    type Target = T;
+    fn deref(&self) -> &T { &self.value }
+}
+
+impl<T> DerefMut for Counted<T> {
+    fn deref_mut(&mut self) -> &mut T { &mut self.value }
+}
+
+impl<T: Hash> Hash for Counted<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) { self.value.hash(state) }
+}
+
+impl<T: PartialEq> PartialEq for Counted<T> {
+    fn eq(&self, other: &Self) -> bool { self.value == other.value }
+}
+
+impl<T: Eq> Eq for Counted<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for Counted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+// on drop, asserts that every value it made was dropped exactly once
+#[derive(Default)]
+pub struct CountedFactory {
+    counts: Arc<Counts>,
+}
+
+impl CountedFactory {
+    pub fn new() -> Self { Self::default() }
+    pub fn make<T>(&self, value: T) -> Counted<T> {
+        self.counts.created.fetch_add(1, Ordering::SeqCst);
+        Counted { value, counts: self.counts.clone() }
+    }
+    pub fn created(&self) -> usize { self.counts.created.load(Ordering::SeqCst) }
+    pub fn dropped(&self) -> usize { self.counts.dropped.load(Ordering::SeqCst) }
+    pub fn live(&self) -> usize { self.created() - self.dropped() }
+}
+
+impl Drop for CountedFactory {
+    fn drop(&mut self) {
+        if thread::panicking() { return; }
+        assert_eq!(
+            self.dropped(), self.created(),
+            "counted values were leaked or dropped more than once"
+        );
+    }
+}
+
+#[macro_export]
+macro_rules! assert_balanced {
+    ($factory:expr) => { $crate::assert_balanced!($factory, 0) };
+    ($factory:expr, $live:expr) => {{
+        let factory = &$factory;
+        assert!(
+            factory.dropped() <= factory.created(),
+            "{} counted values dropped but only {} created",
+            factory.dropped(), factory.created()
+        );
+        assert_eq!(factory.live(), $live, "unexpected number of live values");
+    }};
+}
+
+// Tracks boxes by a serial number rather than by address: boxes of a
+// zero-sized type all share one address.
+#[derive(Default)]
+pub struct LeakRegistry {
+    live: Mutex<HashSet<usize>>,
+    next_id: AtomicUsize,
+}
+
+impl LeakRegistry {
+    pub fn new() -> Self { Self::default() }
+    pub fn boxed<T>(&self, value: T) -> TrackedBox<'_, T> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        assert!(self.live.lock().unwrap().insert(id));
+        TrackedBox { value: Box::new(value), id, registry: self }
+    }
+    // the ids of the boxes that are still alive, in the order they were made
+    pub fn leaked(&self) -> Vec<usize> {
+        let mut leaked: Vec<usize> = self.live.lock().unwrap().iter().copied().collect();
+        leaked.sort_unstable();
+        leaked
+    }
+}
+
+pub struct TrackedBox<'a, T> {
+    value: Box<T>,
+    id: usize,
+    registry: &'a LeakRegistry,
+}
+
+impl<T> TrackedBox<'_, T> {
+    pub fn id(&self) -> usize { self.id }
+}
+
+impl<T> Drop for TrackedBox<'_, T> {
+    fn drop(&mut self) {
+        assert!(
+            self.registry.live.lock().unwrap().remove(&self.id),
+            "box {} freed twice", self.id
+        );
+    }
+}
+
+impl<T> Deref for TrackedBox<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T { &self.value }
+}
+
+impl<T> DerefMut for TrackedBox<'_, T> {
+    fn deref_mut(&mut self) -> &mut T { &mut self.value }
+}
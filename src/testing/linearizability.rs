@@ -0,0 +1,163 @@
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+
+use rand::random;
+
+use crate::listset::ConcurrentSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetOp {
+    Add(u64),
+    Remove(u64),
+    Contains(u64),
+}
+
+impl SetOp {
+    fn apply<S: ConcurrentSet<u64>>(self, set: &S) -> bool {
+        match self {
+            SetOp::Add(key) => set.add(key),
+            SetOp::Remove(key) => set.remove(key),
+            SetOp::Contains(key) => set.contains(key),
+        }
+    }
+    // the sequential specification
+    fn apply_seq(self, state: &mut BTreeSet<u64>) -> bool {
+        match self {
+            SetOp::Add(key) => state.insert(key),
+            SetOp::Remove(key) => state.remove(&key),
+            SetOp::Contains(key) => state.contains(&key),
+        }
+    }
+    fn undo_seq(self, state: &mut BTreeSet<u64>, result: bool) {
+        match self {
+            SetOp::Add(key) if result => { state.remove(&key); },
+            SetOp::Remove(key) if result => { state.insert(key); },
+            _ => {},
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Event {
+    pub thread: usize,
+    pub op: SetOp,
+    pub result: bool,
+    pub invoked: u64,
+    pub returned: u64,
+}
+
+impl Event {
+    fn precedes(&self, other: &Event) -> bool { self.returned < other.invoked }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f, "[{:>3}, {:>3}] thread {}: {:?} -> {}",
+            self.invoked, self.returned, self.thread, self.op, self.result
+        )
+    }
+}
+
+#[derive(Default)]
+pub struct HistoryRecorder {
+    clock: AtomicU64,
+    events: Mutex<Vec<Event>>,
+}
+
+impl HistoryRecorder {
+    pub fn new() -> Self { Self::default() }
+    pub fn record<S: ConcurrentSet<u64>>(&self, thread: usize, set: &S, op: SetOp) -> bool {
+        let invoked = self.clock.fetch_add(1, Ordering::SeqCst);
+        let result = op.apply(set);
+        let returned = self.clock.fetch_add(1, Ordering::SeqCst);
+        let event = Event { thread, op, result, invoked, returned };
+        self.events.lock().unwrap().push(event);
+        result
+    }
+    pub fn into_history(self) -> Vec<Event> {
+        let mut history = self.events.into_inner().unwrap();
+        history.sort_by_key(|event| event.invoked);
+        history
+    }
+}
+
+// returns a linearization (indices into history) if one exists; meant for
+// small histories since the search is exponential in the worst case
+pub fn linearize(history: &[Event]) -> Option<Vec<usize>> {
+    assert!(history.len() <= 64, "history too long to check");
+    let mut order = Vec::with_capacity(history.len());
+    let mut seen = HashSet::new();
+    let found = search(history, 0, &mut BTreeSet::new(), &mut order, &mut seen);
+    found.then_some(order)
+}
+
+pub fn is_linearizable(history: &[Event]) -> bool {
+    linearize(history).is_some()
+}
+
+fn search(
+    history: &[Event],
+    done: u64,
+    state: &mut BTreeSet<u64>,
+    order: &mut Vec<usize>,
+    seen: &mut HashSet<(u64, Vec<u64>)>,
+) -> bool {
+    if order.len() == history.len() { return true; }
+    // the same prefix set and state fail the same way regardless of order
+    if !seen.insert((done, state.iter().copied().collect())) { return false; }
+    let pending = |i: usize| done & (1 << i) == 0;
+    for (i, event) in history.iter().enumerate() {
+        if !pending(i) { continue; }
+        let blocked = history.iter().enumerate()
+            .any(|(j, other)| j != i && pending(j) && other.precedes(event));
+        if blocked { continue; }
+        let result = event.op.apply_seq(state);
+        if result == event.result {
+            order.push(i);
+            if search(history, done | (1 << i), state, order, seen) {
+                return true;
+            }
+            order.pop();
+        }
+        event.op.undo_seq(state, result);
+    }
+    false
+}
+
+pub fn format_history(history: &[Event]) -> String {
+    history.iter().map(|event| format!("{}\n", event)).collect()
+}
+
+// runs short random histories against fresh sets and panics with the
+// offending history if one cannot be linearized
+pub fn check_set<S, F>(make: F, threads: usize, ops_per_thread: usize, rounds: usize)
+where S: ConcurrentSet<u64> + Sync, F: Fn() -> S {
+    const KEYS: u64 = 4;
+    for _ in 0..rounds {
+        let set = make();
+        let recorder = HistoryRecorder::new();
+        thread::scope(|s| {
+            for thread in 0..threads {
+                let (set, recorder) = (&set, &recorder);
+                s.spawn(move || for _ in 0..ops_per_thread {
+                    let key = random::<u64>() % KEYS;
+                    let op = match random::<u8>() % 3 {
+                        0 => SetOp::Add(key),
+                        1 => SetOp::Remove(key),
+                        _ => SetOp::Contains(key),
+                    };
+                    recorder.record(thread, set, op);
+                });
+            }
+        });
+        let history = recorder.into_history();
+        assert!(
+            is_linearizable(&history),
+            "history is not linearizable:\n{}", format_history(&history)
+        );
+    }
+}
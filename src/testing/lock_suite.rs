@@ -0,0 +1,152 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::lock::{ArrayLock, Flags, Lock, TryLock};
+
+// The counter is bumped with a separate load and store, so overlapping
+// critical sections show up as lost updates even when the in-use flag
+// happens to miss them.
+pub fn check_exclusion<L: Lock>(lock: &L, threads: usize, iterations: usize) {
+    let inside = AtomicBool::new(false);
+    let counter = AtomicUsize::new(0);
+    thread::scope(|s| for _ in 0..threads {
+        let (inside, counter) = (&inside, &counter);
+        s.spawn(move || for _ in 0..iterations {
+            let _guard = lock.acquire();
+            assert!(!inside.swap(true, Ordering::Relaxed), "two holders at once");
+            let value = counter.load(Ordering::Relaxed);
+            thread::yield_now();
+            counter.store(value + 1, Ordering::Relaxed);
+            inside.store(false, Ordering::Relaxed);
+        });
+    });
+    assert_eq!(counter.into_inner(), threads * iterations, "lost updates");
+}
+
+pub fn check_reacquire<L: Lock>(lock: &L) {
+    for _ in 0..3 { drop(lock.acquire()); }
+}
+
+pub fn check_release_on_panic<L: Lock>(lock: &L) {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let _guard = lock.acquire();
+        panic!("panicking while holding the lock");
+    }));
+    assert!(result.is_err());
+    drop(lock.acquire());
+}
+
+// try_acquire has to come back within microseconds while another thread
+// holds the lock, with or without a thread queued behind it, succeed once
+// everyone is gone, and never let two threads in at once
+pub fn check_try_acquire<L: TryLock>(lock: &L, threads: usize, iterations: usize) {
+    let guard = lock.acquire();
+    let check_held = || thread::scope(|s| {
+        s.spawn(|| {
+            let mut fastest = Duration::MAX;
+            for _ in 0..100 {
+                let start = Instant::now();
+                assert!(lock.try_acquire().is_none(), "try_acquire got a held lock");
+                let elapsed = start.elapsed();
+                assert!(elapsed < Duration::from_millis(10), "try_acquire blocked");
+                fastest = fastest.min(elapsed);
+            }
+            assert!(fastest < Duration::from_micros(100), "try_acquire took {:?}", fastest);
+        });
+    });
+    check_held();
+    thread::scope(|s| {
+        let waiter = s.spawn(|| drop(lock.acquire()));
+        // long enough for the waiter to queue up behind the holder
+        thread::sleep(Duration::from_millis(10));
+        check_held();
+        drop(guard);
+        waiter.join().unwrap();
+    });
+    assert!(lock.try_acquire().is_some(), "try_acquire failed on a free lock");
+    let inside = AtomicBool::new(false);
+    thread::scope(|s| for thread in 0..threads {
+        let inside = &inside;
+        s.spawn(move || for i in 0..iterations {
+            let _guard = if (thread + i) % 2 == 0 {
+                match lock.try_acquire() {
+                    Some(guard) => guard,
+                    None => continue,
+                }
+            } else {
+                lock.acquire()
+            };
+            assert!(!inside.swap(true, Ordering::Relaxed), "two holders at once");
+            thread::yield_now();
+            inside.store(false, Ordering::Relaxed);
+        });
+    });
+}
+
+// with one guard held and every other slot taken by a waiter, exactly
+// one more acquirer must be turned away
+pub fn check_capacity<F: Flags>(lock: &ArrayLock<F>) {
+    let rejected = AtomicUsize::new(0);
+    let guard = lock.acquire();
+    thread::scope(|s| {
+        for _ in 0..lock.capacity() {
+            let rejected = &rejected;
+            s.spawn(move || if lock.acquire_checked().is_err() {
+                rejected.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+        while rejected.load(Ordering::SeqCst) == 0 { thread::yield_now(); }
+        drop(guard);
+    });
+    assert_eq!(rejected.into_inner(), 1);
+}
+
+// Expands to a module of tests run against a fresh lock from $make each
+// time. Trailing keywords add tests: try_acquire for TryLocks, and
+// capacity for ArrayLocks, which also keeps the other tests within the
+// lock's capacity.
+#[macro_export]
+macro_rules! lock_test_suite {
+    ($name:ident, $make:expr, capacity $(, $extra:ident)*) => {
+        mod $name {
+            #[allow(unused_imports)]
+            use super::*;
+            $crate::lock_test_suite!(@tests $make, $make.capacity().min(4));
+            $($crate::lock_test_suite!(@extra $extra $make, $make.capacity().min(4));)*
+            #[test]
+            fn capacity_exceeded() {
+                $crate::testing::lock_suite::check_capacity(&$make);
+            }
+        }
+    };
+    ($name:ident, $make:expr $(, $extra:ident)*) => {
+        mod $name {
+            #[allow(unused_imports)]
+            use super::*;
+            $crate::lock_test_suite!(@tests $make, 4);
+            $($crate::lock_test_suite!(@extra $extra $make, 4);)*
+        }
+    };
+    (@tests $make:expr, $threads:expr) => {
+        #[test]
+        fn exclusion() {
+            $crate::testing::lock_suite::check_exclusion(&$make, $threads, 200);
+        }
+        #[test]
+        fn reacquire_after_drop() {
+            $crate::testing::lock_suite::check_reacquire(&$make);
+        }
+        #[test]
+        fn release_on_panic() {
+            $crate::testing::lock_suite::check_release_on_panic(&$make);
+        }
+    };
+    (@extra try_acquire $make:expr, $threads:expr) => {
+        #[test]
+        fn try_acquire() {
+            $crate::testing::lock_suite::check_try_acquire(&$make, $threads, 200);
+        }
+    };
+}
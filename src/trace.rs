@@ -0,0 +1,254 @@
+// With the trace-atomics feature, the atomics behind crate::sync write every
+// operation down in a buffer local to the thread that did it. Each event
+// carries a number from one global counter, so the buffers of several
+// threads merge back into the order the operations happened in.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::vec::Vec;
+use std::string::String;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Event {
+    pub seq: u64,
+    pub thread: usize,
+    pub op: &'static str,
+    // the address of the atomic
+    pub addr: usize,
+    pub ordering: Ordering,
+    // what was loaded or stored; for read-modify-writes, the old value
+    pub value: u64,
+}
+
+static SEQ: AtomicU64 = AtomicU64::new(0);
+static THREADS: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static THREAD: usize = THREADS.fetch_add(1, Ordering::Relaxed);
+    static EVENTS: RefCell<Vec<Event>> = const { RefCell::new(Vec::new()) };
+}
+
+// Loads and read-modify-writes are numbered just after they happen, and
+// stores just before, so a value is never read in the timeline before the
+// store that wrote it. Two read-modify-writes racing on one atomic may
+// still come out in either order.
+fn next_seq() -> u64 { SEQ.fetch_add(1, Ordering::SeqCst) }
+
+fn record(op: &'static str, addr: usize, ordering: Ordering, value: u64) {
+    record_at(next_seq(), op, addr, ordering, value);
+}
+
+fn record_at(seq: u64, op: &'static str, addr: usize, ordering: Ordering, value: u64) {
+    // atomics used while the thread is being torn down go unrecorded
+    let thread = THREAD.try_with(|&thread| thread).unwrap_or(usize::MAX);
+    let _ = EVENTS.try_with(|events| {
+        events.borrow_mut().push(Event { seq, thread, op, addr, ordering, value });
+    });
+}
+
+// everything this thread has done since it last called this
+pub fn take_events() -> Vec<Event> {
+    EVENTS.with(|events| core::mem::take(&mut *events.borrow_mut()))
+}
+
+// one line per event in sequence order; atomics are named a0, a1, ... by
+// when they first show up
+pub fn format_timeline(mut events: Vec<Event>) -> String {
+    events.sort_by_key(|event| event.seq);
+    let mut tags = HashMap::new();
+    let mut timeline = String::new();
+    for event in events {
+        let next = tags.len();
+        let tag = *tags.entry(event.addr).or_insert(next);
+        writeln!(
+            timeline, "{:>6} t{} {} a{} {:?} {}",
+            event.seq, event.thread, event.op, tag, event.ordering, event.value,
+        ).expect("writing to a String failed");
+    }
+    timeline
+}
+
+// Same names and methods as core::sync::atomic, for the parts of it the
+// crate uses. Anything missing here fails the build with the feature on.
+#[allow(dead_code)]
+pub(crate) mod atomic {
+    use core::fmt;
+    use core::sync::atomic as core_atomic;
+
+    pub(crate) use core::sync::atomic::Ordering;
+
+    use super::{next_seq, record, record_at};
+
+    macro_rules! traced_int {
+        ($name:ident, $int:ty) => {
+            #[derive(Default)]
+            #[repr(transparent)]
+            pub struct $name(core_atomic::$name);
+
+            impl $name {
+                pub(crate) const fn new(value: $int) -> Self { $name(core_atomic::$name::new(value)) }
+                fn addr(&self) -> usize { self as *const Self as usize }
+                fn record(&self, op: &'static str, ordering: Ordering, value: $int) -> $int {
+                    record(op, self.addr(), ordering, value as u64);
+                    value
+                }
+                pub(crate) fn get_mut(&mut self) -> &mut $int { self.0.get_mut() }
+                pub(crate) fn into_inner(self) -> $int { self.0.into_inner() }
+                pub(crate) fn load(&self, order: Ordering) -> $int {
+                    self.record("load", order, self.0.load(order))
+                }
+                pub(crate) fn store(&self, value: $int, order: Ordering) {
+                    let seq = next_seq();
+                    self.0.store(value, order);
+                    record_at(seq, "store", self.addr(), order, value as u64);
+                }
+                pub(crate) fn swap(&self, value: $int, order: Ordering) -> $int {
+                    self.record("swap", order, self.0.swap(value, order))
+                }
+                pub(crate) fn compare_exchange(
+                    &self, current: $int, new: $int, success: Ordering, failure: Ordering,
+                ) -> Result<$int, $int> {
+                    match self.0.compare_exchange(current, new, success, failure) {
+                        Ok(old) => Ok(self.record("cas", success, old)),
+                        Err(old) => Err(self.record("cas_failed", failure, old)),
+                    }
+                }
+                pub(crate) fn compare_exchange_weak(
+                    &self, current: $int, new: $int, success: Ordering, failure: Ordering,
+                ) -> Result<$int, $int> {
+                    match self.0.compare_exchange_weak(current, new, success, failure) {
+                        Ok(old) => Ok(self.record("cas_weak", success, old)),
+                        Err(old) => Err(self.record("cas_weak_failed", failure, old)),
+                    }
+                }
+                pub(crate) fn fetch_update(
+                    &self, set: Ordering, fetch: Ordering, f: impl FnMut($int) -> Option<$int>,
+                ) -> Result<$int, $int> {
+                    match self.0.fetch_update(set, fetch, f) {
+                        Ok(old) => Ok(self.record("fetch_update", set, old)),
+                        Err(old) => Err(self.record("fetch_update_failed", fetch, old)),
+                    }
+                }
+                traced_int!(@rmw $int, fetch_add, fetch_sub, fetch_and, fetch_or, fetch_xor,
+                    fetch_max, fetch_min);
+            }
+
+            impl fmt::Debug for $name {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { self.0.fmt(f) }
+            }
+        };
+        (@rmw $int:ty, $($op:ident),*) => {$(
+            pub(crate) fn $op(&self, value: $int, order: Ordering) -> $int {
+                self.record(stringify!($op), order, self.0.$op(value, order))
+            }
+        )*};
+    }
+
+    traced_int!(AtomicU8, u8);
+    traced_int!(AtomicU64, u64);
+    traced_int!(AtomicUsize, usize);
+
+    #[derive(Default)]
+    #[repr(transparent)]
+    pub struct AtomicBool(core_atomic::AtomicBool);
+
+    impl AtomicBool {
+        pub(crate) const fn new(value: bool) -> Self { AtomicBool(core_atomic::AtomicBool::new(value)) }
+        fn record(&self, op: &'static str, ordering: Ordering, value: bool) -> bool {
+            record(op, self as *const Self as usize, ordering, value as u64);
+            value
+        }
+        pub(crate) fn get_mut(&mut self) -> &mut bool { self.0.get_mut() }
+        pub(crate) fn into_inner(self) -> bool { self.0.into_inner() }
+        pub(crate) fn load(&self, order: Ordering) -> bool {
+            self.record("load", order, self.0.load(order))
+        }
+        pub(crate) fn store(&self, value: bool, order: Ordering) {
+            let seq = next_seq();
+            self.0.store(value, order);
+            record_at(seq, "store", self as *const Self as usize, order, value as u64);
+        }
+        pub(crate) fn swap(&self, value: bool, order: Ordering) -> bool {
+            self.record("swap", order, self.0.swap(value, order))
+        }
+        pub(crate) fn compare_exchange(
+            &self, current: bool, new: bool, success: Ordering, failure: Ordering,
+        ) -> Result<bool, bool> {
+            match self.0.compare_exchange(current, new, success, failure) {
+                Ok(old) => Ok(self.record("cas", success, old)),
+                Err(old) => Err(self.record("cas_failed", failure, old)),
+            }
+        }
+        pub(crate) fn compare_exchange_weak(
+            &self, current: bool, new: bool, success: Ordering, failure: Ordering,
+        ) -> Result<bool, bool> {
+            match self.0.compare_exchange_weak(current, new, success, failure) {
+                Ok(old) => Ok(self.record("cas_weak", success, old)),
+                Err(old) => Err(self.record("cas_weak_failed", failure, old)),
+            }
+        }
+        pub(crate) fn fetch_and(&self, value: bool, order: Ordering) -> bool {
+            self.record("fetch_and", order, self.0.fetch_and(value, order))
+        }
+        pub(crate) fn fetch_or(&self, value: bool, order: Ordering) -> bool {
+            self.record("fetch_or", order, self.0.fetch_or(value, order))
+        }
+        pub(crate) fn fetch_xor(&self, value: bool, order: Ordering) -> bool {
+            self.record("fetch_xor", order, self.0.fetch_xor(value, order))
+        }
+    }
+
+    impl fmt::Debug for AtomicBool {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { self.0.fmt(f) }
+    }
+
+    #[repr(transparent)]
+    pub struct AtomicPtr<T>(core_atomic::AtomicPtr<T>);
+
+    impl<T> AtomicPtr<T> {
+        pub(crate) const fn new(ptr: *mut T) -> Self { AtomicPtr(core_atomic::AtomicPtr::new(ptr)) }
+        fn record(&self, op: &'static str, ordering: Ordering, ptr: *mut T) -> *mut T {
+            record(op, self as *const Self as usize, ordering, ptr as usize as u64);
+            ptr
+        }
+        pub(crate) fn get_mut(&mut self) -> &mut *mut T { self.0.get_mut() }
+        pub(crate) fn into_inner(self) -> *mut T { self.0.into_inner() }
+        pub(crate) fn load(&self, order: Ordering) -> *mut T {
+            self.record("load", order, self.0.load(order))
+        }
+        pub(crate) fn store(&self, ptr: *mut T, order: Ordering) {
+            let seq = next_seq();
+            self.0.store(ptr, order);
+            record_at(seq, "store", self as *const Self as usize, order, ptr as usize as u64);
+        }
+        pub(crate) fn swap(&self, ptr: *mut T, order: Ordering) -> *mut T {
+            self.record("swap", order, self.0.swap(ptr, order))
+        }
+        pub(crate) fn compare_exchange(
+            &self, current: *mut T, new: *mut T, success: Ordering, failure: Ordering,
+        ) -> Result<*mut T, *mut T> {
+            match self.0.compare_exchange(current, new, success, failure) {
+                Ok(old) => Ok(self.record("cas", success, old)),
+                Err(old) => Err(self.record("cas_failed", failure, old)),
+            }
+        }
+        pub(crate) fn compare_exchange_weak(
+            &self, current: *mut T, new: *mut T, success: Ordering, failure: Ordering,
+        ) -> Result<*mut T, *mut T> {
+            match self.0.compare_exchange_weak(current, new, success, failure) {
+                Ok(old) => Ok(self.record("cas_weak", success, old)),
+                Err(old) => Err(self.record("cas_weak_failed", failure, old)),
+            }
+        }
+    }
+
+    impl<T> Default for AtomicPtr<T> {
+        fn default() -> Self { AtomicPtr(core_atomic::AtomicPtr::default()) }
+    }
+
+    impl<T> fmt::Debug for AtomicPtr<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { self.0.fmt(f) }
+    }
+}
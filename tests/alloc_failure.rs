@@ -0,0 +1,67 @@
+#![cfg(feature = "std")]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::ptr;
+
+use concurrent::listset::{CoarseListSet, SeqListSet, Set};
+use concurrent::lock::TASLock;
+
+// fails the nth allocation made on this thread from now on
+struct FailingAlloc;
+
+thread_local! {
+    static FAIL_IN: Cell<usize> = const { Cell::new(0) };
+}
+
+fn fail_nth_allocation(n: usize) { FAIL_IN.with(|fail_in| fail_in.set(n)); }
+
+unsafe impl GlobalAlloc for FailingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let fail = FAIL_IN.try_with(|fail_in| match fail_in.get() {
+            0 => false,
+            n => {
+                fail_in.set(n - 1);
+                n == 1
+            },
+        });
+        if fail == Ok(true) { return ptr::null_mut(); }
+        unsafe { System.alloc(layout) }
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOC: FailingAlloc = FailingAlloc;
+
+#[test]
+fn seq_try_add_hands_back_the_element() {
+    let mut set = SeqListSet::new();
+    for i in 0..10 { assert_eq!(set.try_add(i.to_string()), Ok(true)); }
+    let element = "failed".to_string();
+    fail_nth_allocation(1);
+    let error = set.try_add(element).unwrap_err();
+    assert_eq!(error.to_string(), "memory allocation failed");
+    assert_eq!(error.into_inner(), "failed");
+    assert_eq!(set.len(), 10);
+    assert!(!set.contains("failed".to_string()));
+    // the set is still usable afterwards
+    assert_eq!(set.try_add("failed".to_string()), Ok(true));
+    assert_eq!(set.try_add("failed".to_string()), Ok(false));
+    for i in 0..10 { assert!(set.contains(i.to_string())); }
+    assert_eq!(set.iter().count(), 11);
+}
+
+#[test]
+fn coarse_try_add_hands_back_the_element() {
+    let set = CoarseListSet::new(TASLock::new());
+    for i in 0..10 { assert_eq!(set.try_add(i), Ok(true)); }
+    fail_nth_allocation(1);
+    assert_eq!(set.try_add(42).unwrap_err().into_inner(), 42);
+    assert_eq!(set.len(), 10);
+    assert!(!set.contains(42));
+    assert_eq!(set.try_add(42), Ok(true));
+    assert_eq!(set.len(), 11);
+}
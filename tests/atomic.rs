@@ -0,0 +1,42 @@
+#![cfg(target_pointer_width = "64")]
+
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+use concurrent::atomic::AtomicStamped;
+
+// The ABA interleaving, played out on one thread: a reader sees A, then
+// A is swapped for B and back again before the reader's compare-exchange.
+// A plain AtomicPtr can't tell anything happened; the stamp can.
+#[test]
+fn stamp_catches_aba() {
+    let (mut a, mut b) = (1, 2);
+    let (a, b): (*mut i32, *mut i32) = (&mut a, &mut b);
+
+    let plain = AtomicPtr::new(a);
+    let seen = plain.load(Ordering::Acquire);
+    plain.store(b, Ordering::Release);
+    plain.store(a, Ordering::Release);
+    assert!(plain.compare_exchange(seen, b, Ordering::AcqRel, Ordering::Acquire).is_ok());
+
+    let stamped = AtomicStamped::new(a);
+    let (seen, stamp) = stamped.load(Ordering::Acquire);
+    stamped.swap(b, Ordering::AcqRel);
+    stamped.swap(a, Ordering::AcqRel);
+    let result = stamped.compare_exchange(seen, stamp, b, Ordering::AcqRel, Ordering::Acquire);
+    assert_eq!(result, Err((a, stamp.wrapping_add(2))));
+    assert_eq!(stamped.load(Ordering::Acquire), (a, 2));
+}
+
+#[test]
+fn stamp_wraps() {
+    let stamped = AtomicStamped::new(ptr::null_mut::<i32>());
+    for _ in 0..=u16::MAX { stamped.swap(ptr::null_mut(), Ordering::Relaxed); }
+    assert_eq!(stamped.load(Ordering::Relaxed), (ptr::null_mut(), 0));
+}
+
+#[test]
+#[should_panic(expected = "pointer does not fit under a stamp")]
+fn rejects_pointer_using_the_top_bits() {
+    AtomicStamped::new(ptr::without_provenance_mut::<i32>(1 << 56));
+}
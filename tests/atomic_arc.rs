@@ -0,0 +1,94 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use concurrent::atomic::AtomicArc;
+
+// counts the values still alive, so a leaked or doubly freed Arc shows up
+struct Generation {
+    number: usize,
+    live: &'static AtomicUsize,
+}
+
+impl Generation {
+    fn new(number: usize, live: &'static AtomicUsize) -> Arc<Self> {
+        live.fetch_add(1, Ordering::Relaxed);
+        Arc::new(Generation { number, live })
+    }
+}
+
+impl Drop for Generation {
+    fn drop(&mut self) { self.live.fetch_sub(1, Ordering::Relaxed); }
+}
+
+#[test]
+fn load_clone_shares_the_value() {
+    let atomic = AtomicArc::new(Arc::new(1));
+    let first = atomic.load_clone();
+    let second = atomic.load_clone();
+    assert!(Arc::ptr_eq(&first, &second));
+    assert_eq!(Arc::strong_count(&first), 3);
+    atomic.store(Arc::new(2));
+    assert_eq!(*atomic.load_clone(), 2);
+    // the replaced Arc lets go of its own count once nobody protects it
+    assert_eq!(Arc::strong_count(&first), 2);
+    assert_eq!(*atomic.into_inner(), 2);
+}
+
+#[test]
+fn stored_values_are_released() {
+    static LIVE: AtomicUsize = AtomicUsize::new(0);
+    let atomic = AtomicArc::new(Generation::new(0, &LIVE));
+    let kept = atomic.load_clone();
+    for number in 1..=10 { atomic.store(Generation::new(number, &LIVE)); }
+    assert_eq!(LIVE.load(Ordering::Relaxed), 2);
+    assert_eq!(kept.number, 0);
+    drop(kept);
+    drop(atomic);
+    assert_eq!(LIVE.load(Ordering::Relaxed), 0);
+}
+
+// readers clone while a writer keeps replacing the value: every clone is a
+// live generation no older than the last one that reader saw
+#[test]
+fn clones_race_stores() {
+    const GENERATIONS: usize = 500;
+    static LIVE: AtomicUsize = AtomicUsize::new(0);
+    let atomic = AtomicArc::new(Generation::new(0, &LIVE));
+    thread::scope(|s| {
+        for _ in 0..3 {
+            s.spawn(|| {
+                let mut last = 0;
+                while last < GENERATIONS {
+                    let value = atomic.load_clone();
+                    assert!(value.number >= last, "went back from {} to {}", last, value.number);
+                    last = value.number;
+                }
+            });
+        }
+        s.spawn(|| for number in 1..=GENERATIONS {
+            atomic.store(Generation::new(number, &LIVE));
+            if number % 16 == 0 { thread::yield_now(); }
+        });
+    });
+    assert_eq!(atomic.load_clone().number, GENERATIONS);
+    drop(atomic);
+    assert_eq!(LIVE.load(Ordering::Relaxed), 0);
+}
+
+// a stale current is refused and the new Arc comes back untouched
+#[test]
+fn compare_exchange_needs_the_current_arc() {
+    let atomic = AtomicArc::new(Arc::new(1));
+    let first = atomic.load_clone();
+    let old = atomic.swap(Arc::new(2));
+    assert!(Arc::ptr_eq(&old, &first));
+    let refused = atomic.compare_exchange(&first, Arc::new(3)).unwrap_err();
+    assert_eq!((*refused, Arc::strong_count(&refused)), (3, 1));
+    let current = atomic.load_clone();
+    assert!(atomic.compare_exchange(&current, Arc::new(4)).is_ok());
+    assert_eq!(*atomic.load_clone(), 4);
+    drop(atomic);
+    assert_eq!(Arc::strong_count(&current), 1);
+    assert_eq!(Arc::strong_count(&first), 2);
+}
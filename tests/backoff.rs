@@ -0,0 +1,65 @@
+use std::cell::RefCell;
+use std::time::Duration;
+
+use concurrent::backoff::{Phase, Sleeper, SpinBackoff};
+
+// sleeps by writing the duration down
+#[derive(Default)]
+struct Recorder(RefCell<Vec<Duration>>);
+
+impl Sleeper for &Recorder {
+    fn sleep(&self, duration: Duration) { self.0.borrow_mut().push(duration); }
+}
+
+const MS: Duration = Duration::from_millis(1);
+
+#[test]
+fn phases_follow_the_limits() {
+    let recorder = Recorder::default();
+    let mut backoff = SpinBackoff::with_sleeper(&recorder)
+        .with_limits(3, 5)
+        .with_sleeps(MS, 4 * MS);
+    let mut phases = Vec::new();
+    for _ in 0..10 {
+        phases.push(backoff.phase());
+        backoff.backoff();
+    }
+    use Phase::*;
+    assert_eq!(phases, [Spin, Spin, Spin, Yield, Yield, Sleep, Sleep, Sleep, Sleep, Sleep]);
+    // nothing sleeps before the yield limit; then the sleeps double up to the cap
+    assert_eq!(*recorder.0.borrow(), [MS, 2 * MS, 4 * MS, 4 * MS, 4 * MS]);
+    assert_eq!(backoff.last_sleep(), Some(4 * MS));
+    assert!(backoff.is_completed());
+    backoff.reset();
+    assert_eq!(backoff.phase(), Spin);
+    assert_eq!(backoff.last_sleep(), None);
+}
+
+#[test]
+fn default_limits() {
+    let recorder = Recorder::default();
+    let mut backoff = SpinBackoff::with_sleeper(&recorder);
+    for step in 0..20 {
+        let expected = if step < 10 { Phase::Spin } else { Phase::Yield };
+        assert_eq!(backoff.phase(), expected, "step {}", step);
+        backoff.backoff();
+    }
+    assert!(recorder.0.borrow().is_empty());
+    assert_eq!(backoff.phase(), Phase::Sleep);
+    backoff.backoff();
+    assert_eq!(*recorder.0.borrow(), [Duration::from_micros(10)]);
+}
+
+#[test]
+fn snooze_never_sleeps() {
+    let recorder = Recorder::default();
+    let mut backoff = SpinBackoff::with_sleeper(&recorder).with_limits(1, 2);
+    for _ in 0..10 { backoff.snooze(); }
+    assert_eq!(backoff.phase(), Phase::Sleep);
+    assert!(recorder.0.borrow().is_empty());
+    assert_eq!(backoff.last_sleep(), None);
+}
+
+#[test]
+#[should_panic(expected = "spin limit is past the yield limit")]
+fn rejects_crossed_limits() { let _ = SpinBackoff::new().with_limits(5, 3); }
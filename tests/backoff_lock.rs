@@ -0,0 +1,92 @@
+// BackoffLock only sleeps after it loses the swap for a lock that looked
+// free. The chaos pause between the two is what makes that happen often
+// enough to test.
+#![cfg(feature = "chaos")]
+
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use concurrent::clock::Clock;
+use concurrent::lock::{BackoffLock, Lock};
+
+// sleeps for real, and writes each sleep down
+#[derive(Default)]
+struct Recorder(Mutex<Vec<Duration>>);
+
+impl Recorder {
+    fn sleeps(&self) -> Vec<Duration> { self.0.lock().unwrap().clone() }
+}
+
+impl Clock for Recorder {
+    fn now(&self) -> Instant { Instant::now() }
+    fn sleep(&self, duration: Duration) {
+        self.0.lock().unwrap().push(duration);
+        thread::sleep(duration);
+    }
+}
+
+const THREADS: usize = 4;
+const ROUNDS: usize = 50;
+
+// contends until some acquire has lost a race and slept; calls inside with
+// the lock held
+fn contend<C: Clock>(lock: &BackoffLock<C>, recorder: &Recorder, inside: impl Fn() + Sync) {
+    for _ in 0..ROUNDS {
+        thread::scope(|s| for _ in 0..THREADS {
+            s.spawn(|| for _ in 0..100 {
+                let _guard = lock.acquire();
+                inside();
+                thread::yield_now();
+            });
+        });
+        if !recorder.sleeps().is_empty() { return; }
+    }
+    panic!("no acquire lost a race in {} rounds", ROUNDS);
+}
+
+#[test]
+fn sleeps_stay_within_custom_delays() {
+    let (min, max) = (Duration::from_micros(3), Duration::from_micros(24));
+    let recorder = Recorder::default();
+    let lock = BackoffLock::with_clock(&recorder).with_delays(min, max);
+    contend(&lock, &recorder, || ());
+    // each wait starts at the minimum and doubles up to the maximum
+    let allowed = [min, 2 * min, 4 * min, max];
+    for sleep in recorder.sleeps() {
+        assert!(allowed.contains(&sleep), "slept {:?}", sleep);
+    }
+}
+
+#[test]
+fn estimate_rises_and_decays() {
+    let (min, max) = (Duration::from_micros(3), Duration::from_micros(200));
+    let recorder = Recorder::default();
+    let lock = BackoffLock::with_clock(&recorder).with_delays(min, max).adaptive();
+    assert_eq!(lock.delay_estimate(), Duration::ZERO);
+    // every acquire updates the estimate before it returns, so reading it
+    // with the lock held gives the estimates in acquisition order
+    let estimates = Mutex::new(vec![Duration::ZERO]);
+    contend(&lock, &recorder, || estimates.lock().unwrap().push(lock.delay_estimate()));
+    let estimates = estimates.into_inner().unwrap();
+    let halve = |estimate: Duration| Duration::from_nanos(estimate.as_nanos() as u64 / 2);
+    let mut decayed = false;
+    for pair in estimates.windows(2) {
+        let (before, after) = (pair[0], pair[1]);
+        // an acquire that slept sets it to that sleep; one that didn't halves it
+        if after == halve(before) {
+            decayed |= before > Duration::ZERO;
+        } else {
+            assert!(min <= after && after <= max, "estimate went from {:?} to {:?}", before, after);
+        }
+    }
+    assert!(estimates.iter().any(|&estimate| estimate >= min), "the estimate never rose");
+    assert!(decayed, "the estimate never fell");
+    // alone, every acquire gets the lock without sleeping
+    let mut estimate = lock.delay_estimate();
+    while estimate > Duration::ZERO {
+        drop(lock.acquire());
+        estimate = halve(estimate);
+        assert_eq!(lock.delay_estimate(), estimate);
+    }
+}
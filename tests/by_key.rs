@@ -0,0 +1,62 @@
+use std::hash::{Hash, Hasher};
+
+use concurrent::listset::{CoarseListSet, ConcurrentSet, MutSet, RefCountListSet, SeqListSet};
+use concurrent::lock::TASLock;
+
+// hashes by id alone, so the id is enough to find a record's node
+#[derive(Debug, Clone, PartialEq)]
+struct Record(u64, String);
+
+impl Hash for Record {
+    fn hash<H: Hasher>(&self, state: &mut H) { self.0.hash(state); }
+}
+
+fn records() -> impl Iterator<Item = Record> {
+    [(1, "one"), (2, "two"), (3, "three")].into_iter()
+        .map(|(id, name)| Record(id, name.to_string()))
+}
+
+fn named(name: &str) -> impl Fn(&Record) -> bool + '_ { move |record| record.1 == name }
+
+#[test]
+fn seq_by_key() {
+    let mut set = SeqListSet::new();
+    for record in records() { set.add(record); }
+    let two = set.make_key(&2u64);
+    assert!(set.contains_by_key(two, named("two")));
+    assert_eq!(set.get_by_key(two, named("two")), Some(&Record(2, "two".to_string())));
+    // the node is there, but eq turns it down
+    assert!(!set.contains_by_key(two, named("three")));
+    assert_eq!(set.get_by_key(two, named("three")), None);
+    let four = set.make_key(&4u64);
+    assert!(!set.contains_by_key(four, |_| true));
+    assert_eq!(set.get_by_key(four, |_| true), None);
+}
+
+#[test]
+fn coarse_by_key() {
+    let set = CoarseListSet::new(TASLock::new());
+    for record in records() { ConcurrentSet::add(&set, record); }
+    let two = set.make_key(&2u64);
+    assert!(set.contains_by_key(two, named("two")));
+    assert_eq!(set.get_by_key(two, named("two")), Some(Record(2, "two".to_string())));
+    assert!(!set.contains_by_key(two, named("three")));
+    assert_eq!(set.get_by_key(two, named("three")), None);
+    let four = set.make_key(&4u64);
+    assert!(!set.contains_by_key(four, |_| true));
+    assert_eq!(set.get_by_key(four, |_| true), None);
+}
+
+#[test]
+fn refcount_by_key() {
+    let set = RefCountListSet::new(TASLock::new());
+    for record in records() { set.add(record); }
+    let two = set.make_key(&2u64);
+    assert!(set.contains_by_key(two, named("two")));
+    assert_eq!(set.get_by_key(two, named("two")), Some(Record(2, "two".to_string())));
+    assert!(!set.contains_by_key(two, named("three")));
+    assert_eq!(set.get_by_key(two, named("three")), None);
+    let four = set.make_key(&4u64);
+    assert!(!set.contains_by_key(four, |_| true));
+    assert_eq!(set.get_by_key(four, |_| true), None);
+}
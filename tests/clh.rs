@@ -0,0 +1,93 @@
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use concurrent::lock::{CLHLock, CLHSendGuard, Lock, TryLock};
+
+const WAITERS: usize = 3;
+
+// polls until cond holds, for up to five seconds
+fn eventually(what: &str, cond: impl Fn() -> bool) {
+    let start = Instant::now();
+    while !cond() {
+        assert!(start.elapsed() < Duration::from_secs(5), "timed out waiting until {}", what);
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn depth_hint_counts_parked_waiters() {
+    let lock: CLHLock = CLHLock::new();
+    assert_eq!(lock.queue_depth_hint(), 0);
+    let guard = lock.acquire();
+    // the holder isn't waiting
+    assert_eq!(lock.queue_depth_hint(), 0);
+    thread::scope(|s| {
+        for _ in 0..WAITERS { s.spawn(|| drop(lock.acquire())); }
+        eventually("every waiter is queued", || lock.queue_depth_hint() == WAITERS);
+        // refused without joining the queue
+        assert!(lock.acquire_if_shallow(WAITERS - 1).is_none());
+        assert!(lock.acquire_if_shallow(0).is_none());
+        assert_eq!(lock.queue_depth_hint(), WAITERS);
+        drop(guard);
+    });
+    assert_eq!(lock.queue_depth_hint(), 0);
+    assert!(lock.acquire_if_shallow(0).is_some());
+}
+
+#[test]
+fn yield_lets_queued_waiters_go_first() {
+    let lock: CLHLock = CLHLock::new();
+    let order = Mutex::new(Vec::new());
+    let guard = lock.acquire();
+    thread::scope(|s| {
+        for _ in 0..WAITERS {
+            s.spawn(|| {
+                let _guard = lock.acquire();
+                order.lock().unwrap().push("waiter");
+            });
+        }
+        eventually("every waiter is queued", || lock.queue_depth_hint() == WAITERS);
+        let _guard = guard.yield_to_waiters();
+        order.lock().unwrap().push("holder");
+    });
+    let mut expected = vec!["waiter"; WAITERS];
+    expected.push("holder");
+    assert_eq!(order.into_inner().unwrap(), expected);
+}
+
+// with nobody waiting the guard comes straight back, still held
+#[test]
+fn yield_alone_keeps_the_lock() {
+    let lock: CLHLock = CLHLock::new();
+    let guard = lock.acquire().yield_to_waiters();
+    assert!(lock.try_acquire().is_none());
+    drop(guard);
+    assert!(lock.try_acquire().is_some());
+}
+
+// the guard is taken on one thread and released on another; the value
+// bequeathed before the move still reaches the next holder
+#[test]
+fn send_guard_releases_on_another_thread() {
+    let lock: CLHLock<u32> = CLHLock::with_handoff();
+    let (sender, receiver) = mpsc::channel::<CLHSendGuard<u32>>();
+    thread::scope(|s| {
+        let releaser = s.spawn(move || {
+            let mut guard = receiver.recv().unwrap().into_inner();
+            guard.bequeath(7);
+            drop(guard);
+        });
+        let guard = lock.acquire();
+        assert!(lock.try_acquire().is_none());
+        sender.send(guard.into_send_guard()).unwrap();
+        releaser.join().unwrap();
+    });
+    let mut guard = lock.try_acquire().expect("the other thread didn't release the lock");
+    assert_eq!(guard.take_inherited(), Some(7));
+}
+
+#[test]
+fn guards_only_send_when_converted() {
+    trybuild::TestCases::new().compile_fail("tests/ui/clh_*.rs");
+}
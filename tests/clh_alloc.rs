@@ -0,0 +1,68 @@
+// tracing allocates for every atomic operation it logs
+#![cfg(not(feature = "trace-atomics"))]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::thread;
+
+use concurrent::lock::{CLHLock, Lock};
+
+// counts the allocations made on each thread
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn allocations() -> usize { ALLOCATIONS.with(Cell::get) }
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+#[test]
+fn plain_acquire_allocates() {
+    let lock = CLHLock::new();
+    let before = allocations();
+    drop(lock.acquire());
+    assert!(allocations() > before);
+}
+
+#[test]
+fn handle_reuses_nodes_alone() {
+    let lock = CLHLock::new();
+    let mut handle = lock.handle();
+    drop(handle.acquire());
+    let before = allocations();
+    for _ in 0..1000 { drop(handle.acquire()); }
+    assert_eq!(allocations(), before);
+}
+
+// nodes pass between the threads as they take turns, but each handle
+// always has a spare to hand in
+#[test]
+fn handles_reuse_nodes_under_contention() {
+    let lock = CLHLock::new();
+    thread::scope(|s| for _ in 0..3 {
+        s.spawn(|| {
+            let mut handle = lock.handle();
+            drop(handle.acquire());
+            let before = allocations();
+            for _ in 0..1000 {
+                let guard = handle.acquire();
+                thread::yield_now();
+                drop(guard);
+            }
+            assert_eq!(allocations(), before, "allocated after warm-up");
+        });
+    });
+}
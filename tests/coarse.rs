@@ -0,0 +1,209 @@
+#![cfg(feature = "std")]
+
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Barrier;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use concurrent::listset::{CoarseListSet, ConcurrentSet, MutSet, Set};
+use concurrent::lock::{CLHGuard, CLHLock, Lock, TASGuard, TASLock};
+use rand::random;
+
+const KEYS: usize = 64;
+
+#[test]
+fn transact_returns_and_applies() {
+    let set = CoarseListSet::new(TASLock::new());
+    let added = set.transact(|seq| (0..10).filter(|&i| seq.add(i)).count());
+    assert_eq!(added, 10);
+    assert_eq!(set.len(), 10);
+}
+
+// the panic leaves the lock free and keeps the edits made before it
+#[test]
+fn transact_panic_releases_lock() {
+    let set = CoarseListSet::new(TASLock::new());
+    ConcurrentSet::add(&set, 0);
+    let result = panic::catch_unwind(AssertUnwindSafe(|| set.transact(|seq| {
+        seq.add(1);
+        seq.remove(0);
+        panic!("halfway through");
+    })));
+    assert!(result.is_err());
+    assert!(ConcurrentSet::add(&set, 2));
+    assert!(!set.contains(0));
+    assert!(set.contains(1));
+    assert_eq!(set.len(), 2);
+    assert_eq!(set.transact(|seq| seq.len()), 2);
+}
+
+// Searches let go of the lock after every node, and the queue lock hands
+// it to a waiting writer each time, so removes keep landing in the middle
+// of a search and sending it back to the head. Even keys never change and
+// must always be found; odd keys come and go, but a search for one can
+// only ever have seen it or not.
+#[test]
+fn yielding_search_restarts_after_removes() {
+    let set = CoarseListSet::new(CLHLock::new()).yield_every(1);
+    for key in 0..KEYS { ConcurrentSet::add(&set, key); }
+    let done = AtomicBool::new(false);
+    thread::scope(|s| {
+        s.spawn(|| {
+            for _ in 0..20 {
+                for key in (1..KEYS).step_by(2) { assert!(ConcurrentSet::remove(&set, key)); }
+                for key in (1..KEYS).step_by(2) { assert!(ConcurrentSet::add(&set, key)); }
+            }
+            done.store(true, Ordering::Relaxed);
+        });
+        while !done.load(Ordering::Relaxed) {
+            for key in (0..KEYS).step_by(2) { assert!(set.contains(key), "lost key {}", key); }
+            for key in (1..KEYS).step_by(2) { set.contains(key); }
+        }
+    });
+    for key in 0..KEYS { assert!(set.contains(key)); }
+    assert_eq!(set.len(), KEYS);
+}
+
+// counts acquisitions, and holds the one numbered pause_at back from the
+// lock until the test has gone through the barrier twice
+struct PausingLock<'a> {
+    inner: TASLock,
+    acquires: &'a AtomicUsize,
+    pause_at: &'a AtomicUsize,
+    barrier: &'a Barrier,
+}
+
+impl Lock for PausingLock<'_> {
+    type Guard<'a> = TASGuard<'a> where Self: 'a;
+    fn acquire(&self) -> Self::Guard<'_> {
+        let number = self.acquires.fetch_add(1, Ordering::SeqCst) + 1;
+        if number == self.pause_at.load(Ordering::SeqCst) {
+            self.barrier.wait();
+            self.barrier.wait();
+        }
+        self.inner.acquire()
+    }
+}
+
+// A search for the last of four keys yields after the first node, and a
+// remove of the second node lands right then. The search has to start
+// over from the head: one acquire to start, one after the first node,
+// then one after each of the two nodes ahead of the key on the way back.
+#[test]
+fn yielding_search_restarts_from_head() {
+    let (acquires, pause_at) = (AtomicUsize::new(0), AtomicUsize::new(0));
+    let barrier = Barrier::new(2);
+    let lock = PausingLock {
+        inner: TASLock::new(), acquires: &acquires, pause_at: &pause_at, barrier: &barrier,
+    };
+    let set = CoarseListSet::new(lock).yield_every(1);
+    let mut order: Vec<usize> = (0..4).collect();
+    order.sort_by_key(|key| set.make_key(key));
+    for &key in &order { ConcurrentSet::add(&set, key); }
+    acquires.store(0, Ordering::SeqCst);
+    pause_at.store(2, Ordering::SeqCst);
+    thread::scope(|s| {
+        let search = s.spawn(|| set.contains(order[3]));
+        barrier.wait();
+        assert!(ConcurrentSet::remove(&set, order[1]));
+        barrier.wait();
+        assert!(search.join().unwrap());
+    });
+    // four for the search; the remove yields too, after the first node
+    assert_eq!(acquires.load(Ordering::SeqCst), 6);
+}
+
+// polls until cond holds, for up to five seconds
+fn eventually(what: &str, cond: impl Fn() -> bool) {
+    let start = Instant::now();
+    while !cond() {
+        assert!(start.elapsed() < Duration::from_secs(5), "timed out waiting until {}", what);
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+// Each thread works on keys of its own, so a plain HashSet per thread
+// predicts every result even with the calls applied by other threads. The
+// adds that went in less the removes that took something out must add up
+// to what is left.
+#[test]
+fn combining_matches_model() {
+    const THREADS: u64 = 4;
+    const OPS: usize = 2000;
+    let set = CoarseListSet::new(CLHLock::new()).combining();
+    let net: i64 = thread::scope(|s| {
+        let threads: Vec<_> = (0..THREADS).map(|thread| {
+            let set = &set;
+            s.spawn(move || {
+                let mut model = HashSet::new();
+                let mut net = 0;
+                for _ in 0..OPS {
+                    let key = random::<u64>() % KEYS as u64 * THREADS + thread;
+                    if random::<bool>() {
+                        let added = ConcurrentSet::add(set, key);
+                        assert_eq!(added, model.insert(key), "add {}", key);
+                        net += added as i64;
+                    } else {
+                        let removed = ConcurrentSet::remove(set, key);
+                        assert_eq!(removed, model.remove(&key), "remove {}", key);
+                        net -= removed as i64;
+                    }
+                }
+                for key in model { assert!(set.contains(key)); }
+                net
+            })
+        }).collect();
+        threads.into_iter().map(|thread| thread.join().unwrap()).sum()
+    });
+    assert_eq!(set.len() as i64, net);
+}
+
+// hashes like its id, or panics
+#[derive(Clone, Copy)]
+struct Touchy { id: u64, panics: bool }
+
+impl Hash for Touchy {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        assert!(!self.panics, "touchy hash");
+        self.id.hash(state);
+    }
+}
+
+// lets the test watch the queue of a lock the set owns
+struct Borrowed<'a>(&'a CLHLock);
+
+impl Lock for Borrowed<'_> {
+    type Guard<'a> = CLHGuard<'a> where Self: 'a;
+    fn acquire(&self) -> Self::Guard<'_> { self.0.acquire() }
+}
+
+// Four writers queue on the lock in turn while the test holds it. The
+// first gets the lock next and applies everyone's ops, newest first, and
+// the third's hash panics. The second's op, which it hadn't got to yet,
+// must still be applied; the third panics as well rather than answering.
+#[test]
+fn combining_panic_requeues_the_rest() {
+    let lock = CLHLock::new();
+    let set = CoarseListSet::new(Borrowed(&lock)).combining();
+    let key = |id| Touchy { id, panics: false };
+    let elements = [key(1), key(2), Touchy { id: 3, panics: true }, key(4)];
+    let results: Vec<_> = thread::scope(|s| {
+        let writers: Vec<_> = set.transact(|_| {
+            elements.into_iter().enumerate().map(|(waiting, element)| {
+                let set = &set;
+                let writer = s.spawn(move || ConcurrentSet::add(set, element));
+                eventually("the writer is queued", || lock.queue_depth_hint() == waiting + 1);
+                writer
+            }).collect()
+        });
+        writers.into_iter().map(|writer| writer.join().ok()).collect()
+    });
+    assert_eq!(results, [None, Some(true), None, Some(true)]);
+    assert!(!set.contains(key(1)));
+    assert!(set.contains(key(2)));
+    assert!(set.contains(key(4)));
+    assert_eq!(set.len(), 2);
+}
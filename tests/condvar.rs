@@ -0,0 +1,81 @@
+#![cfg(feature = "std")]
+
+use std::collections::VecDeque;
+use std::thread;
+
+use concurrent::lock::{CLHLock, Condvar, Lock, Mutex, TTASLock};
+
+const CAPACITY: usize = 4;
+const PRODUCERS: usize = 2;
+const CONSUMERS: usize = 2;
+const ITEMS: usize = 500;
+
+struct BoundedBuffer<L: Lock> {
+    items: Mutex<VecDeque<(usize, usize)>, L>,
+    not_full: Condvar,
+    not_empty: Condvar,
+}
+
+impl<L: Lock> BoundedBuffer<L> {
+    fn new(lock: L) -> Self {
+        BoundedBuffer {
+            items: Mutex::new(VecDeque::new(), lock),
+            not_full: Condvar::new(),
+            not_empty: Condvar::new(),
+        }
+    }
+    fn push(&self, item: (usize, usize)) {
+        let guard = self.items.lock();
+        let mut items = self.not_full.wait_while(guard, |items| items.len() == CAPACITY);
+        items.push_back(item);
+        drop(items);
+        self.not_empty.notify_one();
+    }
+    fn pop(&self) -> (usize, usize) {
+        let guard = self.items.lock();
+        let mut items = self.not_empty.wait_while(guard, |items| items.is_empty());
+        assert!(items.len() <= CAPACITY, "buffer overfilled");
+        let item = items.pop_front().unwrap();
+        drop(items);
+        self.not_full.notify_one();
+        item
+    }
+}
+
+// every item comes out exactly once, and each consumer sees any one
+// producer's items in the order they went in
+fn producer_consumer<L: Lock>(lock: L) {
+    let buffer = BoundedBuffer::new(lock);
+    let mut received = vec![Vec::new(); PRODUCERS];
+    thread::scope(|s| {
+        for producer in 0..PRODUCERS {
+            let buffer = &buffer;
+            s.spawn(move || for seq in 0..ITEMS { buffer.push((producer, seq)); });
+        }
+        let consumers: Vec<_> = (0..CONSUMERS).map(|_| s.spawn(|| {
+            let mut last = [None; PRODUCERS];
+            let mut taken = Vec::new();
+            for _ in 0..PRODUCERS * ITEMS / CONSUMERS {
+                let (producer, seq) = buffer.pop();
+                assert!(last[producer] < Some(seq), "out of order");
+                last[producer] = Some(seq);
+                taken.push((producer, seq));
+            }
+            taken
+        })).collect();
+        for consumer in consumers {
+            for (producer, seq) in consumer.join().unwrap() { received[producer].push(seq); }
+        }
+    });
+    for mut seqs in received {
+        seqs.sort_unstable();
+        assert_eq!(seqs, (0..ITEMS).collect::<Vec<_>>());
+    }
+    assert!(buffer.items.into_inner().is_empty());
+}
+
+#[test]
+fn bounded_buffer_ttas() { producer_consumer(TTASLock::new()); }
+
+#[test]
+fn bounded_buffer_clh() { producer_consumer(CLHLock::new()); }
@@ -0,0 +1,105 @@
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use concurrent::config::Rcu;
+use concurrent::lock::TASLock;
+
+const THREADS: usize = 4;
+const UPDATES: usize = 200;
+
+// doubled is always twice count in a whole version, and live counts the
+// versions not yet dropped
+struct Config {
+    count: usize,
+    doubled: usize,
+    live: &'static AtomicUsize,
+}
+
+impl Config {
+    fn new(count: usize, live: &'static AtomicUsize) -> Self {
+        live.fetch_add(1, Ordering::Relaxed);
+        Config { count, doubled: 2 * count, live }
+    }
+    fn next(&self) -> Self { Config::new(self.count + 1, self.live) }
+}
+
+impl Drop for Config {
+    fn drop(&mut self) { self.live.fetch_sub(1, Ordering::Relaxed); }
+}
+
+#[test]
+fn readers_see_whole_versions() {
+    static LIVE: AtomicUsize = AtomicUsize::new(0);
+    let rcu = Rcu::new(Config::new(0, &LIVE), TASLock::new());
+    thread::scope(|s| {
+        for _ in 0..2 {
+            s.spawn(|| {
+                let mut last = 0;
+                while last < THREADS * UPDATES {
+                    let config = rcu.read();
+                    assert_eq!(config.doubled, 2 * config.count, "torn version");
+                    assert!(config.count >= last, "went back from {} to {}", last, config.count);
+                    last = config.count;
+                }
+            });
+        }
+        for _ in 0..THREADS {
+            s.spawn(|| for _ in 0..UPDATES { rcu.update(Config::next); });
+        }
+    });
+    assert_eq!(rcu.read().count, THREADS * UPDATES);
+}
+
+// every update lands exactly once, whether mixed with replaces or not, and
+// each successful swap is announced once with the version it installed
+#[test]
+fn updates_compose_and_notify_once() {
+    static LIVE: AtomicUsize = AtomicUsize::new(0);
+    let rcu = Rcu::new(Config::new(0, &LIVE), TASLock::new());
+    let notified = Arc::new(Mutex::new(Vec::new()));
+    let seen = notified.clone();
+    rcu.on_change(move |config| seen.lock().unwrap().push(config.count));
+    let installed: Vec<usize> = thread::scope(|s| {
+        let updaters: Vec<_> = (0..THREADS).map(|_| s.spawn(|| {
+            (0..UPDATES).map(|_| rcu.update(Config::next).count).collect::<Vec<_>>()
+        })).collect();
+        updaters.into_iter().flat_map(|updater| updater.join().unwrap()).collect()
+    });
+    assert_eq!(rcu.read().count, THREADS * UPDATES);
+    let mut installed = installed;
+    installed.sort_unstable();
+    assert_eq!(installed, (1..=THREADS * UPDATES).collect::<Vec<_>>());
+    let old = rcu.replace(Config::new(0, &LIVE));
+    assert_eq!(old.count, THREADS * UPDATES);
+    let mut notified = notified.lock().unwrap().clone();
+    assert_eq!(notified.pop(), Some(0));
+    notified.sort_unstable();
+    assert_eq!(notified, installed);
+}
+
+// Replaced versions may wait in the hazard domain until the cell goes, but
+// once it has, every count it held is given back and only the versions
+// still held outside it are alive.
+#[test]
+fn versions_are_released() {
+    static LIVE: AtomicUsize = AtomicUsize::new(0);
+    let rcu = Rcu::new(Config::new(0, &LIVE), TASLock::new());
+    let first = rcu.read();
+    thread::scope(|s| {
+        for _ in 0..THREADS {
+            s.spawn(|| for i in 0..UPDATES {
+                if i % 2 == 0 { rcu.update(Config::next); }
+                else { drop(rcu.replace(Config::new(i, &LIVE))); }
+                drop(rcu.read());
+            });
+        }
+    });
+    let last = rcu.read();
+    drop(rcu);
+    assert_eq!(Arc::strong_count(&first), 1);
+    assert_eq!(Arc::strong_count(&last), 1);
+    assert_eq!(LIVE.load(Ordering::Relaxed), 2);
+    drop((first, last));
+    assert_eq!(LIVE.load(Ordering::Relaxed), 0);
+}
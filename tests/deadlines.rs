@@ -0,0 +1,106 @@
+#![cfg(feature = "std")]
+
+// BackoffLock's timed waiters share one reading of the clock. The test
+// clock only moves when told to, and notes which threads read it.
+
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant};
+
+use concurrent::clock::{Clock, TestClock};
+use concurrent::lock::{BackoffLock, Lock, TryLock};
+
+const TIMEOUT: Duration = Duration::from_millis(10);
+const LONG: Duration = Duration::from_secs(1);
+const WAITERS: usize = 64;
+
+#[derive(Default)]
+struct Watched {
+    clock: TestClock,
+    readers: Mutex<HashSet<ThreadId>>,
+}
+
+impl Watched {
+    fn readers(&self) -> usize { self.readers.lock().unwrap().len() }
+    fn forget_readers(&self) { self.readers.lock().unwrap().clear(); }
+}
+
+impl Clock for Watched {
+    fn now(&self) -> Instant {
+        self.readers.lock().unwrap().insert(thread::current().id());
+        self.clock.now()
+    }
+    fn sleep(&self, duration: Duration) { self.clock.sleep(duration) }
+}
+
+// polls until cond holds, for up to five seconds of real time
+fn eventually(what: &str, cond: impl Fn() -> bool) {
+    let start = Instant::now();
+    while !cond() {
+        assert!(start.elapsed() < Duration::from_secs(5), "timed out waiting until {}", what);
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+// Every waiter starts at time zero. With the clock a nanosecond short of
+// the deadline nobody gives up, however long they wait; once it gets
+// there they all do, on that very reading.
+fn check_accuracy(waiters: usize) {
+    let clock = Watched::default();
+    let lock = BackoffLock::with_clock(&clock);
+    let guard = lock.acquire();
+    thread::scope(|s| {
+        let handles: Vec<_> = (0..waiters).map(|_| s.spawn(|| {
+            assert!(lock.acquire_timeout(TIMEOUT).is_err(), "acquired a held lock");
+            clock.clock.elapsed()
+        })).collect();
+        eventually("every waiter has read the clock", || clock.readers() == waiters);
+        clock.clock.advance(TIMEOUT - Duration::from_nanos(1));
+        thread::sleep(Duration::from_millis(20));
+        assert!(handles.iter().all(|handle| !handle.is_finished()), "gave up early");
+        clock.clock.advance(Duration::from_nanos(1));
+        for handle in handles { assert_eq!(handle.join().unwrap(), TIMEOUT); }
+    });
+    drop(guard);
+}
+
+#[test]
+fn lone_waiter_times_out_on_time() { check_accuracy(1); }
+
+#[test]
+fn many_waiters_time_out_on_time() { check_accuracy(WAITERS); }
+
+// returns how many of the waiters went on reading the clock once they had
+// all started, while the lock stayed held and the clock stood still
+fn readers_while_waiting(granularity: Duration, settle: impl Fn(&Watched)) -> usize {
+    let clock = Watched::default();
+    let lock = BackoffLock::with_clock(&clock).with_granularity(granularity);
+    let guard = lock.acquire();
+    thread::scope(|s| {
+        for _ in 0..WAITERS {
+            s.spawn(|| assert!(lock.acquire_timeout(LONG).is_ok()));
+        }
+        eventually("every waiter has read the clock", || clock.readers() == WAITERS);
+        clock.forget_readers();
+        settle(&clock);
+        let readers = clock.readers();
+        drop(guard);
+        readers
+    })
+}
+
+// Far from their deadlines, the waiters leave the clock to whichever of
+// them keeps the time. Near them, as they always are when the granularity
+// is as long as the timeout, each one reads it.
+#[test]
+fn one_waiter_keeps_the_time() {
+    let readers = readers_while_waiting(Duration::from_millis(1), |_| {
+        thread::sleep(Duration::from_millis(20));
+    });
+    assert_eq!(readers, 1);
+    let readers = readers_while_waiting(LONG, |clock| {
+        eventually("every waiter reads the clock", || clock.readers() == WAITERS);
+    });
+    assert_eq!(readers, WAITERS);
+}
@@ -0,0 +1,57 @@
+#![cfg(feature = "std")]
+
+use std::time::Duration;
+
+use concurrent::error::{BorrowError, Closed, TimedOut};
+use concurrent::lock::{ArrayLock, Lock, TASLock, TryLock};
+use concurrent::oneshot;
+
+#[test]
+fn capacity_message() {
+    let lock = ArrayLock::new(1);
+    let _guard = lock.acquire();
+    let error = lock.acquire_checked().err().unwrap();
+    assert_eq!(error, BorrowError::ThreadCapacityExceeded);
+    assert_eq!(error.to_string(), "too many threads trying to acquire the lock");
+}
+
+#[test]
+#[should_panic(expected = "too many threads trying to acquire the lock")]
+fn acquire_panics_with_capacity_message() {
+    let lock = ArrayLock::new(1);
+    let _guard = lock.acquire();
+    let _ = lock.acquire();
+}
+
+#[test]
+fn timeout_message() {
+    let lock = ArrayLock::new(1);
+    let _guard = lock.acquire();
+    let error = lock.acquire_checked_timeout(Duration::from_millis(1)).err().unwrap();
+    assert_eq!(error, BorrowError::Timeout);
+    assert_eq!(error.to_string(), "timed out waiting for room in the lock");
+}
+
+#[test]
+fn timed_acquire_message() {
+    let lock = TASLock::new();
+    let _guard = lock.acquire();
+    let error = lock.acquire_timeout(Duration::from_millis(1)).err().unwrap();
+    assert_eq!(error, TimedOut);
+    assert_eq!(error.to_string(), "timed out waiting for the lock");
+}
+
+#[test]
+fn closed_message() {
+    let (sender, receiver) = oneshot::channel::<()>();
+    drop(sender);
+    let error = receiver.recv().unwrap_err();
+    assert_eq!(error, Closed);
+    assert_eq!(error.to_string(), "channel closed: sender dropped without sending");
+}
+
+#[test]
+fn errors_are_std_errors() {
+    let error: Box<dyn std::error::Error> = Box::new(BorrowError::Timeout);
+    assert!(error.source().is_none());
+}
@@ -0,0 +1,116 @@
+#![cfg(feature = "std")]
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use concurrent::clock::Clock;
+use concurrent::metrics::{EventKind, EventRecord, EventRing};
+
+const KINDS: [EventKind; 5] = [
+    EventKind::AcquireStart,
+    EventKind::AcquireComplete,
+    EventKind::Release,
+    EventKind::TryFail,
+    EventKind::Timeout,
+];
+const WRITERS: u64 = 4;
+const READERS: usize = 2;
+const RECORDS: u64 = 500_000;
+// micros per writer, so a record's time tells which writer made it
+const SPAN: u64 = 1 << 32;
+
+thread_local! {
+    static STAMP: Cell<u64> = const { Cell::new(0) };
+}
+
+// reads as however many micros past base the calling thread last set
+struct Stamped { base: Instant }
+
+impl Clock for Stamped {
+    fn now(&self) -> Instant { self.base + Duration::from_micros(STAMP.with(Cell::get)) }
+    fn sleep(&self, duration: Duration) { thread::sleep(duration) }
+}
+
+fn ring(capacity: usize) -> EventRing<Stamped> {
+    STAMP.with(|stamp| stamp.set(0));
+    EventRing::with_clock(capacity, Stamped { base: Instant::now() })
+}
+
+// writer n stamps its records (n + 1) * SPAN + i for its ith record, and
+// always logs the same kind
+fn write(ring: &EventRing<Stamped>, writer: u64, records: u64) {
+    for i in 0..records {
+        STAMP.with(|stamp| stamp.set((writer + 1) * SPAN + i));
+        ring.record(KINDS[writer as usize % KINDS.len()]);
+    }
+}
+
+// Every record has to be one that some writer made whole: its kind matches
+// its stamp, its thread tag belongs to that one writer, and each writer's
+// records come out in the order it made them.
+fn check(records: &[EventRecord], writers: &mut HashMap<u16, u64>) {
+    assert!(records.windows(2).all(|pair| pair[0].seq < pair[1].seq), "out of order");
+    let mut last = HashMap::new();
+    for record in records {
+        let writer = record.micros / SPAN - 1;
+        assert!(writer < WRITERS, "torn record {:?}", record);
+        assert_eq!(record.kind, KINDS[writer as usize % KINDS.len()], "torn record {:?}", record);
+        assert_eq!(*writers.entry(record.thread).or_insert(writer), writer, "torn {:?}", record);
+        if let Some(previous) = last.insert(record.thread, record.micros) {
+            assert!(previous < record.micros, "writer {} out of order", writer);
+        }
+    }
+}
+
+#[test]
+fn overwrites_at_wrap() {
+    let ring = ring(8);
+    for i in 0..20 { ring.record(KINDS[i % KINDS.len()]); }
+    let records = ring.dump();
+    let seqs: Vec<_> = records.iter().map(|record| record.seq).collect();
+    assert_eq!(seqs, (13..=20).collect::<Vec<_>>());
+    for record in records { assert_eq!(record.kind, KINDS[(record.seq - 1) as usize % KINDS.len()]); }
+}
+
+#[test]
+fn dump_keeps_each_threads_order() {
+    let ring = ring(1024);
+    thread::scope(|s| for writer in 0..WRITERS {
+        let ring = &ring;
+        s.spawn(move || write(ring, writer, 100));
+    });
+    let records = ring.dump();
+    assert_eq!(records.len(), (WRITERS * 100) as usize);
+    let mut writers = HashMap::new();
+    check(&records, &mut writers);
+    assert_eq!(writers.len(), WRITERS as usize);
+}
+
+// A small ring with writers lapping each other while dumps run: whatever
+// a dump catches mid-write has to be left out rather than mixed up.
+#[test]
+fn dump_skips_torn_slots() {
+    let ring = ring(8);
+    let done = AtomicBool::new(false);
+    thread::scope(|s| {
+        let writers: Vec<_> = (0..WRITERS).map(|writer| {
+            let ring = &ring;
+            s.spawn(move || write(ring, writer, RECORDS))
+        }).collect();
+        for _ in 0..READERS {
+            s.spawn(|| {
+                let mut writers = HashMap::new();
+                while !done.load(Ordering::Relaxed) { check(&ring.dump(), &mut writers); }
+            });
+        }
+        for writer in writers { writer.join().unwrap(); }
+        done.store(true, Ordering::Relaxed);
+    });
+    let records = ring.dump();
+    assert_eq!(records.len(), 8);
+    assert_eq!(records.last().unwrap().seq, WRITERS * RECORDS);
+    check(&records, &mut HashMap::new());
+}
@@ -0,0 +1,193 @@
+#![cfg(feature = "std")]
+
+use std::cell::Cell;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use concurrent::clock::TestClock;
+use concurrent::hashset::ExpiringHashSet;
+use concurrent::listset::{ConcurrentSet, ExpiringSet};
+use concurrent::lock::{Lock, TASGuard, TASLock};
+
+const TTL: Duration = Duration::from_millis(10);
+const LONG: Duration = Duration::from_secs(3600);
+const STRIPES: usize = 4;
+// more than one sweep chunk
+const ENTRIES: u64 = 300;
+
+thread_local! {
+    static HELD: Cell<usize> = const { Cell::new(0) };
+}
+
+// keeps count of the locks the current thread holds
+#[derive(Default)]
+struct Counting(TASLock);
+
+struct CountingGuard<'a> { _guard: TASGuard<'a> }
+
+impl Lock for Counting {
+    type Guard<'a> = CountingGuard<'a>;
+    fn acquire(&self) -> Self::Guard<'_> {
+        let guard = self.0.acquire();
+        HELD.with(|held| held.set(held.get() + 1));
+        CountingGuard { _guard: guard }
+    }
+}
+
+impl Drop for CountingGuard<'_> {
+    fn drop(&mut self) { HELD.with(|held| held.set(held.get() - 1)); }
+}
+
+// Hashes by id alone. Stored entries may count their drops, and may insist
+// on being dropped with a lock held; probes do neither.
+struct Entry {
+    id: u64,
+    drops: Option<&'static AtomicUsize>,
+    under_lock: bool,
+}
+
+fn probe(id: u64) -> Entry { Entry { id, drops: None, under_lock: false } }
+
+fn counted(id: u64, drops: &'static AtomicUsize) -> Entry {
+    Entry { id, drops: Some(drops), under_lock: false }
+}
+
+impl Hash for Entry {
+    fn hash<H: Hasher>(&self, state: &mut H) { self.id.hash(state) }
+}
+
+impl Drop for Entry {
+    fn drop(&mut self) {
+        if self.under_lock {
+            assert!(HELD.with(Cell::get) > 0, "entry {} dropped without the lock", self.id);
+        }
+        if let Some(drops) = self.drops { drops.fetch_add(1, Ordering::SeqCst); }
+    }
+}
+
+trait Expiry: ConcurrentSet<Entry> + Sync {
+    fn add_with_ttl(&self, entry: Entry, ttl: Duration) -> bool;
+    fn sweep(&self) -> usize;
+    fn len(&self) -> usize;
+}
+
+impl Expiry for ExpiringSet<Entry, Counting, &TestClock> {
+    fn add_with_ttl(&self, entry: Entry, ttl: Duration) -> bool { self.add_with_ttl(entry, ttl) }
+    fn sweep(&self) -> usize { self.sweep() }
+    fn len(&self) -> usize { self.len() }
+}
+
+impl Expiry for ExpiringHashSet<Entry, Counting, &TestClock> {
+    fn add_with_ttl(&self, entry: Entry, ttl: Duration) -> bool { self.add_with_ttl(entry, ttl) }
+    fn sweep(&self) -> usize { self.sweep() }
+    fn len(&self) -> usize { self.len() }
+}
+
+// runs check on a fresh set of each kind, each with its own clock
+fn both(check: impl Fn(&TestClock, &dyn Expiry)) {
+    let clock = TestClock::new();
+    check(&clock, &ExpiringSet::with_clock(Counting::default(), &clock));
+    let clock = TestClock::new();
+    check(&clock, &ExpiringHashSet::with_clock(STRIPES, &clock));
+}
+
+// Gone at the very instant the TTL runs out, and an expired entry that
+// nothing has unlinked yet can be added again with a fresh TTL.
+#[test]
+fn visible_until_expiry() {
+    both(|clock, set| {
+        assert!(set.add_with_ttl(probe(1), TTL));
+        assert!(set.add(probe(2)));
+        clock.advance(TTL - Duration::from_millis(1));
+        assert!(set.contains(probe(1)));
+        assert!(!set.add_with_ttl(probe(1), TTL));
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(set.len(), 2);
+        assert!(set.add_with_ttl(probe(1), TTL));
+        clock.advance(TTL - Duration::from_millis(1));
+        assert!(set.contains(probe(1)));
+        clock.advance(Duration::from_millis(1));
+        assert!(!set.contains(probe(1)));
+        assert!(!set.remove(probe(1)));
+        assert!(set.contains(probe(2)));
+        assert_eq!(set.len(), 1);
+    });
+}
+
+// contains and remove unlink what they find expired, and the entry is
+// dropped right there, with the lock still held
+#[test]
+fn lazy_unlink_under_lock() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+    both(|clock, set| {
+        DROPS.store(0, Ordering::SeqCst);
+        for id in 0..4 {
+            assert!(set.add_with_ttl(Entry { id, drops: Some(&DROPS), under_lock: true }, TTL));
+        }
+        clock.advance(TTL);
+        assert_eq!(set.len(), 4);
+        assert!(!set.contains(probe(0)));
+        assert!(!set.contains(probe(1)));
+        assert_eq!((set.len(), DROPS.load(Ordering::SeqCst)), (2, 2));
+        assert!(!set.remove(probe(2)));
+        assert!(!set.remove(probe(3)));
+        assert_eq!((set.len(), DROPS.load(Ordering::SeqCst)), (0, 4));
+    });
+}
+
+// every third entry expires; the sweep takes all of those, across chunk
+// boundaries, and none of the rest
+#[test]
+fn sweep_removes_only_expired() {
+    both(|clock, set| {
+        for id in 0..ENTRIES {
+            let ttl = if id % 3 == 0 { TTL } else { LONG };
+            assert!(set.add_with_ttl(probe(id), ttl));
+        }
+        clock.advance(TTL);
+        assert_eq!(set.sweep(), ENTRIES as usize / 3);
+        assert_eq!(set.len(), ENTRIES as usize * 2 / 3);
+        for id in 0..ENTRIES { assert_eq!(set.contains(probe(id)), id % 3 != 0, "entry {}", id); }
+        assert_eq!(set.sweep(), 0);
+    });
+}
+
+// Each chunk hands the next one the key of the first entry it didn't get
+// to; resuming anywhere past that would leave expired entries behind.
+#[test]
+fn sweep_resumes_across_chunks() {
+    both(|clock, set| {
+        for id in 0..ENTRIES { assert!(set.add_with_ttl(probe(id), TTL)); }
+        clock.advance(TTL);
+        assert_eq!(set.sweep(), ENTRIES as usize);
+        assert_eq!(set.len(), 0);
+    });
+}
+
+// Several sweepers and a reader race to unlink the same expired entries
+// while a writer keeps adding, which resizes the hash set under the
+// sweeps. Each expired entry is dropped once, and the live ones stay.
+#[test]
+fn concurrent_sweeps_drop_once() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+    both(|clock, set| {
+        DROPS.store(0, Ordering::SeqCst);
+        for id in 0..ENTRIES {
+            let ttl = if id % 2 == 0 { TTL } else { LONG };
+            assert!(set.add_with_ttl(counted(id, &DROPS), ttl));
+        }
+        clock.advance(TTL);
+        thread::scope(|s| {
+            for _ in 0..3 { s.spawn(|| set.sweep()); }
+            s.spawn(|| for id in 0..ENTRIES { set.contains(probe(id)); });
+            s.spawn(|| for id in ENTRIES..4 * ENTRIES { assert!(set.add(counted(id, &DROPS))); });
+        });
+        assert_eq!(DROPS.load(Ordering::SeqCst), ENTRIES as usize / 2);
+        assert_eq!(set.len(), 4 * ENTRIES as usize - ENTRIES as usize / 2);
+        assert_eq!(set.sweep(), 0);
+        for id in (1..ENTRIES).step_by(2) { assert!(set.contains(probe(id))); }
+    });
+    assert_eq!(DROPS.load(Ordering::SeqCst), 4 * ENTRIES as usize);
+}
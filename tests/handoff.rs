@@ -0,0 +1,120 @@
+#![cfg(all(feature = "std", not(loom)))]
+
+// Message passing through the crate's handoffs: a value written just
+// before the release has to reach the acquiring side whole. Payloads run
+// from a single byte to a page, so a receiver that got in ahead of the
+// writes shows up as a bad checksum rather than a wrong first byte.
+
+use std::thread;
+
+use concurrent::lock::{CLHLock, Lock};
+use concurrent::oneshot;
+
+const PAIRS: usize = 2;
+const ROUNDS: usize = 200;
+
+// FNV-1a
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |sum, &byte| {
+        (sum ^ byte as u64).wrapping_mul(0x100_0000_01b3)
+    })
+}
+
+struct Message<const N: usize> {
+    round: usize,
+    bytes: [u8; N],
+    sum: u64,
+}
+
+impl<const N: usize> Message<N> {
+    // xorshift bytes seeded by round, so no two rounds look alike
+    fn new(round: usize) -> Self {
+        let mut state = (round as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+        let bytes = [0; N].map(|_: u8| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        });
+        Message { round, bytes, sum: checksum(&bytes) }
+    }
+    fn verify(&self) {
+        assert_eq!(checksum(&self.bytes), self.sum, "torn {}-byte payload in round {}", N, self.round);
+    }
+}
+
+// every round a fresh channel between a new sender and the pair's receiver
+fn oneshot_pairs<const N: usize>() {
+    thread::scope(|s| for _ in 0..PAIRS {
+        s.spawn(|| for round in 0..ROUNDS {
+            let (sender, receiver) = oneshot::channel();
+            let sending = thread::spawn(move || {
+                assert!(sender.send(Message::<N>::new(round)).is_ok(), "receiver gone");
+            });
+            let message = receiver.recv().expect("sender dropped without sending");
+            assert_eq!(message.round, round);
+            message.verify();
+            sending.join().unwrap();
+        });
+    });
+}
+
+// Two threads per lock pass one message along: each holder checks what it
+// inherited and bequeaths the next round, so the rounds count up by one
+// across the whole run and only the very first holder finds nothing.
+fn clh_pairs<const N: usize>() {
+    thread::scope(|s| for _ in 0..PAIRS {
+        s.spawn(|| {
+            let lock: CLHLock<Message<N>> = CLHLock::with_handoff();
+            let firsts: usize = thread::scope(|s| {
+                let threads: Vec<_> = (0..2).map(|_| s.spawn(|| {
+                    let mut firsts = 0;
+                    for _ in 0..ROUNDS {
+                        let mut guard = lock.acquire();
+                        let round = match guard.take_inherited() {
+                            Some(message) => {
+                                message.verify();
+                                message.round + 1
+                            },
+                            None => {
+                                firsts += 1;
+                                0
+                            },
+                        };
+                        guard.bequeath(Message::new(round));
+                    }
+                    firsts
+                })).collect();
+                threads.into_iter().map(|thread| thread.join().unwrap()).sum()
+            });
+            assert_eq!(firsts, 1);
+            let last = lock.acquire().take_inherited().expect("the last message went missing");
+            last.verify();
+            assert_eq!(last.round, 2 * ROUNDS - 1);
+        });
+    });
+}
+
+#[test]
+fn oneshot_small() {
+    oneshot_pairs::<1>();
+    oneshot_pairs::<16>();
+}
+
+#[test]
+fn oneshot_large() {
+    oneshot_pairs::<256>();
+    oneshot_pairs::<4096>();
+}
+
+#[test]
+fn clh_small() {
+    clh_pairs::<1>();
+    clh_pairs::<16>();
+}
+
+#[test]
+fn clh_large() {
+    clh_pairs::<256>();
+    clh_pairs::<4096>();
+}
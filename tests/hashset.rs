@@ -0,0 +1,43 @@
+use std::thread;
+
+use concurrent::hashset::StripedHashSet;
+use concurrent::listset::ConcurrentSet;
+use concurrent::lock::{CLHLock, TASLock};
+
+const THREADS: usize = 4;
+const KEYS: usize = 400;
+
+// enough keys to double the table from one bucket per stripe several
+// times over while every thread is adding, looking up and removing
+fn grow_concurrently<S: ConcurrentSet<usize> + Sync>(set: &S) {
+    thread::scope(|s| for thread in 0..THREADS {
+        s.spawn(move || for key in (thread..KEYS).step_by(THREADS) {
+            assert!(set.add(key));
+            assert!(set.contains(key));
+            if key % 4 == 0 { assert!(set.remove(key)); }
+        });
+    });
+    for key in 0..KEYS { assert_eq!(set.contains(key), key % 4 != 0, "key {}", key); }
+}
+
+#[test]
+fn resizes_under_contention() {
+    let set: StripedHashSet<usize, TASLock> = StripedHashSet::new(4);
+    assert_eq!(set.buckets(), 4);
+    grow_concurrently(&set);
+    assert_eq!(set.len(), KEYS * 3 / 4);
+    // at least two doublings, and never more than it takes to get the
+    // load back under four per bucket
+    assert!(set.buckets() >= 16, "only {} buckets", set.buckets());
+    assert!(set.buckets() <= 128, "{} buckets", set.buckets());
+    assert!(set.buckets().is_power_of_two());
+    assert_eq!(set.stripes(), 4);
+}
+
+#[test]
+fn resizes_with_queue_locks() {
+    let set: StripedHashSet<usize, CLHLock> = StripedHashSet::new(2);
+    grow_concurrently(&set);
+    assert_eq!(set.len(), KEYS * 3 / 4);
+    assert!(set.buckets() >= 8, "only {} buckets", set.buckets());
+}
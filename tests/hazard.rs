@@ -0,0 +1,122 @@
+#![cfg(feature = "testing")]
+
+use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+use std::thread;
+
+use concurrent::assert_balanced;
+use concurrent::hazard::HazardDomain;
+use concurrent::testing::drop_counter::{Counted, CountedFactory};
+
+struct Node {
+    live: AtomicBool,
+    _value: Counted<u32>,
+}
+
+impl Node {
+    fn boxed(factory: &CountedFactory, value: u32) -> *mut Node {
+        Box::into_raw(Box::new(Node { live: AtomicBool::new(true), _value: factory.make(value) }))
+    }
+}
+
+impl Drop for Node {
+    fn drop(&mut self) { self.live.store(false, Ordering::SeqCst); }
+}
+
+#[test]
+fn protected_pointer_outlives_scan() {
+    let factory = CountedFactory::new();
+    let domain = HazardDomain::new();
+    let shared = AtomicPtr::new(Node::boxed(&factory, 0));
+    let (reader, writer) = (domain.register(), domain.register());
+    let protected = reader.protect(&shared);
+    let old = shared.swap(Node::boxed(&factory, 1), Ordering::AcqRel);
+    unsafe { writer.retire(old); }
+    assert_eq!(writer.scan(), 0);
+    assert_eq!(writer.pending(), 1);
+    assert!(protected.as_ref().unwrap().live.load(Ordering::SeqCst));
+    assert_balanced!(factory, 2);
+    drop(protected);
+    assert_eq!(writer.scan(), 1);
+    assert_balanced!(factory, 1);
+    unsafe { drop(Box::from_raw(shared.into_inner())); }
+    assert_balanced!(factory);
+}
+
+// Readers keep protecting whatever is current while a writer replaces it
+// and retires the old node; a node a reader can see must never have been
+// dropped. Everything is freed by the end.
+#[test]
+fn concurrent_readers() {
+    let factory = CountedFactory::new();
+    let domain = HazardDomain::new();
+    let shared = AtomicPtr::new(Node::boxed(&factory, 0));
+    let done = AtomicBool::new(false);
+    thread::scope(|s| {
+        for _ in 0..3 {
+            s.spawn(|| {
+                let handle = domain.register();
+                while !done.load(Ordering::Relaxed) {
+                    let protected = handle.protect(&shared);
+                    let node = protected.as_ref().unwrap();
+                    assert!(node.live.load(Ordering::SeqCst), "node freed while protected");
+                    thread::yield_now();
+                    assert!(node.live.load(Ordering::SeqCst), "node freed while protected");
+                }
+            });
+        }
+        let writer = domain.register();
+        for value in 1..2000 {
+            let old = shared.swap(Node::boxed(&factory, value), Ordering::AcqRel);
+            unsafe { writer.retire(old); }
+            if value % 16 == 0 { thread::yield_now(); }
+        }
+        done.store(true, Ordering::Relaxed);
+    });
+    unsafe { drop(Box::from_raw(shared.into_inner())); }
+    drop(domain);
+    assert_balanced!(factory);
+}
+
+// a handle dropped while its retired node is still protected leaves the
+// node with its record, and the next handle to take the record frees it
+#[test]
+fn retired_list_handed_over() {
+    let factory = CountedFactory::new();
+    let domain = HazardDomain::new();
+    let shared = AtomicPtr::new(Node::boxed(&factory, 0));
+    let reader = domain.register();
+    let writer = domain.register();
+    let protected = reader.protect(&shared);
+    let old = shared.swap(Node::boxed(&factory, 1), Ordering::AcqRel);
+    unsafe { writer.retire(old); }
+    drop(writer);
+    assert_balanced!(factory, 2);
+    let successor = domain.register();
+    assert_eq!(successor.pending(), 1);
+    drop(protected);
+    assert_eq!(successor.scan(), 1);
+    assert_balanced!(factory, 1);
+    unsafe { drop(Box::from_raw(shared.into_inner())); }
+    assert_balanced!(factory);
+}
+
+// nobody picks the record up again, so the domain frees what it held
+#[test]
+fn domain_frees_leftovers() {
+    let factory = CountedFactory::new();
+    let domain = HazardDomain::new();
+    let shared = AtomicPtr::new(Node::boxed(&factory, 0));
+    let reader = domain.register();
+    let writer = domain.register();
+    let protected = reader.protect(&shared);
+    let old = shared.swap(Node::boxed(&factory, 1), Ordering::AcqRel);
+    unsafe { writer.retire(old); }
+    drop(writer);
+    drop(protected);
+    drop(reader);
+    assert_balanced!(factory, 2);
+    drop(domain);
+    assert_balanced!(factory, 1);
+    unsafe { drop(Box::from_raw(shared.into_inner())); }
+    assert_balanced!(factory);
+}
@@ -0,0 +1,100 @@
+#![cfg(feature = "std")]
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant};
+
+use concurrent::lock::{CLHLock, Lock, TTASLock, TryLock};
+
+const EVERY: Duration = Duration::from_millis(2);
+const BEATS: usize = 3;
+
+// polls until cond holds, for up to five seconds
+fn eventually(what: &str, cond: impl Fn() -> bool) {
+    let start = Instant::now();
+    while !cond() {
+        assert!(start.elapsed() < Duration::from_secs(5), "timed out waiting until {}", what);
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+// the threads heartbeat ran on, once per beat
+#[derive(Default)]
+struct Beats(Mutex<Vec<ThreadId>>);
+
+impl Beats {
+    fn beat(&self) { self.0.lock().unwrap().push(thread::current().id()); }
+    fn count(&self) -> usize { self.0.lock().unwrap().len() }
+}
+
+// The waiter beats on its own thread, no more often than every, for as
+// long as the lock is held against it, and stops once it gets the lock.
+fn check_heartbeat<L: TryLock + Sync>(lock: L, acquire: impl Fn(&L, &Beats) + Sync) {
+    let beats = Beats::default();
+    let guard = lock.acquire();
+    let waited = thread::scope(|s| {
+        let waiter = s.spawn(|| {
+            let start = Instant::now();
+            acquire(&lock, &beats);
+            (thread::current().id(), start.elapsed())
+        });
+        eventually("the waiter has beaten a few times", || beats.count() >= BEATS);
+        drop(guard);
+        waiter.join().unwrap()
+    });
+    let (waiter, waited) = waited;
+    let beats = beats.0.into_inner().unwrap();
+    assert!(beats.iter().all(|&thread| thread == waiter), "beat on another thread");
+    assert!(beats.len() as u128 <= waited.as_nanos() / EVERY.as_nanos(), "beat too often");
+    assert!(lock.try_acquire().is_some(), "the waiter kept the lock");
+}
+
+// nothing to wait for, so no beats
+fn check_uncontended<L: Lock>(lock: L, acquire: impl Fn(&L, &Beats)) {
+    let beats = Beats::default();
+    acquire(&lock, &beats);
+    assert_eq!(beats.count(), 0);
+}
+
+fn ttas(lock: &TTASLock, beats: &Beats) { drop(lock.acquire_with_heartbeat(EVERY, || beats.beat())); }
+
+fn clh(lock: &CLHLock, beats: &Beats) { drop(lock.acquire_with_heartbeat(EVERY, || beats.beat())); }
+
+#[test]
+fn ttas_beats_while_waiting() {
+    check_heartbeat(TTASLock::new(), ttas);
+    check_uncontended(TTASLock::new(), ttas);
+}
+
+#[test]
+fn clh_beats_while_waiting() {
+    check_heartbeat(CLHLock::new(), clh);
+    check_uncontended(CLHLock::new(), clh);
+}
+
+// A heartbeat that panics stops beating, but the waiter stays queued
+// until its turn; only then does the panic carry on, with the lock
+// already released behind it.
+#[test]
+fn clh_heartbeat_panic_waits_its_turn() {
+    let lock: CLHLock = CLHLock::new();
+    let beats = Beats::default();
+    let guard = lock.acquire();
+    thread::scope(|s| {
+        let waiter = s.spawn(|| panic::catch_unwind(AssertUnwindSafe(|| {
+            lock.acquire_with_heartbeat(EVERY, || {
+                beats.beat();
+                panic!("heartbeat failed");
+            })
+        })).is_err());
+        eventually("the heartbeat has panicked", || beats.count() == 1);
+        thread::sleep(10 * EVERY);
+        assert!(!waiter.is_finished(), "gave up its place in the queue");
+        assert_eq!(lock.queue_depth_hint(), 1);
+        drop(guard);
+        assert!(waiter.join().unwrap(), "the panic was swallowed");
+    });
+    assert_eq!(beats.count(), 1);
+    assert!(lock.try_acquire().is_some(), "the panic kept the lock");
+}
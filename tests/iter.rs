@@ -0,0 +1,90 @@
+use concurrent::listset::{CoarseListSet, ConcurrentSet, MutSet, SeqListSet};
+use concurrent::lock::TASLock;
+
+const N: u64 = 100;
+
+fn seq() -> SeqListSet<u64> {
+    let mut set = SeqListSet::new();
+    for i in 0..N { set.add(i); }
+    set
+}
+
+// what iterating a set of 0..N has to give: every element once, by key
+fn key_order(make_key: impl Fn(&u64) -> u64) -> Vec<u64> {
+    let mut items: Vec<_> = (0..N).collect();
+    items.sort_by_key(make_key);
+    items
+}
+
+#[test]
+fn seq_iter_is_exact_and_fused() {
+    let set = seq();
+    let mut iter = set.iter();
+    for left in (0..N as usize).rev() {
+        assert!(iter.next().is_some());
+        assert_eq!(iter.len(), left);
+        assert_eq!(iter.size_hint(), (left, Some(left)));
+    }
+    for _ in 0..3 { assert_eq!(iter.next(), None); }
+    assert_eq!(iter.len(), 0);
+}
+
+#[test]
+fn seq_iter_in_key_order() {
+    let set = seq();
+    let items: Vec<_> = (&set).into_iter().copied().collect();
+    assert_eq!(items, key_order(|i| set.make_key(i)));
+    assert_eq!(set.iter().len(), set.len());
+}
+
+#[test]
+fn seq_into_iter_is_exact_and_fused() {
+    let set = seq();
+    let expected = key_order(|i| set.make_key(i));
+    let mut iter = set.into_iter();
+    assert_eq!(iter.len(), N as usize);
+    let items: Vec<_> = iter.by_ref().take(N as usize / 2).collect();
+    assert_eq!(iter.len(), N as usize / 2);
+    let items: Vec<_> = items.into_iter().chain(iter.by_ref()).collect();
+    assert_eq!(items, expected);
+    for _ in 0..3 { assert_eq!(iter.next(), None); }
+}
+
+#[test]
+fn snapshot_runs_both_ways() {
+    let set = CoarseListSet::new(TASLock::new());
+    for i in 0..N { ConcurrentSet::add(&set, i); }
+    let expected = key_order(|i| set.make_key(i));
+    let snapshot = set.snapshot();
+    assert_eq!(snapshot.len(), N as usize);
+    assert_eq!(snapshot.clone().collect::<Vec<_>>(), expected);
+    assert!(snapshot.clone().rev().eq(expected.iter().rev().copied()));
+    // the two ends meet in the middle without skipping or repeating
+    let mut snapshot = snapshot;
+    let (mut front, mut back) = (Vec::new(), Vec::new());
+    while let Some(item) = snapshot.next() {
+        front.push(item);
+        back.extend(snapshot.next_back());
+        assert_eq!(snapshot.len(), N as usize - front.len() - back.len());
+    }
+    back.reverse();
+    front.append(&mut back);
+    assert_eq!(front, expected);
+    assert_eq!(snapshot.next_back(), None);
+}
+
+#[test]
+fn snapshot_ignores_later_changes() {
+    let set = CoarseListSet::new(TASLock::new());
+    for i in 0..N { ConcurrentSet::add(&set, i); }
+    let expected = key_order(|i| set.make_key(i));
+    let snapshot = set.snapshot();
+    for i in (0..N).step_by(2) { assert!(ConcurrentSet::remove(&set, i)); }
+    for i in N..2 * N { assert!(ConcurrentSet::add(&set, i)); }
+    assert_eq!(snapshot.len(), N as usize);
+    assert_eq!(snapshot.clone().collect::<Vec<_>>(), expected);
+    set.clear();
+    assert!(set.is_empty());
+    assert_eq!(snapshot.collect::<Vec<_>>(), expected);
+    assert_eq!(set.snapshot().len(), 0);
+}
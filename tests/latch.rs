@@ -0,0 +1,55 @@
+#![cfg(feature = "std")]
+
+use std::cell::UnsafeCell;
+use std::thread;
+
+use concurrent::latch::CountDownLatch;
+
+const WORKERS: usize = 4;
+
+// plain memory, with nothing but the latch to order the writes before
+// the reads
+struct Slots([UnsafeCell<Vec<usize>>; WORKERS]);
+
+unsafe impl Sync for Slots {}
+
+#[test]
+fn wait_sees_writes_before_count_down() {
+    for _ in 0..50 {
+        let slots = Slots(Default::default());
+        let latch = CountDownLatch::new(WORKERS);
+        thread::scope(|s| {
+            for worker in 0..WORKERS {
+                let (slots, latch) = (&slots, &latch);
+                s.spawn(move || {
+                    // each worker owns its slot until it counts down
+                    unsafe { *slots.0[worker].get() = vec![worker; 100]; }
+                    latch.count_down();
+                });
+            }
+            latch.wait();
+            for (worker, slot) in slots.0.iter().enumerate() {
+                assert_eq!(unsafe { &*slot.get() }, &vec![worker; 100]);
+            }
+        });
+    }
+}
+
+#[test]
+fn extra_count_downs_stay_at_zero() {
+    let latch = CountDownLatch::new(2);
+    thread::scope(|s| for _ in 0..4 { s.spawn(|| latch.count_down()); });
+    assert_eq!(latch.count(), 0);
+    latch.count_down();
+    assert_eq!(latch.count(), 0);
+    assert!(latch.try_wait());
+    latch.wait();
+}
+
+#[test]
+fn zero_count_is_open() {
+    let latch = CountDownLatch::new(0);
+    assert!(latch.try_wait());
+    latch.count_down();
+    assert_eq!(latch.count(), 0);
+}
@@ -0,0 +1,241 @@
+#![cfg(feature = "testing")]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use concurrent::assert_balanced;
+use concurrent::hashset::StripedHashSet;
+use concurrent::listset::{
+    CoarseListSet, ConcurrentSet, FineListSet, LazyListSet, LockFreeListSet, MutSet, SeqListSet,
+    Set, UnlinkPolicy,
+};
+use concurrent::lock::{CLHLock, Lock, TASLock};
+use concurrent::oneshot;
+use concurrent::queue::{MsQueue, TwoLockQueue};
+use concurrent::stack::TreiberStack;
+use concurrent::testing::drop_counter::{Counted, CountedFactory, LeakRegistry};
+
+// adds 0..100 from two threads, removes the odd ones, and leaves the
+// rest for the set's Drop
+fn fill_concurrently<S: ConcurrentSet<Counted<u32>> + Sync>(factory: &CountedFactory, set: &S) {
+    thread::scope(|s| for half in 0..2 {
+        s.spawn(move || for i in (half..100).step_by(2) {
+            set.add(factory.make(i));
+            if i % 2 == 1 { assert!(set.remove(factory.make(i))); }
+        });
+    });
+}
+
+#[test]
+fn seq_list_set() {
+    let factory = CountedFactory::new();
+    let mut set = SeqListSet::new();
+    for i in 0..10 { set.add(factory.make(i)); }
+    assert!(!set.add(factory.make(3)));
+    assert!(set.remove(factory.make(3)));
+    assert_balanced!(factory, 9);
+    set.clear();
+    assert_balanced!(factory);
+    for i in 0..10 { set.add(factory.make(i)); }
+    drop(set);
+    assert_balanced!(factory);
+}
+
+#[test]
+fn coarse_list_set() {
+    let factory = CountedFactory::new();
+    let set = CoarseListSet::new(TASLock::new());
+    fill_concurrently(&factory, &set);
+    assert_balanced!(factory, 50);
+    drop(set);
+    assert_balanced!(factory);
+}
+
+// the combiner drops elements that belong to other threads' calls
+#[test]
+fn coarse_combining_list_set() {
+    let factory = CountedFactory::new();
+    let set = CoarseListSet::new(CLHLock::new()).combining();
+    fill_concurrently(&factory, &set);
+    assert_balanced!(factory, 50);
+    drop(set);
+    assert_balanced!(factory);
+}
+
+#[test]
+fn fine_list_set() {
+    let factory = CountedFactory::new();
+    let set: FineListSet<_, TASLock> = FineListSet::new();
+    fill_concurrently(&factory, &set);
+    drop(set);
+    assert_balanced!(factory);
+}
+
+#[test]
+fn lazy_list_set() {
+    let factory = CountedFactory::new();
+    let mut set: LazyListSet<_, TASLock> = LazyListSet::new();
+    fill_concurrently(&factory, &set);
+    set.reclaim();
+    assert_balanced!(factory, 50);
+    drop(set);
+    assert_balanced!(factory);
+}
+
+#[test]
+fn lock_free_list_set() {
+    let factory = CountedFactory::new();
+    let mut set = LockFreeListSet::new();
+    fill_concurrently(&factory, &set);
+    set.reclaim();
+    assert_balanced!(factory, 50);
+    drop(set);
+    assert_balanced!(factory);
+}
+
+// Four threads add, remove and look up the same 32 keys; between rounds
+// the set is quiet, so reclaim must free exactly the nodes removed so far
+// and the values still alive must be exactly the ones in the set.
+#[test]
+fn lock_free_list_set_stress() {
+    let factory = CountedFactory::new();
+    let mut set = LockFreeListSet::new();
+    let mut len = 0;
+    for round in 0..3 {
+        let (added, removed) = (AtomicUsize::new(0), AtomicUsize::new(0));
+        thread::scope(|s| for thread in 0..4 {
+            let (factory, set, added, removed) = (&factory, &set, &added, &removed);
+            s.spawn(move || for i in 0..2000 {
+                let key = (thread * 7 + i * 13 + round) % 32;
+                let succeeded = match i % 3 {
+                    0 => set.add(factory.make(key)).then_some(added),
+                    1 => set.remove(factory.make(key)).then_some(removed),
+                    _ => { set.contains(factory.make(key)); None },
+                };
+                if let Some(counter) = succeeded { counter.fetch_add(1, Ordering::Relaxed); }
+            });
+        });
+        let (added, removed) = (added.into_inner(), removed.into_inner());
+        len = len + added - removed;
+        assert_eq!(set.len(), len);
+        assert_eq!(set.reclaim(), removed);
+        assert_balanced!(factory, len);
+    }
+    drop(set);
+    assert_balanced!(factory);
+}
+
+// whatever marked nodes no writer unlinked go with the sets
+#[test]
+fn deferred_unlinking() {
+    let factory = CountedFactory::new();
+    let lazy: LazyListSet<_, TASLock> = LazyListSet::new()
+        .with_unlink_policy(UnlinkPolicy::Deferred);
+    let lock_free = LockFreeListSet::new().with_unlink_policy(UnlinkPolicy::Deferred);
+    for i in (0..20).rev() {
+        lazy.add(factory.make(i));
+        lock_free.add(factory.make(i));
+        assert!(lazy.remove(factory.make(i)));
+        assert!(lock_free.remove(factory.make(i)));
+    }
+    drop((lazy, lock_free));
+    assert_balanced!(factory);
+}
+
+#[test]
+fn striped_hash_set() {
+    let factory = CountedFactory::new();
+    let set: StripedHashSet<_, TASLock> = StripedHashSet::new(4);
+    fill_concurrently(&factory, &set);
+    assert_balanced!(factory, 50);
+    drop(set);
+    assert_balanced!(factory);
+}
+
+#[test]
+fn treiber_stack() {
+    let factory = CountedFactory::new();
+    let stack = TreiberStack::new();
+    for i in 0..10 { stack.push(factory.make(i)); }
+    for _ in 0..4 { stack.pop(); }
+    assert_balanced!(factory, 6);
+    drop(stack);
+    assert_balanced!(factory);
+}
+
+#[test]
+fn queues() {
+    let factory = CountedFactory::new();
+    let ms = MsQueue::new();
+    let two_lock = TwoLockQueue::new(16, TASLock::new(), TASLock::new());
+    for i in 0..10 {
+        ms.enqueue(factory.make(i));
+        two_lock.push(factory.make(i));
+    }
+    for _ in 0..4 {
+        ms.dequeue();
+        two_lock.pop();
+    }
+    assert_balanced!(factory, 12);
+    drop(ms);
+    drop(two_lock);
+    assert_balanced!(factory);
+}
+
+// what the consumers left behind goes with the queue
+#[test]
+fn ms_queue_concurrent_leftovers() {
+    let factory = CountedFactory::new();
+    let queue = MsQueue::new();
+    thread::scope(|s| for _ in 0..2 {
+        s.spawn(|| for i in 0..500 {
+            queue.enqueue(factory.make(i));
+            if i % 2 == 0 { queue.dequeue(); }
+        });
+    });
+    assert_balanced!(factory, 500);
+    drop(queue);
+    assert_balanced!(factory);
+}
+
+#[test]
+fn oneshot_unreceived() {
+    let factory = CountedFactory::new();
+    let (sender, receiver) = oneshot::channel();
+    sender.send(factory.make(1)).unwrap();
+    assert_balanced!(factory, 1);
+    drop(receiver);
+    assert_balanced!(factory);
+}
+
+#[test]
+fn clh_handoff_values() {
+    let factory = CountedFactory::new();
+    let lock = CLHLock::with_handoff();
+    {
+        let mut guard = lock.acquire();
+        guard.bequeath(factory.make(1));
+    }
+    {
+        let mut guard = lock.acquire();
+        assert!(guard.inherited().is_some());
+        guard.bequeath(factory.make(2));
+    }
+    assert_balanced!(factory, 1);
+    // a value nobody acquired to take is freed with the lock
+    drop(lock);
+    assert_balanced!(factory);
+}
+
+#[test]
+fn registry_tracks_zero_sized_boxes() {
+    let registry = LeakRegistry::new();
+    let first = registry.boxed(());
+    let second = registry.boxed(());
+    assert_ne!(first.id(), second.id());
+    assert_eq!(registry.leaked(), [first.id(), second.id()]);
+    let second_id = second.id();
+    drop(first);
+    std::mem::forget(second);
+    assert_eq!(registry.leaked(), [second_id]);
+}
@@ -0,0 +1,132 @@
+#![cfg(feature = "testing")]
+
+use concurrent::listset::{
+    CoarseListSet, FineListSet, LazyListSet, LockFreeListSet, RefCountListSet, RwListSet,
+    SkipListSet, StdSet, UnlinkPolicy,
+};
+use concurrent::lock::{CLHLock, RwSpinLock, TASLock, TTASLock};
+use concurrent::testing::linearizability::{check_set, is_linearizable, linearize, Event, SetOp};
+
+fn event(thread: usize, op: SetOp, result: bool, invoked: u64, returned: u64) -> Event {
+    Event { thread, op, result, invoked, returned }
+}
+
+#[test]
+fn sequential_history() {
+    let history = [
+        event(0, SetOp::Add(1), true, 0, 1),
+        event(0, SetOp::Add(1), false, 2, 3),
+        event(0, SetOp::Contains(1), true, 4, 5),
+        event(0, SetOp::Remove(1), true, 6, 7),
+        event(0, SetOp::Contains(1), false, 8, 9),
+    ];
+    assert_eq!(linearize(&history), Some(vec![0, 1, 2, 3, 4]));
+}
+
+#[test]
+fn overlapping_history_reorders() {
+    // the contains starts first but overlaps the add, so it may take
+    // effect after it
+    let history = [
+        event(0, SetOp::Contains(1), true, 0, 3),
+        event(1, SetOp::Add(1), true, 1, 2),
+    ];
+    assert_eq!(linearize(&history), Some(vec![1, 0]));
+}
+
+#[test]
+fn concurrent_adds_one_wins() {
+    let history = [
+        event(0, SetOp::Add(1), true, 0, 3),
+        event(1, SetOp::Add(1), false, 1, 4),
+        event(2, SetOp::Remove(1), true, 5, 6),
+    ];
+    assert!(is_linearizable(&history));
+}
+
+#[test]
+fn remove_of_absent_key_succeeds() {
+    let history = [event(0, SetOp::Remove(1), true, 0, 1)];
+    assert!(!is_linearizable(&history));
+}
+
+#[test]
+fn completed_add_not_seen() {
+    // the add returned before the contains was invoked, so no order puts
+    // the contains first
+    let history = [
+        event(0, SetOp::Add(1), true, 0, 1),
+        event(1, SetOp::Contains(1), false, 2, 3),
+    ];
+    assert!(!is_linearizable(&history));
+}
+
+#[test]
+fn both_concurrent_adds_win() {
+    let history = [
+        event(0, SetOp::Add(1), true, 0, 3),
+        event(1, SetOp::Add(1), true, 1, 2),
+    ];
+    assert!(!is_linearizable(&history));
+}
+
+#[test]
+#[should_panic(expected = "history too long")]
+fn long_history_rejected() {
+    let history: Vec<_> = (0..65).map(|i| event(0, SetOp::Contains(i), false, 2 * i, 2 * i + 1))
+        .collect();
+    linearize(&history);
+}
+
+const THREADS: usize = 3;
+const OPS: usize = 4;
+const ROUNDS: usize = 50;
+
+#[test]
+fn coarse_list_set() {
+    check_set(|| CoarseListSet::new(TASLock::new()), THREADS, OPS, ROUNDS);
+    check_set(|| CoarseListSet::new(CLHLock::new()).combining(), THREADS, OPS, ROUNDS);
+}
+
+#[test]
+fn fine_list_set() {
+    check_set(FineListSet::<u64, CLHLock>::new, THREADS, OPS, ROUNDS);
+}
+
+#[test]
+fn lazy_list_set() {
+    check_set(LazyListSet::<u64, TTASLock>::new, THREADS, OPS, ROUNDS);
+    check_set(
+        || LazyListSet::<u64, TTASLock>::new().with_unlink_policy(UnlinkPolicy::Deferred),
+        THREADS, OPS, ROUNDS,
+    );
+}
+
+#[test]
+fn lock_free_list_set() {
+    check_set(LockFreeListSet::<u64>::new, THREADS, OPS, ROUNDS);
+    check_set(
+        || LockFreeListSet::<u64>::new().with_unlink_policy(UnlinkPolicy::Deferred),
+        THREADS, OPS, ROUNDS,
+    );
+}
+
+#[test]
+fn refcount_list_set() {
+    check_set(|| RefCountListSet::new(TTASLock::new()), THREADS, OPS, ROUNDS);
+}
+
+#[test]
+fn rw_list_set() {
+    check_set(|| RwListSet::new(RwSpinLock::new()), THREADS, OPS, ROUNDS);
+}
+
+#[test]
+fn std_set() {
+    check_set(|| StdSet::new(TASLock::new()), THREADS, OPS, ROUNDS);
+}
+
+#[test]
+fn skip_list_set() {
+    check_set(|| SkipListSet::new(TASLock::new(), 4), THREADS, OPS, ROUNDS);
+}
@@ -0,0 +1,291 @@
+#![cfg(feature = "std")]
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Barrier;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use concurrent::listset::{
+    CoarseListSet, ConcurrentSet, FineListSet, LazyListSet, LockFreeListSet, MutSet,
+    RefCountListSet, RwListSet, Set, SkipListSet, StdSet, UnlinkPolicy,
+};
+use concurrent::lock::{CLHLock, RwSpinLock, TASLock, TTASLock};
+use rand::random;
+
+const THREADS: usize = 4;
+const KEYS: usize = 200;
+
+// Each writer owns its own keys, so the final contents are known: every
+// key gets added and the odd ones removed again. Readers look at all the
+// keys the whole time.
+fn concurrent_workload<S: ConcurrentSet<usize> + Sync>(set: &S) {
+    thread::scope(|s| {
+        for thread in 0..THREADS {
+            s.spawn(move || for key in (thread..KEYS).step_by(THREADS) {
+                assert!(set.add(key));
+                assert!(set.contains(key));
+                assert!(!set.add(key));
+                if key % 2 == 1 {
+                    assert!(set.remove(key));
+                    assert!(!set.contains(key));
+                }
+            });
+            s.spawn(move || for key in 0..KEYS { set.contains(key); });
+        }
+    });
+    for key in 0..KEYS {
+        assert_eq!(set.contains(key), key % 2 == 0, "key {}", key);
+    }
+}
+
+#[test]
+fn refcount_concurrent() {
+    let set = RefCountListSet::new(TASLock::new());
+    concurrent_workload(&set);
+    assert_eq!(set.len(), KEYS / 2);
+}
+
+#[test]
+fn std_concurrent() {
+    let set = StdSet::new(TASLock::new());
+    concurrent_workload(&set);
+    assert_eq!(set.len(), KEYS / 2);
+}
+
+// runs the same random operations against set and a StdSet, which is
+// taken to be right, and checks every result against it
+fn differential<S: ConcurrentSet<u64>>(set: S) {
+    const KEYS: u64 = 64;
+    let oracle = StdSet::new(TASLock::new());
+    for step in 0..5_000 {
+        let key = random::<u64>() % KEYS;
+        let (op, expected, actual) = match random::<u8>() % 3 {
+            0 => ("add", oracle.add(key), set.add(key)),
+            1 => ("remove", oracle.remove(key), set.remove(key)),
+            _ => ("contains", oracle.contains(key), set.contains(key)),
+        };
+        assert_eq!(actual, expected, "step {}: {}({})", step, op, key);
+    }
+    for key in 0..KEYS {
+        assert_eq!(set.contains(key), oracle.contains(key), "key {}", key);
+    }
+}
+
+#[test]
+fn differential_against_std() {
+    differential(CoarseListSet::new(TASLock::new()));
+    differential(CoarseListSet::new(CLHLock::new()).combining());
+    differential(FineListSet::<u64, TTASLock>::new());
+    differential(LazyListSet::<u64, TTASLock>::new());
+    differential(LockFreeListSet::new());
+    differential(RefCountListSet::new(TASLock::new()));
+    differential(RwListSet::new(RwSpinLock::new()));
+    differential(SkipListSet::new(TASLock::new(), 8));
+}
+
+type RefCountSet = RefCountListSet<usize, TASLock>;
+
+// a refcount set holding 0..len, and its elements in list order
+fn refcount_set(len: usize) -> (RefCountSet, Vec<usize>) {
+    let set = RefCountListSet::new(TASLock::new());
+    for element in 0..len { set.add(element); }
+    let mut order: Vec<_> = (0..len).collect();
+    order.sort_by_key(|element| set.make_key(element));
+    (set, order)
+}
+
+// looks element up, and stays counted on its node between the two waits
+fn park_on(set: &RefCountSet, element: usize, barrier: &Barrier) -> bool {
+    set.contains_by_key(set.make_key(&element), |&item| {
+        barrier.wait();
+        barrier.wait();
+        item == element
+    })
+}
+
+// runs write on another thread while a reader is parked on element, and
+// checks whether it finished before the reader let go
+fn write_while_parked(
+    set: &RefCountSet, element: usize, write: impl FnOnce() -> bool + Send,
+) -> bool {
+    let barrier = Barrier::new(2);
+    let done = AtomicBool::new(false);
+    thread::scope(|s| {
+        let reader = s.spawn(|| park_on(set, element, &barrier));
+        barrier.wait();
+        let writer = s.spawn(|| {
+            assert!(write());
+            done.store(true, Ordering::SeqCst);
+        });
+        thread::sleep(Duration::from_millis(20));
+        let early = done.load(Ordering::SeqCst);
+        barrier.wait();
+        assert!(reader.join().unwrap());
+        writer.join().unwrap();
+        early
+    })
+}
+
+#[test]
+fn refcount_remove_waits_for_reader() {
+    let (set, order) = refcount_set(3);
+    let element = order[1];
+    assert!(!write_while_parked(&set, element, || set.remove(element)), "freed a counted node");
+    assert!(!set.contains(element));
+}
+
+// a reader counted on the predecessor may already hold the old next
+// pointer, so the add has to wait for it to move on
+#[test]
+fn refcount_add_waits_for_reader_on_predecessor() {
+    let (set, order) = refcount_set(4);
+    let (pred, element) = (order[1], order[2]);
+    assert!(set.remove(element));
+    assert!(!write_while_parked(&set, pred, || set.add(element)), "linked past a counted node");
+    assert!(set.contains(element));
+}
+
+// readers counted on different nodes run at the same time, and writers
+// away from those nodes go ahead while they do
+#[test]
+fn refcount_readers_overlap() {
+    let (set, order) = refcount_set(10);
+    let barrier = Barrier::new(3);
+    thread::scope(|s| {
+        let readers: Vec<_> = [order[2], order[6]].into_iter()
+            .map(|element| s.spawn({
+                let (set, barrier) = (&set, &barrier);
+                move || park_on(set, element, barrier)
+            }))
+            .collect();
+        // both readers are inside their lookups now
+        barrier.wait();
+        assert!(set.remove(order[4]));
+        assert!(set.add(order[4]));
+        assert!(set.contains(order[8]));
+        barrier.wait();
+        for reader in readers { assert!(reader.join().unwrap()); }
+    });
+}
+
+#[test]
+fn rw_concurrent() {
+    let mut set = RwListSet::new(RwSpinLock::new());
+    concurrent_workload(&set);
+    assert_eq!(set.len(), KEYS / 2);
+    assert!(MutSet::remove(&mut set, 0));
+    assert!(MutSet::add(&mut set, 0));
+}
+
+fn deferred_lazy() -> LazyListSet<usize, TTASLock> {
+    LazyListSet::new().with_unlink_policy(UnlinkPolicy::Deferred)
+}
+
+fn deferred_lock_free() -> LockFreeListSet<usize> {
+    LockFreeListSet::new().with_unlink_policy(UnlinkPolicy::Deferred)
+}
+
+// The sets keep their nodes in order of the key's hash. Removing from the
+// back of the list means no later remove has to get past a node already
+// marked, so all of them are left for purge.
+fn mark_odd_keys<S: ConcurrentSet<usize>>(set: &S) {
+    let hash = |key: &usize| {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        hasher.finish()
+    };
+    for key in 0..10 { set.add(key); }
+    let mut odd: Vec<usize> = (1..10).step_by(2).collect();
+    odd.sort_by_key(|key| std::cmp::Reverse(hash(key)));
+    for key in odd { assert!(set.remove(key)); }
+}
+
+#[test]
+fn lazy_deferred_purge() {
+    let set = deferred_lazy();
+    mark_odd_keys(&set);
+    assert_eq!(set.len(), 5);
+    assert_eq!(set.purge(2), 2);
+    assert_eq!(set.purge(usize::MAX), 3);
+    assert_eq!(set.purge(usize::MAX), 0);
+    for key in 0..10 { assert_eq!(set.contains(key), key % 2 == 0); }
+}
+
+#[test]
+fn lock_free_deferred_purge() {
+    let set = deferred_lock_free();
+    mark_odd_keys(&set);
+    assert_eq!(set.len(), 5);
+    assert_eq!(set.purge(2), 2);
+    assert_eq!(set.purge(usize::MAX), 3);
+    assert_eq!(set.purge(usize::MAX), 0);
+    for key in 0..10 { assert_eq!(set.contains(key), key % 2 == 0); }
+}
+
+#[test]
+fn eager_leaves_nothing_to_purge() {
+    let lazy: LazyListSet<usize, TTASLock> = LazyListSet::new();
+    mark_odd_keys(&lazy);
+    assert_eq!(lazy.purge(usize::MAX), 0);
+    let lock_free = LockFreeListSet::new();
+    mark_odd_keys(&lock_free);
+    assert_eq!(lock_free.purge(usize::MAX), 0);
+}
+
+#[test]
+fn readd_after_deferred_remove() {
+    let lazy = deferred_lazy();
+    let lock_free = deferred_lock_free();
+    for _ in 0..3 {
+        assert!(lazy.add(7) && lazy.remove(7));
+        assert!(lock_free.add(7) && lock_free.remove(7));
+    }
+    assert!(lazy.add(7) && lock_free.add(7));
+    assert_eq!((lazy.len(), lock_free.len()), (1, 1));
+}
+
+#[test]
+fn lazy_deferred_concurrent() {
+    let set = deferred_lazy();
+    concurrent_workload(&set);
+    assert_eq!(set.len(), KEYS / 2);
+}
+
+#[test]
+fn lock_free_deferred_concurrent() {
+    let set = deferred_lock_free();
+    concurrent_workload(&set);
+    assert_eq!(set.len(), KEYS / 2);
+}
+
+// purge only ever unlinks marked nodes, so running it the whole time
+// must leave the workload's final contents intact
+fn purge_while_writing<S: ConcurrentSet<usize> + Sync>(set: &S, purge: impl Fn() + Sync) {
+    let done = AtomicBool::new(false);
+    thread::scope(|s| {
+        s.spawn(|| while !done.load(Ordering::Relaxed) { purge(); });
+        concurrent_workload(set);
+        done.store(true, Ordering::Relaxed);
+    });
+}
+
+#[test]
+fn lazy_purge_races_writers() {
+    let set = deferred_lazy();
+    purge_while_writing(&set, || { set.purge(1); });
+    assert_eq!(set.len(), KEYS / 2);
+}
+
+#[test]
+fn lock_free_purge_races_writers() {
+    let set = deferred_lock_free();
+    purge_while_writing(&set, || { set.purge(1); });
+    assert_eq!(set.len(), KEYS / 2);
+}
+
+#[test]
+fn sync_only_with_send_locks() {
+    trybuild::TestCases::new().compile_fail("tests/ui/listset_*.rs");
+}
@@ -0,0 +1,71 @@
+#![cfg(all(feature = "lock_api", not(loom)))]
+
+use std::thread;
+
+use concurrent::lock::TASLock;
+use lock_api::{Mutex, MutexGuard};
+
+type TASMutex<T> = Mutex<TASLock, T>;
+
+#[test]
+fn try_lock_fails_while_held() {
+    let mutex = TASMutex::new(vec![1u32, 2, 3]);
+    let guard = mutex.lock();
+    assert!(mutex.is_locked());
+    thread::scope(|s| {
+        s.spawn(|| assert!(mutex.try_lock().is_none())).join().unwrap();
+    });
+    drop(guard);
+    assert!(!mutex.is_locked());
+    let mut guard = mutex.try_lock().expect("mutex still locked after unlock");
+    guard.push(4);
+    drop(guard);
+    assert_eq!(mutex.into_inner(), [1, 2, 3, 4]);
+}
+
+#[test]
+fn mapped_guard_keeps_the_lock() {
+    let mutex = TASMutex::new(vec![1u32, 2, 3]);
+    let mut last = MutexGuard::map(mutex.lock(), |v| v.last_mut().unwrap());
+    *last = 30;
+    assert!(mutex.try_lock().is_none());
+    drop(last);
+    let guard = match MutexGuard::try_map(mutex.lock(), |v| v.get_mut(5)) {
+        Ok(_) => panic!("mapped to an element that isn't there"),
+        Err(guard) => guard,
+    };
+    assert!(mutex.is_locked());
+    drop(guard);
+    assert_eq!(*mutex.lock(), [1, 2, 30]);
+}
+
+#[test]
+fn pushes_from_many_threads() {
+    let mutex = TASMutex::new(Vec::new());
+    thread::scope(|s| for t in 0..4 {
+        let mutex = &mutex;
+        s.spawn(move || for i in 0..100 { mutex.lock().push(t * 100 + i); });
+    });
+    let mut values = mutex.into_inner();
+    values.sort_unstable();
+    assert_eq!(values, (0..400).collect::<Vec<u32>>());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn try_lock_for_times_out() {
+    use std::time::{Duration, Instant};
+    use concurrent::lock::TTASLock;
+    let mutex = Mutex::<TTASLock, u32>::new(0);
+    let guard = mutex.lock();
+    thread::scope(|s| {
+        s.spawn(|| {
+            let start = Instant::now();
+            assert!(mutex.try_lock_for(Duration::from_millis(5)).is_none());
+            assert!(start.elapsed() >= Duration::from_millis(5));
+        });
+    });
+    drop(guard);
+    *mutex.try_lock_for(Duration::from_millis(5)).expect("mutex still locked") += 1;
+    assert_eq!(mutex.into_inner(), 1);
+}
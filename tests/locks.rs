@@ -0,0 +1,165 @@
+#![cfg(feature = "testing")]
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use concurrent::error::{BorrowError, TimedOut};
+use concurrent::lock::{
+    ArrayLock, BackoffLock, CLHLock, Lock, OwnedGuard, StaticArrayLock, TASLock, TTASLock, TryLock,
+};
+use concurrent::lock_test_suite;
+use concurrent::testing::lock_suite::{check_exclusion, check_try_acquire};
+
+lock_test_suite!(tas, TASLock::new(), try_acquire);
+lock_test_suite!(ttas, TTASLock::new(), try_acquire);
+lock_test_suite!(backoff, BackoffLock::new(), try_acquire);
+lock_test_suite!(backoff_adaptive, BackoffLock::new().adaptive(), try_acquire);
+lock_test_suite!(array, ArrayLock::new(3), capacity, try_acquire);
+lock_test_suite!(array_power_of_two, ArrayLock::new(4), capacity, try_acquire);
+#[cfg(not(loom))]
+lock_test_suite!(static_array, StaticArrayLock::<4>::new_static(), capacity, try_acquire);
+// a few hundred acquires from the end, so the slot counter wraps partway
+// through every test
+lock_test_suite!(
+    array_wrap, ArrayLock::new(3).starting_at(usize::MAX - 100), capacity, try_acquire
+);
+lock_test_suite!(clh, CLHLock::new(), try_acquire);
+lock_test_suite!(clh_handoff, CLHLock::<u32>::with_handoff(), try_acquire);
+#[cfg(feature = "stats")]
+lock_test_suite!(
+    instrumented, concurrent::lock::Instrumented::new(CLHLock::new()), try_acquire
+);
+
+#[test]
+fn array_capacity_rounds_up() {
+    assert_eq!(ArrayLock::new(1).capacity(), 1);
+    assert_eq!(ArrayLock::new(3).capacity(), 4);
+    assert_eq!(ArrayLock::new(4).capacity(), 4);
+    assert_eq!(ArrayLock::new(5).capacity(), 8);
+}
+
+// two more threads than slots, so some of them keep getting turned away
+// and have to back off until a slot frees up
+#[test]
+fn array_acquire_blocking_over_capacity() {
+    let lock = ArrayLock::new(4);
+    let inside = AtomicBool::new(false);
+    thread::scope(|s| for _ in 0..lock.capacity() + 2 {
+        s.spawn(|| for _ in 0..50 {
+            let _guard = lock.acquire_blocking();
+            assert!(!inside.swap(true, Ordering::Relaxed), "two holders at once");
+            thread::yield_now();
+            inside.store(false, Ordering::Relaxed);
+        });
+    });
+}
+
+#[test]
+fn array_timeout_when_saturated() {
+    let lock = ArrayLock::new(2);
+    let guard = lock.acquire();
+    thread::scope(|s| {
+        // takes the last slot and waits in it
+        let waiter = s.spawn(|| drop(lock.acquire()));
+        thread::sleep(Duration::from_millis(10));
+        let start = Instant::now();
+        let result = lock.acquire_checked_timeout(Duration::from_millis(5));
+        assert_eq!(result.err(), Some(BorrowError::Timeout));
+        assert!(start.elapsed() >= Duration::from_millis(5));
+        drop(guard);
+        waiter.join().unwrap();
+    });
+    assert!(lock.acquire_checked_timeout(Duration::from_millis(5)).is_ok());
+}
+
+// Four threads give up after a millisecond while the holder sleeps for
+// fifty; once it lets go, the same timeout is plenty for each of them.
+fn check_acquire_timeout<L: TryLock>(lock: L) {
+    let timeout = Duration::from_millis(1);
+    let guard = lock.acquire();
+    thread::scope(|s| {
+        let waiters: Vec<_> = (0..4).map(|_| s.spawn(|| {
+            let start = Instant::now();
+            assert_eq!(lock.acquire_timeout(timeout).err(), Some(TimedOut), "acquired a held lock");
+            start.elapsed()
+        })).collect();
+        thread::sleep(Duration::from_millis(50));
+        for waiter in waiters {
+            assert!(waiter.join().unwrap() >= timeout, "gave up before the timeout");
+        }
+        drop(guard);
+    });
+    thread::scope(|s| for _ in 0..4 {
+        s.spawn(|| assert!(lock.acquire_timeout(Duration::from_secs(5)).is_ok()));
+    });
+}
+
+#[test]
+fn acquire_timeout_tas() { check_acquire_timeout(TASLock::new()); }
+
+#[test]
+fn acquire_timeout_array() { check_acquire_timeout(ArrayLock::new(5)); }
+
+#[test]
+fn acquire_timeout_clh() { check_acquire_timeout(CLHLock::new()); }
+
+#[test]
+fn acquire_timeout_backoff() { check_acquire_timeout(BackoffLock::new()); }
+
+// The owned guard goes to a thread that isn't scoped and is dropped
+// there. Until then it holds the lock, and keeps it alive after every
+// other Arc is gone.
+fn check_owned_guard_moves<L: TryLock + Send + 'static>(lock: L) where OwnedGuard<L>: Send {
+    let lock = Arc::new(lock);
+    let guard = lock.acquire_owned();
+    let (release, released) = mpsc::channel::<()>();
+    let holder = thread::spawn(move || {
+        assert!(guard.lock().try_acquire().is_none());
+        released.recv().unwrap();
+        let lock = guard.lock().clone();
+        drop(guard);
+        assert!(lock.try_acquire().is_some(), "dropping the guard didn't release");
+    });
+    assert!(lock.try_acquire().is_none());
+    let weak = Arc::downgrade(&lock);
+    drop(lock);
+    assert_eq!(weak.strong_count(), 1, "the guard let go of the lock");
+    release.send(()).unwrap();
+    holder.join().unwrap();
+    assert_eq!(weak.strong_count(), 0);
+}
+
+#[test]
+fn owned_guard_drops_on_another_thread() {
+    check_owned_guard_moves(TASLock::new());
+    check_owned_guard_moves(TTASLock::new());
+    check_owned_guard_moves(BackoffLock::new());
+    check_owned_guard_moves(ArrayLock::new(3));
+}
+
+// lets everyone in at once, so the suite had better notice
+struct NoopLock;
+
+impl Lock for NoopLock {
+    type Guard<'a> = ();
+    fn acquire(&self) -> Self::Guard<'_> {}
+}
+
+impl TryLock for NoopLock {
+    fn try_acquire(&self) -> Option<Self::Guard<'_>> { Some(()) }
+}
+
+#[test]
+#[should_panic]
+fn exclusion_catches_noop_lock() {
+    check_exclusion(&NoopLock, 4, 200);
+}
+
+#[test]
+#[should_panic]
+fn try_acquire_catches_noop_lock() {
+    check_try_acquire(&NoopLock, 4, 200);
+}
+
@@ -0,0 +1,73 @@
+use std::thread;
+
+use concurrent::listset::{CoarseListSet, ConcurrentSet, MutSet, SeqListSet};
+use concurrent::lock::TASLock;
+
+const NODES: u64 = 1_000_000;
+// far too small for one frame per node
+const STACK: usize = 64 * 1024;
+
+// Inserting in descending key order puts every node at the head, so
+// building the chain takes one step per element rather than a walk.
+fn descending_keys(key: impl Fn(&u64) -> u64) -> Vec<u64> {
+    let mut items: Vec<u64> = (0..NODES).collect();
+    items.sort_by_cached_key(|item| std::cmp::Reverse(key(item)));
+    items
+}
+
+fn long_seq() -> SeqListSet<u64> {
+    let mut set = SeqListSet::new();
+    for item in descending_keys(|item| set.make_key(item)) { assert!(set.add(item)); }
+    assert_eq!(set.len(), NODES as usize);
+    set
+}
+
+fn long_coarse() -> CoarseListSet<u64, TASLock> {
+    let set = CoarseListSet::new(TASLock::new());
+    for item in descending_keys(|item| set.make_key(item)) { ConcurrentSet::add(&set, item); }
+    assert_eq!(set.len(), NODES as usize);
+    set
+}
+
+fn on_small_stack(f: impl FnOnce() + Send + 'static) {
+    thread::Builder::new().stack_size(STACK).spawn(f)
+        .expect("couldn't spawn a thread").join()
+        .expect("ran out of stack");
+}
+
+#[test]
+fn seq_drop() {
+    let set = long_seq();
+    on_small_stack(move || drop(set));
+}
+
+#[test]
+fn seq_clear() {
+    let mut set = long_seq();
+    on_small_stack(move || {
+        set.clear();
+        assert!(set.is_empty());
+    });
+}
+
+// the iterator is dropped with nearly the whole chain left in it
+#[test]
+fn seq_into_iter_abandoned() {
+    let set = long_seq();
+    on_small_stack(move || {
+        let mut items = set.into_iter();
+        items.next();
+        drop(items);
+    });
+}
+
+#[test]
+fn coarse_clear_and_drop() {
+    let set = long_coarse();
+    on_small_stack(move || {
+        set.clear();
+        assert!(set.is_empty());
+    });
+    let set = long_coarse();
+    on_small_stack(move || drop(set));
+}
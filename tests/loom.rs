@@ -0,0 +1,153 @@
+#![cfg(loom)]
+
+// Model-checks the locks with loom, which reruns each scenario under every
+// interleaving of the locks' atomics, and every reordering the memory model
+// allows. Needs a loom build:
+//     RUSTFLAGS="--cfg loom" cargo test --release --test loom
+// LOOM_MAX_PREEMPTIONS bounds the search when a model takes too long.
+
+use loom::cell::UnsafeCell;
+use loom::sync::Arc;
+use loom::sync::atomic::{AtomicBool, Ordering};
+use loom::thread;
+
+use concurrent::lock::{ArrayLock, CLHLock, Lock, TASLock, TTASLock, TryLock};
+use concurrent::oneshot;
+
+// a loom cell, so two holders at once, or a release that doesn't publish
+// the holder's writes, shows up as a data race
+struct Counter(UnsafeCell<usize>);
+
+unsafe impl Sync for Counter {}
+
+fn check<L: Lock + Send + Sync + 'static>(
+    new: impl Fn() -> L + Send + Sync + 'static, threads: usize,
+    enter: fn(&L, &Counter) -> bool,
+) {
+    loom::model(move || {
+        let shared = Arc::new((new(), Counter(UnsafeCell::new(0))));
+        let handles: Vec<_> = (0..threads).map(|_| {
+            let shared = shared.clone();
+            thread::spawn(move || enter(&shared.0, &shared.1))
+        }).collect();
+        let entered: usize = handles.into_iter()
+            .map(|handle| handle.join().unwrap() as usize).sum();
+        assert_eq!(shared.1.0.with(|count| unsafe { *count }), entered);
+    });
+}
+
+fn acquire<L: Lock>(lock: &L, counter: &Counter) -> bool {
+    let _guard = lock.acquire();
+    counter.0.with_mut(|count| unsafe { *count += 1 });
+    true
+}
+
+fn try_acquire<L: TryLock>(lock: &L, counter: &Counter) -> bool {
+    let Some(_guard) = lock.try_acquire() else { return false };
+    counter.0.with_mut(|count| unsafe { *count += 1 });
+    true
+}
+
+// A waiter that reads the flag stale, loses the swap and goes back to
+// waiting never sees the release in loom, so no model of the flag locks'
+// acquire can finish. Their try_acquire takes and clears the same flag.
+#[test]
+fn tas_lock_try() { check(TASLock::new, 3, try_acquire); }
+
+#[test]
+fn ttas_lock_try() { check(TTASLock::new, 3, try_acquire); }
+
+#[test]
+fn array_lock() { check(|| ArrayLock::new(2), 2, acquire); }
+
+#[test]
+fn array_lock_try() { check(|| ArrayLock::new(2), 2, try_acquire); }
+
+#[test]
+fn clh_lock() { check(CLHLock::new, 2, acquire); }
+
+#[test]
+fn clh_lock_try() { check(CLHLock::new, 2, try_acquire); }
+
+// TASLock with its orderings weakened to Relaxed: nothing orders one
+// holder's writes before the next holder's, and loom has to notice
+struct RelaxedLock(AtomicBool);
+
+struct RelaxedGuard<'a>(&'a RelaxedLock);
+
+impl Lock for RelaxedLock {
+    type Guard<'a> = RelaxedGuard<'a>;
+    fn acquire(&self) -> Self::Guard<'_> {
+        loop { if let Some(guard) = self.try_acquire() { return guard; } }
+    }
+}
+
+impl TryLock for RelaxedLock {
+    fn try_acquire(&self) -> Option<Self::Guard<'_>> {
+        (!self.0.swap(true, Ordering::Relaxed)).then_some(RelaxedGuard(self))
+    }
+}
+
+impl Drop for RelaxedGuard<'_> {
+    fn drop(&mut self) { self.0.0.store(false, Ordering::Relaxed); }
+}
+
+#[test]
+#[should_panic(expected = "Causality violation")]
+fn relaxed_lock_races() { check(|| RelaxedLock(AtomicBool::new(false)), 2, try_acquire); }
+
+// A few bytes in a loom cell, written just before a handoff and read
+// after it. The checksum travels through the handoff itself, so a
+// receiver reading ahead of the writes is caught as a data race, and one
+// that somehow read the cell early would see the wrong sum.
+struct Payload(UnsafeCell<[u8; 4]>);
+
+unsafe impl Sync for Payload {}
+
+impl Payload {
+    fn write(&self, seed: u8) -> u32 {
+        let bytes = [seed, seed ^ 0x5a, seed.wrapping_mul(3), !seed];
+        self.0.with_mut(|payload| unsafe { *payload = bytes });
+        Self::sum(&bytes)
+    }
+    fn verify(&self, sum: u32) {
+        self.0.with(|payload| assert_eq!(Self::sum(unsafe { &*payload }), sum));
+    }
+    fn sum(bytes: &[u8; 4]) -> u32 {
+        bytes.iter().fold(17, |sum: u32, &byte| sum.wrapping_mul(31).wrapping_add(byte as u32))
+    }
+}
+
+#[test]
+fn oneshot_publishes_payload() {
+    loom::model(|| {
+        let payload = Arc::new(Payload(UnsafeCell::new([0; 4])));
+        let (sender, receiver) = oneshot::channel();
+        let writer = payload.clone();
+        let sending = thread::spawn(move || {
+            assert!(sender.send(writer.write(7)).is_ok());
+        });
+        payload.verify(receiver.recv().unwrap());
+        sending.join().unwrap();
+    });
+}
+
+// each holder checks the payload the last one bequeathed a sum for, then
+// writes its own
+#[test]
+fn clh_handoff_publishes_payload() {
+    loom::model(|| {
+        let shared = Arc::new((CLHLock::<u32>::with_handoff(), Payload(UnsafeCell::new([0; 4]))));
+        let threads: Vec<_> = (1..=2).map(|seed| {
+            let shared = shared.clone();
+            thread::spawn(move || {
+                let mut guard = shared.0.acquire();
+                if let Some(sum) = guard.take_inherited() { shared.1.verify(sum); }
+                guard.bequeath(shared.1.write(seed));
+            })
+        }).collect();
+        for thread in threads { thread.join().unwrap(); }
+        let sum = shared.0.acquire().take_inherited().expect("the last payload went missing");
+        shared.1.verify(sum);
+    });
+}
@@ -0,0 +1,55 @@
+use std::sync::Arc;
+use std::thread;
+
+use concurrent::lock::{ArrayLock, CLHLock, Lock, Mutex, TASLock, TTASLock};
+#[cfg(feature = "std")]
+use concurrent::lock::BackoffLock;
+
+const THREADS: usize = 4;
+const INCREMENTS: usize = 200;
+
+// the classic lost-update test: the increment is a read and a write, so
+// any overlap between two critical sections loses a count
+fn count_with<L: Lock + Send + 'static>(lock: L) {
+    let counter = Arc::new(Mutex::new(0, lock));
+    let handles: Vec<_> = (0..THREADS).map(|_| {
+        let counter = counter.clone();
+        thread::spawn(move || for _ in 0..INCREMENTS {
+            let mut guard = counter.lock();
+            let value = *guard;
+            thread::yield_now();
+            *guard = value + 1;
+        })
+    }).collect();
+    for handle in handles { handle.join().unwrap(); }
+    let counter = Arc::into_inner(counter).expect("a thread still holds the mutex");
+    assert_eq!(counter.into_inner(), THREADS * INCREMENTS);
+}
+
+#[test]
+fn tas_lock() { count_with(TASLock::new()); }
+
+#[test]
+fn ttas_lock() { count_with(TTASLock::new()); }
+
+#[cfg(feature = "std")]
+#[test]
+fn backoff_lock() { count_with(BackoffLock::new()); }
+
+#[test]
+fn array_lock() { count_with(ArrayLock::new(THREADS)); }
+
+#[cfg(not(loom))]
+#[test]
+fn static_array_lock() { count_with(concurrent::lock::StaticArrayLock::<THREADS>::new_static()); }
+
+#[test]
+fn clh_lock() { count_with(CLHLock::new()); }
+
+#[test]
+fn get_mut_and_into_inner() {
+    let mut mutex = Mutex::new(vec![1], TASLock::new());
+    mutex.get_mut().push(2);
+    mutex.lock().push(3);
+    assert_eq!(mutex.into_inner(), [1, 2, 3]);
+}
@@ -0,0 +1,87 @@
+use std::sync::Barrier;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use concurrent::atomic::OnceBox;
+
+const THREADS: usize = 16;
+
+// counts the values still alive, so a losing box that isn't dropped, or
+// the winner dropped too early, shows up
+struct Counted<'a> {
+    id: usize,
+    live: &'a AtomicUsize,
+}
+
+impl<'a> Counted<'a> {
+    fn new(id: usize, live: &'a AtomicUsize) -> Self {
+        live.fetch_add(1, Ordering::Relaxed);
+        Counted { id, live }
+    }
+}
+
+impl Drop for Counted<'_> {
+    fn drop(&mut self) { self.live.fetch_sub(1, Ordering::Relaxed); }
+}
+
+// every thread tries to initialize at once: they all get the same value,
+// and only the winner's initializer has a lasting effect
+#[test]
+fn get_or_init_race() {
+    let cell = OnceBox::new();
+    let live = AtomicUsize::new(0);
+    let effects = AtomicUsize::new(0);
+    let barrier = Barrier::new(THREADS);
+    let seen: Vec<_> = thread::scope(|s| {
+        let handles: Vec<_> = (0..THREADS).map(|id| {
+            let (cell, live, effects, barrier) = (&cell, &live, &effects, &barrier);
+            s.spawn(move || {
+                barrier.wait();
+                let value = cell.get_or_init(|| Counted::new(id, live));
+                // the effect an initializer would have, kept only if it won
+                if value.id == id { effects.fetch_add(1, Ordering::Relaxed); }
+                value as *const Counted as usize
+            })
+        }).collect();
+        handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+    });
+    assert!(seen.iter().all(|&ptr| ptr == seen[0]));
+    assert_eq!(effects.load(Ordering::Relaxed), 1);
+    assert_eq!(live.load(Ordering::Relaxed), 1);
+    drop(cell);
+    assert_eq!(live.load(Ordering::Relaxed), 0);
+}
+
+#[test]
+fn losing_boxes_are_dropped() {
+    let cell = OnceBox::new();
+    let live = AtomicUsize::new(0);
+    let barrier = Barrier::new(THREADS);
+    let rejected = thread::scope(|s| {
+        let handles: Vec<_> = (0..THREADS).map(|id| {
+            let (cell, live, barrier) = (&cell, &live, &barrier);
+            s.spawn(move || {
+                barrier.wait();
+                cell.set(Counted::new(id, live)).is_err()
+            })
+        }).collect();
+        handles.into_iter().map(|handle| handle.join().unwrap())
+            .filter(|&lost| lost).count()
+    });
+    assert_eq!(rejected, THREADS - 1);
+    assert_eq!(live.load(Ordering::Relaxed), 1);
+    assert!(cell.get().is_some());
+    let winner = cell.into_inner().expect("no value was set");
+    assert_eq!(live.load(Ordering::Relaxed), 1);
+    drop(winner);
+    assert_eq!(live.load(Ordering::Relaxed), 0);
+}
+
+#[test]
+fn get_or_init_runs_once_when_set() {
+    let cell = OnceBox::new();
+    assert!(cell.get().is_none());
+    assert_eq!(*cell.get_or_init(|| 1), 1);
+    assert_eq!(*cell.get_or_init(|| unreachable!()), 1);
+    assert_eq!(cell.set(2), Err(2));
+}
@@ -0,0 +1,47 @@
+#![cfg(feature = "std")]
+
+use std::thread;
+use std::time::Duration;
+
+use concurrent::error::{Closed, TryRecvError};
+use concurrent::oneshot;
+
+#[test]
+fn send_across_threads() {
+    let (sender, receiver) = oneshot::channel();
+    let handle = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(5));
+        sender.send(vec![String::from("a"), String::from("b")]).unwrap();
+    });
+    assert_eq!(receiver.recv(), Ok(vec![String::from("a"), String::from("b")]));
+    handle.join().unwrap();
+}
+
+#[test]
+fn drop_without_send() {
+    let (sender, receiver) = oneshot::channel::<String>();
+    let handle = thread::spawn(move || drop(sender));
+    assert_eq!(receiver.recv(), Err(Closed));
+    handle.join().unwrap();
+}
+
+#[test]
+fn try_recv_states() {
+    let (sender, mut receiver) = oneshot::channel();
+    assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+    sender.send(Box::new(7)).unwrap();
+    assert_eq!(receiver.try_recv(), Ok(Box::new(7)));
+    assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+}
+
+#[test]
+fn send_to_dropped_receiver() {
+    let (sender, receiver) = oneshot::channel();
+    drop(receiver);
+    assert_eq!(sender.send(String::from("lost")), Err(String::from("lost")));
+}
+
+#[test]
+fn compile_fail() {
+    trybuild::TestCases::new().compile_fail("tests/ui/oneshot_*.rs");
+}
@@ -0,0 +1,29 @@
+use std::mem::{align_of, size_of};
+
+use concurrent::lock::Flag;
+use concurrent::pad::CachePadded;
+
+#[cfg(target_arch = "aarch64")]
+const LINE: usize = 128;
+#[cfg(not(target_arch = "aarch64"))]
+const LINE: usize = 64;
+
+#[test]
+fn flag_fills_one_line() {
+    assert_eq!(align_of::<Flag>(), LINE);
+    assert_eq!(size_of::<Flag>(), LINE);
+}
+
+#[test]
+fn neighbouring_flags_on_separate_lines() {
+    let flags: [Flag; 2] = Default::default();
+    let (first, second) = (&flags[0] as *const Flag as usize, &flags[1] as *const Flag as usize);
+    assert_eq!(first % LINE, 0);
+    assert_eq!(second - first, LINE);
+}
+
+#[test]
+fn larger_values_round_up() {
+    assert_eq!(size_of::<CachePadded<[u8; LINE + 1]>>(), 2 * LINE);
+    assert_eq!(align_of::<CachePadded<u8>>(), LINE);
+}
@@ -0,0 +1,51 @@
+#![cfg(feature = "rayon")]
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+
+use concurrent::hashset::StripedHashSet;
+use concurrent::listset::{ConcurrentSet, Set};
+use concurrent::lock::TASLock;
+
+const STABLE: usize = 1000;
+const CHURN: usize = 1000;
+
+// elements below STABLE stay put; writers keep adding and removing the
+// ones above it, growing the table while the visits run
+#[test]
+fn par_for_each_visits_each_element_once() {
+    let set: StripedHashSet<usize, TASLock> = StripedHashSet::new(4);
+    for element in 0..STABLE { set.add(element); }
+    let visits: Vec<_> = (0..STABLE + CHURN).map(|_| AtomicUsize::new(0)).collect();
+    let done = AtomicBool::new(false);
+    thread::scope(|s| {
+        s.spawn(|| while !done.load(Ordering::Relaxed) {
+            for element in STABLE..STABLE + CHURN { set.add(element); }
+            for element in STABLE..STABLE + CHURN { set.remove(element); }
+        });
+        for _ in 0..20 {
+            set.par_for_each(|&element| { visits[element].fetch_add(1, Ordering::Relaxed); });
+            for (element, count) in visits.iter().enumerate() {
+                let count = count.swap(0, Ordering::Relaxed);
+                if element < STABLE {
+                    assert_eq!(count, 1, "element {}", element);
+                } else {
+                    assert!(count <= 1, "element {} visited {} times", element, count);
+                }
+            }
+        }
+        done.store(true, Ordering::Relaxed);
+    });
+}
+
+#[test]
+fn par_drain_filter_removes_matches() {
+    let set: StripedHashSet<usize, TASLock> = StripedHashSet::new(4);
+    for element in 0..STABLE { set.add(element); }
+    let mut drained = set.par_drain_filter(|element| element % 3 == 0);
+    drained.sort_unstable();
+    assert_eq!(drained, (0..STABLE).step_by(3).collect::<Vec<_>>());
+    assert_eq!(set.len(), STABLE - drained.len());
+    for element in 0..STABLE { assert_eq!(set.contains(element), element % 3 != 0); }
+    assert!(set.par_drain_filter(|element| element % 3 == 0).is_empty());
+}
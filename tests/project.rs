@@ -0,0 +1,34 @@
+use concurrent::lock::{Mutex, TASLock};
+use concurrent::project_guard;
+
+#[derive(Default)]
+struct Account {
+    balance: u64,
+    history: Vec<u64>,
+    name: String,
+}
+
+fn deposit(balance: &mut u64, history: &mut Vec<u64>, amount: u64) {
+    *balance += amount;
+    history.push(amount);
+}
+
+#[test]
+fn disjoint_fields() {
+    let mutex = Mutex::new(Account::default(), TASLock::new());
+    let mut guard = mutex.lock();
+    let (balance, history) = project_guard!(guard => balance, history);
+    deposit(balance, history, 5);
+    deposit(balance, history, 7);
+    guard.name.push_str("savings");
+    drop(guard);
+    let account = mutex.into_inner();
+    assert_eq!(account.balance, 12);
+    assert_eq!(account.history, [5, 7]);
+    assert_eq!(account.name, "savings");
+}
+
+#[test]
+fn compile_fail() {
+    trybuild::TestCases::new().compile_fail("tests/ui/project_*.rs");
+}
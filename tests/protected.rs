@@ -0,0 +1,59 @@
+#![cfg(feature = "std")]
+
+use std::panic::{self, AssertUnwindSafe};
+
+use concurrent::lock::{ArrayLock, CLHLock, Lock, TASLock};
+use concurrent::run_protected;
+
+const WORKERS: usize = 4;
+
+fn count_with<L: Lock>(lock_factory: impl Fn() -> L) {
+    let (total, results) = run_protected(0, lock_factory, WORKERS, |worker, counter| {
+        for _ in 0..100 { *counter.lock() += 1; }
+        worker * 10
+    });
+    assert_eq!(total, WORKERS * 100);
+    assert_eq!(results, [0, 10, 20, 30]);
+}
+
+#[test]
+fn tas_lock() { count_with(TASLock::new); }
+
+#[test]
+fn clh_lock() { count_with(CLHLock::new); }
+
+#[test]
+fn array_lock() { count_with(|| ArrayLock::new(WORKERS)); }
+
+#[test]
+fn final_data() {
+    let (mut log, _) = run_protected(Vec::new(), TASLock::new, WORKERS, |worker, log| {
+        log.lock().push(worker);
+    });
+    log.sort();
+    assert_eq!(log, [0, 1, 2, 3]);
+}
+
+#[test]
+fn first_panic_propagates() {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        run_protected(0, TASLock::new, WORKERS, |worker, counter| {
+            *counter.lock() += 1;
+            if worker % 2 == 1 { panic!("worker {} failed", worker); }
+        })
+    }));
+    let payload = result.expect_err("a worker panic was swallowed");
+    assert_eq!(payload.downcast_ref::<String>().map(String::as_str), Some("worker 1 failed"));
+}
+
+#[test]
+fn panicking_worker_releases_the_lock() {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        run_protected(0, CLHLock::new, WORKERS, |worker, counter| {
+            let mut guard = counter.lock();
+            *guard += 1;
+            if worker == 0 { panic!("holding the lock"); }
+        })
+    }));
+    assert!(result.is_err());
+}
@@ -0,0 +1,131 @@
+#![cfg(feature = "std")]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use concurrent::lock::{CLHLock, TASLock, TTASLock};
+#[cfg(target_pointer_width = "64")]
+use concurrent::queue::MsQueue;
+use concurrent::queue::TwoLockQueue;
+
+const PRODUCERS: usize = 2;
+const CONSUMERS: usize = 2;
+const ITEMS: usize = 2000;
+
+// Each consumer must see any one producer's items in order, and between
+// them the consumers must get every item exactly once.
+fn check_drained(taken: Vec<Vec<(usize, usize)>>) {
+    let mut received = vec![Vec::new(); PRODUCERS];
+    for consumer in taken {
+        let mut last = [None; PRODUCERS];
+        for (producer, seq) in consumer {
+            assert!(last[producer] < Some(seq), "producer {}'s items out of order", producer);
+            last[producer] = Some(seq);
+            received[producer].push(seq);
+        }
+    }
+    for (producer, mut seqs) in received.into_iter().enumerate() {
+        seqs.sort_unstable();
+        let len = seqs.len();
+        seqs.dedup();
+        assert_eq!(seqs.len(), len, "producer {}'s items came out twice", producer);
+        assert_eq!(seqs, (0..ITEMS).collect::<Vec<_>>(), "producer {}'s items lost", producer);
+    }
+}
+
+#[cfg(target_pointer_width = "64")]
+#[test]
+fn ms_queue_mpmc() {
+    let queue = MsQueue::new();
+    let remaining = AtomicUsize::new(PRODUCERS * ITEMS);
+    let taken = thread::scope(|s| {
+        for producer in 0..PRODUCERS {
+            let queue = &queue;
+            s.spawn(move || for seq in 0..ITEMS { queue.enqueue((producer, seq)); });
+        }
+        let consumers: Vec<_> = (0..CONSUMERS).map(|_| s.spawn(|| {
+            let mut taken = Vec::new();
+            // keep going through empty spells until everything is out
+            while remaining.load(Ordering::Relaxed) > 0 {
+                match queue.dequeue() {
+                    Some(item) => {
+                        remaining.fetch_sub(1, Ordering::Relaxed);
+                        taken.push(item);
+                    },
+                    None => thread::yield_now(),
+                }
+            }
+            taken
+        })).collect();
+        consumers.into_iter().map(|consumer| consumer.join().unwrap()).collect()
+    });
+    check_drained(taken);
+    assert!(queue.is_empty());
+}
+
+// a capacity well below the number of items keeps both sides blocking
+#[test]
+fn two_lock_spsc() {
+    let queue = TwoLockQueue::new(4, TTASLock::new(), TTASLock::new());
+    thread::scope(|s| {
+        s.spawn(|| for item in 0..ITEMS { queue.push(item); });
+        for expected in 0..ITEMS { assert_eq!(queue.pop(), expected); }
+    });
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn two_lock_mpmc() {
+    let queue = TwoLockQueue::new(4, CLHLock::new(), CLHLock::new());
+    let taken = thread::scope(|s| {
+        for producer in 0..PRODUCERS {
+            let queue = &queue;
+            s.spawn(move || for seq in 0..ITEMS { queue.push((producer, seq)); });
+        }
+        let consumers: Vec<_> = (0..CONSUMERS).map(|_| s.spawn(|| {
+            (0..PRODUCERS * ITEMS / CONSUMERS).map(|_| queue.pop()).collect()
+        })).collect();
+        consumers.into_iter().map(|consumer| consumer.join().unwrap()).collect()
+    });
+    check_drained(taken);
+    assert!(queue.is_empty());
+}
+
+fn wait_for(what: &str, condition: impl Fn() -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !condition() {
+        assert!(Instant::now() < deadline, "timed out waiting for {}", what);
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+// Two producers block on a full queue of one. Each pop makes room for one
+// of them: it has to be woken, since nothing else will retry its push, and
+// the other has to stay blocked.
+#[test]
+fn pop_wakes_one_blocked_producer() {
+    let queue = TwoLockQueue::new(1, TASLock::new(), TASLock::new());
+    let pushed = AtomicUsize::new(0);
+    queue.push(0);
+    thread::scope(|s| {
+        for item in 1..=2 {
+            let (queue, pushed) = (&queue, &pushed);
+            s.spawn(move || {
+                queue.push(item);
+                pushed.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(pushed.load(Ordering::SeqCst), 0, "pushed into a full queue");
+        for popped in 1..=2 {
+            queue.pop();
+            wait_for("a blocked producer", || pushed.load(Ordering::SeqCst) == popped);
+            thread::sleep(Duration::from_millis(20));
+            assert_eq!(pushed.load(Ordering::SeqCst), popped, "one pop let two pushes in");
+            assert_eq!(queue.len(), 1);
+        }
+    });
+    queue.pop();
+    assert!(queue.is_empty());
+}
@@ -0,0 +1,99 @@
+#![cfg(feature = "std")]
+
+use std::sync::Barrier;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use concurrent::quiescence::Quiescence;
+
+const THREADS: usize = 3;
+const RETIRED: usize = 100;
+
+// counts its own drops
+struct Garbage(&'static AtomicUsize);
+
+impl Drop for Garbage {
+    fn drop(&mut self) { self.0.fetch_add(1, Ordering::SeqCst); }
+}
+
+// A reader that is inside its read section when something is retired
+// keeps it alive, however often collect runs, until it passes a quiescent
+// point of its own.
+#[test]
+fn reader_holds_back_collection() {
+    static DROPPED: AtomicUsize = AtomicUsize::new(0);
+    let quiescence = Quiescence::new();
+    let barrier = Barrier::new(2);
+    thread::scope(|s| {
+        s.spawn(|| {
+            let participant = quiescence.register();
+            participant.quiescent();
+            // reading
+            barrier.wait();
+            barrier.wait();
+            participant.quiescent();
+            barrier.wait();
+        });
+        barrier.wait();
+        quiescence.retire(Box::new(Garbage(&DROPPED)));
+        for _ in 0..10 {
+            assert_eq!(quiescence.collect(), 0);
+            thread::yield_now();
+        }
+        assert_eq!(DROPPED.load(Ordering::SeqCst), 0);
+        assert_eq!(quiescence.pending(), 1);
+        barrier.wait();
+        barrier.wait();
+        assert_eq!(quiescence.collect(), 1);
+        assert_eq!(DROPPED.load(Ordering::SeqCst), 1);
+    });
+}
+
+#[test]
+fn all_quiescent_frees_everything() {
+    static DROPPED: AtomicUsize = AtomicUsize::new(0);
+    let quiescence = Quiescence::new();
+    let (registered, retired) = (Barrier::new(THREADS + 1), Barrier::new(THREADS + 1));
+    thread::scope(|s| {
+        for _ in 0..THREADS {
+            s.spawn(|| {
+                let participant = quiescence.register();
+                registered.wait();
+                retired.wait();
+                participant.quiescent();
+            });
+        }
+        registered.wait();
+        for _ in 0..RETIRED { quiescence.retire(Box::new(Garbage(&DROPPED))); }
+        assert_eq!(quiescence.collect(), 0);
+        retired.wait();
+    });
+    // the participants are gone by now, but each passed a quiescent point first
+    assert_eq!(quiescence.collect(), RETIRED);
+    assert_eq!(DROPPED.load(Ordering::SeqCst), RETIRED);
+    assert_eq!(quiescence.pending(), 0);
+}
+
+// Threads that never registered, or have since dropped their participant,
+// are not waited for. Whatever is left over goes with the Quiescence.
+#[test]
+fn only_live_participants_block() {
+    static DROPPED: AtomicUsize = AtomicUsize::new(0);
+    let quiescence = Quiescence::new();
+    thread::scope(|s| {
+        s.spawn(|| quiescence.retire(Box::new(Garbage(&DROPPED))));
+    });
+    assert_eq!(quiescence.collect(), 1);
+    let participant = quiescence.register();
+    quiescence.retire(Box::new(Garbage(&DROPPED)));
+    assert_eq!(quiescence.collect(), 0);
+    drop(participant);
+    assert_eq!(quiescence.collect(), 1);
+    let stuck = quiescence.register();
+    for _ in 0..RETIRED { quiescence.retire(Box::new(Garbage(&DROPPED))); }
+    assert_eq!(quiescence.collect(), 0);
+    assert_eq!(DROPPED.load(Ordering::SeqCst), 2);
+    drop(stuck);
+    drop(quiescence);
+    assert_eq!(DROPPED.load(Ordering::SeqCst), RETIRED + 2);
+}
@@ -0,0 +1,191 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Barrier, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use concurrent::lock::{RwLock, RwPolicy, RwSpinLock};
+
+const POLICIES: [RwPolicy; 3] = [RwPolicy::ReaderPref, RwPolicy::WriterPref, RwPolicy::PhaseFair];
+
+// Two readers get in together and stay in until told to leave; a writer
+// arriving meanwhile has to wait for both, and nobody reads while it writes.
+#[test]
+fn readers_share_writer_excludes() {
+    for policy in POLICIES { check_readers_share_writer_excludes(RwSpinLock::with_policy(policy)); }
+}
+
+fn check_readers_share_writer_excludes(lock: RwSpinLock) {
+    let (both_in, leave) = (Barrier::new(3), Barrier::new(3));
+    let (writing, written) = (AtomicBool::new(false), AtomicBool::new(false));
+    thread::scope(|s| {
+        for _ in 0..2 {
+            s.spawn(|| {
+                let _guard = lock.acquire_read();
+                both_in.wait();
+                leave.wait();
+                assert!(!writing.load(Ordering::SeqCst), "read while writing");
+            });
+        }
+        both_in.wait();
+        assert_eq!(lock.readers(), 2);
+        s.spawn(|| {
+            let _guard = lock.acquire_write();
+            writing.store(true, Ordering::SeqCst);
+            assert_eq!(lock.readers(), 0);
+            thread::sleep(Duration::from_millis(5));
+            writing.store(false, Ordering::SeqCst);
+            written.store(true, Ordering::SeqCst);
+        });
+        thread::sleep(Duration::from_millis(20));
+        assert!(!written.load(Ordering::SeqCst), "writer got past two readers");
+        leave.wait();
+    });
+    assert!(written.load(Ordering::SeqCst));
+    drop(lock.acquire_read());
+}
+
+// once the writer is in, a reader waits for it to finish
+#[test]
+fn writer_excludes_readers() {
+    let lock = RwSpinLock::new();
+    let writing = AtomicBool::new(true);
+    let guard = lock.acquire_write();
+    thread::scope(|s| {
+        s.spawn(|| {
+            let _guard = lock.acquire_read();
+            assert!(!writing.load(Ordering::SeqCst), "read while writing");
+        });
+        thread::sleep(Duration::from_millis(20));
+        writing.store(false, Ordering::SeqCst);
+        drop(guard);
+    });
+}
+
+#[test]
+fn default_policy_prefers_writers() {
+    assert_eq!(RwSpinLock::new().policy(), RwPolicy::WriterPref);
+}
+
+// nobody reads while a writer writes, whatever the policy
+#[test]
+fn policies_exclude_writers() {
+    for policy in POLICIES {
+        let lock = RwSpinLock::with_policy(policy);
+        let writing = AtomicBool::new(false);
+        thread::scope(|s| {
+            for _ in 0..2 {
+                s.spawn(|| for _ in 0..50 {
+                    let _guard = lock.acquire_write();
+                    assert!(!writing.swap(true, Ordering::SeqCst), "{:?}: two writers", policy);
+                    thread::yield_now();
+                    writing.store(false, Ordering::SeqCst);
+                });
+                s.spawn(|| for _ in 0..50 {
+                    let _guard = lock.acquire_read();
+                    assert!(!writing.load(Ordering::SeqCst), "{:?}: read while writing", policy);
+                    thread::yield_now();
+                });
+            }
+        });
+        assert_eq!(lock.readers(), 0);
+    }
+}
+
+// runs the threads one after another, giving each time to block, then lets
+// go of held and returns the order the threads got in
+fn entry_order(
+    lock: &RwSpinLock, held: impl Sized, arrivals: &[(&'static str, bool)],
+) -> Vec<&'static str> {
+    let order = Mutex::new(Vec::new());
+    thread::scope(|s| {
+        for &(name, write) in arrivals {
+            let order = &order;
+            s.spawn(move || if write {
+                let _guard = lock.acquire_write();
+                order.lock().unwrap().push(name);
+            } else {
+                let _guard = lock.acquire_read();
+                order.lock().unwrap().push(name);
+            });
+            thread::sleep(Duration::from_millis(20));
+        }
+        drop(held);
+    });
+    order.into_inner().unwrap()
+}
+
+// a reader arrives while a writer waits for the reader already inside
+fn reader_behind_waiting_writer(policy: RwPolicy) -> Vec<&'static str> {
+    let lock = RwSpinLock::with_policy(policy);
+    entry_order(&lock, lock.acquire_read(), &[("writer", true), ("reader", false)])
+}
+
+#[test]
+fn reader_pref_lets_readers_past_writers() {
+    assert_eq!(reader_behind_waiting_writer(RwPolicy::ReaderPref), ["reader", "writer"]);
+}
+
+#[test]
+fn writer_pref_holds_readers_back() {
+    assert_eq!(reader_behind_waiting_writer(RwPolicy::WriterPref), ["writer", "reader"]);
+}
+
+#[test]
+fn phase_fair_holds_readers_back() {
+    assert_eq!(reader_behind_waiting_writer(RwPolicy::PhaseFair), ["writer", "reader"]);
+}
+
+// while one writer holds the lock, a reader arrives and then another writer
+fn reader_between_writers(policy: RwPolicy) -> Vec<&'static str> {
+    let lock = RwSpinLock::with_policy(policy);
+    entry_order(&lock, lock.acquire_write(), &[("reader", false), ("writer", true)])
+}
+
+#[test]
+fn writer_pref_lets_writers_past_readers() {
+    assert_eq!(reader_between_writers(RwPolicy::WriterPref), ["writer", "reader"]);
+}
+
+// the reader waited on the first writer's phase, so it goes before the
+// second writer
+#[test]
+fn phase_fair_admits_readers_between_writers() {
+    assert_eq!(reader_between_writers(RwPolicy::PhaseFair), ["reader", "writer"]);
+}
+
+// Readers keep the lock busy with overlapping reads; each write still gets
+// in once the readers inside when it arrived have left. ReaderPref gives no
+// such bound, as a writer there needs a moment with no readers at all.
+fn writer_wait_under_reader_flood(policy: RwPolicy) -> Duration {
+    let lock = RwSpinLock::with_policy(policy);
+    let done = AtomicBool::new(false);
+    thread::scope(|s| {
+        for _ in 0..3 {
+            s.spawn(|| while !done.load(Ordering::Relaxed) {
+                let _guard = lock.acquire_read();
+                thread::yield_now();
+            });
+        }
+        let mut longest = Duration::ZERO;
+        for _ in 0..20 {
+            let start = Instant::now();
+            drop(lock.acquire_write());
+            longest = longest.max(start.elapsed());
+            thread::yield_now();
+        }
+        done.store(true, Ordering::Relaxed);
+        longest
+    })
+}
+
+#[test]
+fn writer_pref_bounds_writer_wait() {
+    let longest = writer_wait_under_reader_flood(RwPolicy::WriterPref);
+    assert!(longest < Duration::from_secs(1), "a writer waited {:?}", longest);
+}
+
+#[test]
+fn phase_fair_bounds_writer_wait() {
+    let longest = writer_wait_under_reader_flood(RwPolicy::PhaseFair);
+    assert!(longest < Duration::from_secs(1), "a writer waited {:?}", longest);
+}
@@ -0,0 +1,55 @@
+#![cfg(feature = "testing")]
+
+use std::thread;
+
+use concurrent::listset::{ConcurrentSet, Set, SkipListSet};
+use concurrent::lock::TASLock;
+use rand::random;
+
+const HEIGHT: usize = 8;
+const KEYS: u64 = 500;
+
+#[test]
+fn invariants_hold_through_churn() {
+    let set = SkipListSet::new(TASLock::new(), HEIGHT);
+    for _ in 0..5_000 {
+        let key = random::<u64>() % KEYS;
+        if random() { set.add(key); } else { set.remove(key); }
+    }
+    set.check_invariants();
+    assert_eq!(set.level_lens()[0], set.len());
+}
+
+#[test]
+fn invariants_hold_after_concurrent_churn() {
+    let set = SkipListSet::new(TASLock::new(), HEIGHT);
+    thread::scope(|s| for thread in 0..4 {
+        let set = &set;
+        s.spawn(move || for key in (thread..KEYS).step_by(4) {
+            assert!(set.add(key));
+            if key % 3 == 0 { assert!(set.remove(key)); }
+        });
+    });
+    set.check_invariants();
+    for key in 0..KEYS { assert_eq!(set.contains(key), key % 3 != 0); }
+}
+
+// the heights come from the seed alone, so the same seed and the same
+// operations build the same shape
+#[test]
+fn seed_fixes_the_shape() {
+    let build = || {
+        let set = SkipListSet::with_seed(TASLock::new(), HEIGHT, 42);
+        for key in 0..KEYS { set.add(key); }
+        for key in (0..KEYS).step_by(7) { set.remove(key); }
+        set
+    };
+    let (first, second) = (build(), build());
+    first.check_invariants();
+    let lens = first.level_lens();
+    assert_eq!(lens, second.level_lens());
+    assert_eq!(lens.len(), HEIGHT);
+    // roughly half the nodes reach each next level up
+    assert!(lens.windows(2).all(|pair| pair[0] >= pair[1]));
+    assert!(lens[1] > 0 && lens[1] < lens[0]);
+}
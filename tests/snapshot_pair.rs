@@ -0,0 +1,41 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+use concurrent::atomic::SnapshotPair;
+
+const THREADS: usize = 3;
+const ROUNDS: usize = 500;
+
+// Each recorder removes only what it added, so at every moment the removes
+// trail the adds by at most one per recorder. Reading adds and then
+// removes separately can see more removes than adds; a snapshot never
+// does, and neither count goes backwards from one snapshot to the next.
+#[test]
+fn snapshots_are_consistent_pairs() {
+    let pair = SnapshotPair::new();
+    let done = AtomicBool::new(false);
+    thread::scope(|s| {
+        let recorders: Vec<_> = (0..THREADS).map(|_| s.spawn(|| for _ in 0..ROUNDS {
+            pair.record_add();
+            thread::yield_now();
+            pair.record_remove();
+        })).collect();
+        for _ in 0..2 {
+            s.spawn(|| {
+                let mut last = (0, 0);
+                while !done.load(Ordering::Relaxed) {
+                    let (adds, removes) = pair.snapshot();
+                    assert!(adds >= removes, "{} removes but only {} adds", removes, adds);
+                    assert!(adds - removes <= THREADS, "{} in flight", adds - removes);
+                    assert!(adds >= last.0 && removes >= last.1, "went back from {:?}", last);
+                    assert!(pair.len() <= THREADS);
+                    last = (adds, removes);
+                }
+            });
+        }
+        for recorder in recorders { recorder.join().unwrap(); }
+        done.store(true, Ordering::Relaxed);
+    });
+    assert_eq!(pair.snapshot(), (THREADS * ROUNDS, THREADS * ROUNDS));
+    assert!(pair.is_empty());
+}
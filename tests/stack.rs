@@ -0,0 +1,79 @@
+#![cfg(all(feature = "std", target_pointer_width = "64"))]
+
+use std::collections::HashSet;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use concurrent::stack::{EliminationStack, Exchanger};
+
+#[test]
+fn timeout_returns_the_value() {
+    let exchanger = Exchanger::new();
+    let start = Instant::now();
+    let result = exchanger.exchange(String::from("mine"), Duration::from_millis(5));
+    assert_eq!(result, Err("mine".into()));
+    assert!(start.elapsed() >= Duration::from_millis(5));
+    // the slot was emptied on the way out
+    assert_eq!(exchanger.exchange(String::from("again"), Duration::ZERO), Err("again".into()));
+}
+
+#[test]
+fn partners_swap() {
+    let exchanger = Exchanger::new();
+    let timeout = Duration::from_secs(5);
+    thread::scope(|s| {
+        let other = s.spawn(|| exchanger.exchange(String::from("b"), timeout));
+        assert_eq!(exchanger.exchange(String::from("a"), timeout), Ok("b".into()));
+        assert_eq!(other.join().unwrap(), Ok("a".into()));
+    });
+}
+
+// With timeouts about as long as the gap between the two arrivals, a
+// withdrawal keeps racing a partner claiming the offer. Either both
+// threads swap or both get their own value back; nothing is lost or
+// handed out twice.
+#[test]
+fn timeout_racing_partner() {
+    let exchanger = Exchanger::new();
+    for round in 0..500 {
+        let timeout = Duration::from_micros(round % 50);
+        let (a, b) = thread::scope(|s| {
+            let other = s.spawn(|| exchanger.exchange(vec![1], timeout));
+            let mine = exchanger.exchange(vec![0], timeout);
+            (mine, other.join().unwrap())
+        });
+        match (a, b) {
+            (Ok(theirs), Ok(mine)) => assert_eq!((theirs, mine), (vec![1], vec![0])),
+            (Err(mine), Err(theirs)) => assert_eq!((mine, theirs), (vec![0], vec![1])),
+            results => panic!("one-sided exchange: {:?}", results),
+        }
+    }
+}
+
+// one slot and a short timeout, so pushes and pops keep meeting in the
+// exchanger as well as on the stack
+#[test]
+fn elimination_under_contention() {
+    const THREADS: usize = 4;
+    const VALUES: usize = 2000;
+    let stack = EliminationStack::new(1).timeout(Duration::from_micros(20));
+    let popped: Vec<Vec<usize>> = thread::scope(|s| {
+        let handles: Vec<_> = (0..THREADS).map(|thread| {
+            let stack = &stack;
+            s.spawn(move || {
+                let mut popped = Vec::new();
+                for value in (thread..VALUES).step_by(THREADS) {
+                    stack.push(value);
+                    if value % 3 != 0 { popped.extend(stack.pop()); }
+                }
+                popped
+            })
+        }).collect();
+        handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+    });
+    let mut seen = HashSet::new();
+    for value in popped.into_iter().flatten().chain(std::iter::from_fn(|| stack.pop())) {
+        assert!(seen.insert(value), "{} popped twice", value);
+    }
+    assert_eq!(seen.len(), VALUES);
+}
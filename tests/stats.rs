@@ -0,0 +1,159 @@
+#![cfg(feature = "stats")]
+
+use std::thread;
+use std::time::Duration;
+
+use concurrent::metrics::EventKind;
+use concurrent::lock::{
+    CLHLock, Instrumented, Lock, LockStats, StatsSnapshot, TASLock, TryLock, WAIT_BUCKETS,
+};
+
+const THREADS: u64 = 4;
+const ACQUIRES: u64 = 100;
+
+fn hammer<L: TryLock + Sync>(lock: &Instrumented<L>) -> LockStats {
+    thread::scope(|s| for _ in 0..THREADS {
+        s.spawn(|| for _ in 0..ACQUIRES {
+            let _guard = lock.acquire();
+            thread::yield_now();
+        });
+    });
+    lock.stats()
+}
+
+fn check_counts<L: TryLock + Sync>(lock: L) {
+    let stats = hammer(&Instrumented::new(lock));
+    assert_eq!(stats.acquisitions, THREADS * ACQUIRES);
+    assert!(stats.contended <= stats.acquisitions);
+    assert!(stats.max_wait <= stats.total_wait);
+    assert!(stats.max_hold <= stats.total_hold);
+}
+
+#[test]
+fn tas_counts() { check_counts(TASLock::new()); }
+
+#[test]
+fn clh_counts() { check_counts(CLHLock::new()); }
+
+#[test]
+fn contended_and_hold() {
+    let lock = Instrumented::new(TASLock::new());
+    let guard = lock.acquire();
+    assert!(lock.try_acquire().is_none());
+    thread::scope(|s| {
+        s.spawn(|| drop(lock.acquire()));
+        thread::sleep(Duration::from_millis(10));
+        drop(guard);
+    });
+    let stats = lock.stats();
+    assert_eq!(stats.acquisitions, 2);
+    assert_eq!(stats.contended, 1);
+    assert!(stats.max_hold >= Duration::from_millis(10));
+    // the waiter may have started a little after the holder went to sleep
+    assert!(stats.max_wait >= Duration::from_millis(5));
+    lock.reset_stats();
+    assert_eq!(lock.stats(), LockStats::default());
+}
+
+#[test]
+fn events_follow_the_lock() {
+    assert!(Instrumented::new(TASLock::new()).events().is_none());
+    let lock = Instrumented::new(TASLock::new()).with_events(16);
+    let guard = lock.acquire();
+    assert!(lock.try_acquire().is_none());
+    assert!(lock.acquire_timeout(Duration::from_millis(1)).is_err());
+    drop(guard);
+    drop(lock.try_acquire().expect("free lock not acquired"));
+    let kinds: Vec<_> = lock.events().unwrap().dump().iter().map(|event| event.kind).collect();
+    assert_eq!(kinds, [
+        EventKind::AcquireStart,
+        EventKind::AcquireComplete,
+        EventKind::TryFail,
+        EventKind::AcquireStart,
+        EventKind::Timeout,
+        EventKind::Release,
+        EventKind::AcquireComplete,
+        EventKind::Release,
+    ]);
+    // the successful acquires count, the failed ones don't
+    assert_eq!(lock.stats().acquisitions, 2);
+}
+
+// Resets run the whole time the threads are acquiring; adding up the
+// intervals has to give back every acquisition, and each interval's
+// histogram has to account for exactly its own acquisitions.
+#[test]
+fn resets_lose_nothing() {
+    let lock = Instrumented::new(TASLock::new());
+    let mut total = StatsSnapshot::default();
+    let mut intervals = 0;
+    thread::scope(|s| {
+        let workers: Vec<_> = (0..THREADS).map(|_| s.spawn(|| for _ in 0..ACQUIRES {
+            let _guard = lock.acquire();
+            thread::yield_now();
+        })).collect();
+        while !workers.iter().all(|worker| worker.is_finished()) {
+            let interval = lock.snapshot_and_reset();
+            assert_eq!(interval.wait_histogram.iter().sum::<u64>(), interval.stats.acquisitions);
+            total = total.merge(interval);
+            intervals += 1;
+            thread::yield_now();
+        }
+    });
+    total = total.merge(lock.snapshot_and_reset());
+    assert!(intervals > 1, "only {} intervals", intervals);
+    assert_eq!(total.stats.acquisitions, THREADS * ACQUIRES);
+    assert_eq!(total.wait_histogram.iter().sum::<u64>(), THREADS * ACQUIRES);
+    assert!(total.stats.contended <= total.stats.acquisitions);
+    assert_eq!(lock.snapshot(), StatsSnapshot::default());
+}
+
+#[test]
+fn snapshot_leaves_counts() {
+    let lock = Instrumented::new(TASLock::new());
+    for _ in 0..3 { drop(lock.acquire()); }
+    let snapshot = lock.snapshot();
+    assert_eq!(snapshot.stats, lock.stats());
+    assert_eq!(snapshot.stats.acquisitions, 3);
+    assert_eq!(snapshot.wait_histogram.iter().sum::<u64>(), 3);
+    assert_eq!(lock.snapshot_and_reset(), snapshot);
+    assert_eq!(lock.snapshot(), StatsSnapshot::default());
+}
+
+#[test]
+fn long_waits_land_in_the_last_bucket() {
+    let lock = Instrumented::new(TASLock::new());
+    let guard = lock.acquire();
+    thread::scope(|s| {
+        s.spawn(|| drop(lock.acquire()));
+        // 2^14 microseconds is about 16ms
+        thread::sleep(Duration::from_millis(40));
+        drop(guard);
+    });
+    let histogram = lock.snapshot().wait_histogram;
+    assert_eq!(histogram[WAIT_BUCKETS - 1], 1);
+    assert_eq!(histogram.iter().sum::<u64>(), 2);
+}
+
+#[test]
+fn display_lists_counters() {
+    let lock = Instrumented::new(TASLock::new());
+    drop(lock.acquire());
+    let text = lock.snapshot().to_string();
+    let lines: Vec<_> = text.lines().collect();
+    assert_eq!(lines[0], "acquisitions 1");
+    assert_eq!(lines[1], "contended 0");
+    assert!(lines[6].starts_with("wait_histogram_us "));
+    assert_eq!(lines[6].split(' ').count(), WAIT_BUCKETS + 1);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_round_trip() {
+    let lock = Instrumented::new(CLHLock::new());
+    let stats = hammer(&lock);
+    let snapshot = lock.snapshot();
+    assert_eq!(snapshot.stats, stats);
+    let json = serde_json::to_string(&snapshot).unwrap();
+    assert_eq!(serde_json::from_str::<StatsSnapshot>(&json).unwrap(), snapshot);
+}
@@ -0,0 +1,69 @@
+#![cfg(all(feature = "trace-atomics", not(loom)))]
+
+use std::sync::atomic::Ordering;
+use std::thread;
+
+use concurrent::lock::{Lock, TASLock};
+use concurrent::trace::{format_timeline, take_events, Event};
+
+const ACQUIRES: usize = 20;
+
+// Two threads take turns on a TASLock. Merged by sequence number, the
+// swaps that got the lock and the stores that released it alternate, one
+// pair per acquisition, all on the same atomic. The crate has no Peterson
+// lock, so TAS stands in for the two-thread case.
+#[test]
+fn tas_timeline() {
+    let lock = TASLock::new();
+    let mut events: Vec<Event> = thread::scope(|s| {
+        let threads: Vec<_> = (0..2).map(|_| s.spawn(|| {
+            for _ in 0..ACQUIRES {
+                drop(lock.acquire());
+                thread::yield_now();
+            }
+            take_events()
+        })).collect();
+        threads.into_iter().flat_map(|thread| thread.join().unwrap()).collect()
+    });
+    events.sort_by_key(|event| event.seq);
+    let flag = events[0].addr;
+    assert!(events.iter().all(|event| event.addr == flag));
+    let mut held_by = None;
+    let mut acquisitions = 0;
+    for event in &events {
+        match (event.op, event.ordering, event.value) {
+            ("swap", Ordering::Acquire, 0) => {
+                assert_eq!(held_by, None, "acquired a held lock at {}", event.seq);
+                held_by = Some(event.thread);
+                acquisitions += 1;
+            },
+            // a swap that lost to the other thread; it may be numbered
+            // ahead of the winning swap, so its place says nothing
+            ("swap", Ordering::Acquire, 1) => {},
+            ("store", Ordering::Release, 0) => {
+                assert_eq!(held_by, Some(event.thread), "released by a non-holder at {}", event.seq);
+                held_by = None;
+            },
+            _ => panic!("unexpected event {:?}", event),
+        }
+    }
+    assert_eq!(acquisitions, 2 * ACQUIRES);
+    assert_eq!(held_by, None);
+    let timeline = format_timeline(events.clone());
+    assert_eq!(timeline.lines().count(), events.len());
+    assert!(timeline.contains(" swap a0 Acquire 0"));
+    assert!(timeline.contains(" store a0 Release 0"));
+}
+
+// a thread only ever sees its own events, once
+#[test]
+fn take_events_drains() {
+    let lock = TASLock::new();
+    take_events();
+    drop(lock.acquire());
+    let events = take_events();
+    assert_eq!(events.len(), 2);
+    assert!(events[0].seq < events[1].seq);
+    assert!(take_events().is_empty());
+    thread::spawn(|| assert!(take_events().is_empty())).join().unwrap();
+}
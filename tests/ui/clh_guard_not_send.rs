@@ -0,0 +1,12 @@
+use std::rc::Rc;
+
+use concurrent::lock::{CLHGuard, CLHSendGuard};
+
+fn assert_send<T: Send>() {}
+
+fn main() {
+    // only into_send_guard makes a guard that can move
+    assert_send::<CLHGuard<'static, ()>>();
+    // and only when what it hands on can move too
+    assert_send::<CLHSendGuard<'static, Rc<u32>>>();
+}
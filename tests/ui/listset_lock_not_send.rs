@@ -0,0 +1,21 @@
+use std::marker::PhantomData;
+use std::sync::MutexGuard;
+
+use concurrent::listset::{FineListSet, LazyListSet};
+use concurrent::lock::{Lock, TASGuard, TASLock};
+
+// shared freely, but pinned to the thread that made it
+struct Pinned(TASLock, PhantomData<MutexGuard<'static, ()>>);
+
+impl Lock for Pinned {
+    type Guard<'a> = TASGuard<'a>;
+    fn acquire(&self) -> Self::Guard<'_> { self.0.acquire() }
+}
+
+fn assert_sync<T: Sync>() {}
+
+fn main() {
+    // threads sharing the set make and drop its nodes' locks
+    assert_sync::<FineListSet<u32, Pinned>>();
+    assert_sync::<LazyListSet<u32, Pinned>>();
+}
@@ -0,0 +1,10 @@
+use std::rc::Rc;
+
+use concurrent::oneshot::{Receiver, Sender};
+
+fn assert_send<T: Send>() {}
+
+fn main() {
+    assert_send::<Sender<Rc<u32>>>();
+    assert_send::<Receiver<Rc<u32>>>();
+}
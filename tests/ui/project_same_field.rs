@@ -0,0 +1,12 @@
+use concurrent::lock::{Mutex, TASLock};
+use concurrent::project_guard;
+
+struct Pair { a: u32, b: u32 }
+
+fn main() {
+    let mutex = Mutex::new(Pair { a: 0, b: 0 }, TASLock::new());
+    let mut guard = mutex.lock();
+    let (a, b, again) = project_guard!(guard => a, b, a);
+    *a += *b;
+    *again += 1;
+}